@@ -0,0 +1,82 @@
+/*
+ * A C api for hrekt, a really fast http prober.
+ *
+ *   const char *args[] = {"--title", "--tech-detect"};
+ *   const char *targets[] = {"example.com", "10.0.0.1:8443"};
+ *   hrekt_scanner *scanner = hrekt_new(args, 2);
+ *   if (hrekt_submit(scanner, targets, 2) != 0)
+ *       fprintf(stderr, "%s\n", hrekt_last_error());
+ *
+ *   char *result;
+ *   int status;
+ *   while ((status = hrekt_poll(scanner, 1000, &result)) != HREKT_DONE) {
+ *       if (status == HREKT_RESULT) {
+ *           puts(result);
+ *           hrekt_string_free(result);
+ *       }
+ *   }
+ *   hrekt_free(scanner);
+ *
+ * A scanner is used from one thread at a time, every string is utf-8 and
+ * nul-terminated. A panic in the library doesn't unwind into the caller, the
+ * call fails as it does on an error and hrekt_last_error says why.
+ */
+
+#ifndef HREKT_H
+#define HREKT_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* what hrekt_poll returns */
+#define HREKT_ERROR -1
+#define HREKT_PENDING 0
+#define HREKT_RESULT 1
+#define HREKT_DONE 2
+
+typedef struct HrektScanner hrekt_scanner;
+
+/*
+ * Create a scanner with the options of the command line, without the name of
+ * the binary, checked on hrekt_submit. args points to args_len strings, or
+ * is NULL when args_len is 0. NULL when an option isn't utf-8 or the runtime
+ * can't be started.
+ */
+hrekt_scanner *hrekt_new(const char *const *args, size_t args_len);
+
+/*
+ * Start probing targets_len targets in any form the input accepts, their
+ * results come out of hrekt_poll along with those of the targets submitted
+ * before. 0 once started, -1 on wrong options or targets.
+ */
+int hrekt_submit(hrekt_scanner *scanner, const char *const *targets, size_t targets_len);
+
+/*
+ * Wait up to timeout_ms for the next result. HREKT_RESULT sets *result to the
+ * result as a line of the json output, to free with hrekt_string_free,
+ * HREKT_PENDING means none came in time, and HREKT_DONE that every scan
+ * submitted is over, or that none was.
+ */
+int hrekt_poll(hrekt_scanner *scanner, uint64_t timeout_ms, char **result);
+
+/*
+ * Why the last call on this thread failed, owned by the library and valid
+ * until the next call that fails. NULL when none did.
+ */
+const char *hrekt_last_error(void);
+
+/* Free a result returned by hrekt_poll, NULL is ignored. */
+void hrekt_string_free(char *string);
+
+/* Stop the scans still running and free the scanner, NULL is ignored. */
+void hrekt_free(hrekt_scanner *scanner);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
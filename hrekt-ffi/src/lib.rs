@@ -0,0 +1,336 @@
+// what every pointer has to be is documented in include/hrekt.h
+#![allow(clippy::missing_safety_doc)]
+
+use futures::{stream::SelectAll, Stream, StreamExt};
+use hrekt::{ProbeResult, Prober};
+use std::{
+    any::Any,
+    cell::RefCell,
+    ffi::{c_char, c_int, CStr, CString},
+    panic::{self, AssertUnwindSafe},
+    pin::Pin,
+    ptr, slice,
+    time::Duration,
+};
+use tokio::runtime::Runtime;
+
+// what hrekt_poll returns
+const HREKT_ERROR: c_int = -1;
+const HREKT_PENDING: c_int = 0;
+const HREKT_RESULT: c_int = 1;
+const HREKT_DONE: c_int = 2;
+
+thread_local! {
+    // why the last call on this thread failed
+    static LAST_ERROR: RefCell<Option<CString>> = RefCell::new(None);
+}
+
+/**
+ * The scans submitted through one handle, and the runtime they run on
+ */
+pub struct HrektScanner {
+    runtime: Runtime,
+    args: Vec<String>,
+    results: SelectAll<Pin<Box<dyn Stream<Item = ProbeResult> + Send>>>,
+}
+
+fn set_error(error: impl ToString) {
+    let error = CString::new(error.to_string().replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(error));
+}
+
+/**
+ * Run the body of an entry point, a panic is turned into the value the
+ * entry point fails with and the last error rather than unwinding into C
+ */
+fn guard<T>(failed: T, body: impl FnOnce() -> T) -> T {
+    match panic::catch_unwind(AssertUnwindSafe(body)) {
+        Ok(value) => value,
+        Err(panic) => {
+            set_error(format!("hrekt panicked: {}", panic_message(&*panic)));
+            failed
+        }
+    }
+}
+
+fn panic_message(panic: &(dyn Any + Send)) -> &str {
+    if let Some(message) = panic.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = panic.downcast_ref::<String>() {
+        message
+    } else {
+        "no message"
+    }
+}
+
+/**
+ * Read an array of C strings, None when one of them is null or not utf-8
+ */
+unsafe fn read_strings(strings: *const *const c_char, len: usize) -> Option<Vec<String>> {
+    if len == 0 {
+        return Some(vec![]);
+    }
+    if strings.is_null() {
+        return None;
+    }
+    let mut read = vec![];
+    for &string in slice::from_raw_parts(strings, len) {
+        if string.is_null() {
+            return None;
+        }
+        read.push(CStr::from_ptr(string).to_str().ok()?.to_string());
+    }
+    Some(read)
+}
+
+/**
+ * Create a scanner with the options of the command line, without the name
+ * of the binary, checked on hrekt_submit. Null when an option isn't utf-8
+ * or the runtime can't be started
+ */
+#[no_mangle]
+pub unsafe extern "C" fn hrekt_new(
+    args: *const *const c_char,
+    args_len: usize,
+) -> *mut HrektScanner {
+    guard(ptr::null_mut(), || new(args, args_len))
+}
+
+unsafe fn new(args: *const *const c_char, args_len: usize) -> *mut HrektScanner {
+    let args = match read_strings(args, args_len) {
+        Some(args) => args,
+        None => {
+            set_error("the options have to be utf-8 strings");
+            return ptr::null_mut();
+        }
+    };
+    let runtime = match Runtime::new() {
+        Ok(runtime) => runtime,
+        Err(err) => {
+            set_error(err);
+            return ptr::null_mut();
+        }
+    };
+    Box::into_raw(Box::new(HrektScanner {
+        runtime,
+        args,
+        results: SelectAll::new(),
+    }))
+}
+
+/**
+ * Start probing the targets, their results come out of hrekt_poll along with
+ * those of the targets submitted before. 0 once started, -1 on wrong options
+ * or targets
+ */
+#[no_mangle]
+pub unsafe extern "C" fn hrekt_submit(
+    scanner: *mut HrektScanner,
+    targets: *const *const c_char,
+    targets_len: usize,
+) -> c_int {
+    guard(HREKT_ERROR, || submit(scanner, targets, targets_len))
+}
+
+unsafe fn submit(
+    scanner: *mut HrektScanner,
+    targets: *const *const c_char,
+    targets_len: usize,
+) -> c_int {
+    let scanner = match scanner.as_mut() {
+        Some(scanner) => scanner,
+        None => {
+            set_error("the scanner is null");
+            return HREKT_ERROR;
+        }
+    };
+    let targets = match read_strings(targets, targets_len) {
+        Some(targets) => targets,
+        None => {
+            set_error("the targets have to be utf-8 strings");
+            return HREKT_ERROR;
+        }
+    };
+    let args = &scanner.args;
+    let started = scanner.runtime.block_on(async {
+        let prober = Prober::from_args(args, targets).await?;
+        Ok::<_, Box<dyn std::error::Error + Send + Sync>>(prober.run())
+    });
+    match started {
+        Ok(results) => {
+            scanner.results.push(Box::pin(results));
+            0
+        }
+        Err(err) => {
+            set_error(err);
+            HREKT_ERROR
+        }
+    }
+}
+
+/**
+ * Wait up to the timeout for the next result. HREKT_RESULT sets the result
+ * to its json, to free with hrekt_string_free, HREKT_PENDING means none came
+ * in time and HREKT_DONE that every scan submitted is over
+ */
+#[no_mangle]
+pub unsafe extern "C" fn hrekt_poll(
+    scanner: *mut HrektScanner,
+    timeout_ms: u64,
+    result: *mut *mut c_char,
+) -> c_int {
+    guard(HREKT_ERROR, || poll(scanner, timeout_ms, result))
+}
+
+unsafe fn poll(scanner: *mut HrektScanner, timeout_ms: u64, result: *mut *mut c_char) -> c_int {
+    let scanner = match scanner.as_mut() {
+        Some(scanner) => scanner,
+        None => {
+            set_error("the scanner is null");
+            return HREKT_ERROR;
+        }
+    };
+    if result.is_null() {
+        set_error("the result is null");
+        return HREKT_ERROR;
+    }
+    *result = ptr::null_mut();
+
+    let results = &mut scanner.results;
+    let next = scanner.runtime.block_on(tokio::time::timeout(
+        Duration::from_millis(timeout_ms),
+        results.next(),
+    ));
+    match next {
+        Ok(Some(probed)) => match serde_json::to_string(&probed.record()) {
+            Ok(json) => {
+                *result = CString::new(json).unwrap_or_default().into_raw();
+                HREKT_RESULT
+            }
+            Err(err) => {
+                set_error(err);
+                HREKT_ERROR
+            }
+        },
+        Ok(None) => HREKT_DONE,
+        Err(_) => HREKT_PENDING,
+    }
+}
+
+/**
+ * Why the last call on this thread failed, owned by the library and valid
+ * until the next call that fails
+ */
+#[no_mangle]
+pub extern "C" fn hrekt_last_error() -> *const c_char {
+    guard(ptr::null(), || {
+        LAST_ERROR.with(|last| match &*last.borrow() {
+            Some(error) => error.as_ptr(),
+            None => ptr::null(),
+        })
+    })
+}
+
+/**
+ * Free a result returned by hrekt_poll
+ */
+#[no_mangle]
+pub unsafe extern "C" fn hrekt_string_free(string: *mut c_char) {
+    guard((), || {
+        if !string.is_null() {
+            drop(CString::from_raw(string));
+        }
+    })
+}
+
+/**
+ * Stop the scans still running and free the scanner
+ */
+#[no_mangle]
+pub unsafe extern "C" fn hrekt_free(scanner: *mut HrektScanner) {
+    guard((), || {
+        if !scanner.is_null() {
+            drop(Box::from_raw(scanner));
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn last_error() -> String {
+        let error = hrekt_last_error();
+        assert!(!error.is_null());
+        unsafe { CStr::from_ptr(error) }
+            .to_str()
+            .unwrap()
+            .to_string()
+    }
+
+    fn scanner(args: &[&str]) -> *mut HrektScanner {
+        let args = args
+            .iter()
+            .map(|arg| CString::new(*arg).unwrap())
+            .collect::<Vec<CString>>();
+        let pointers = args.iter().map(|arg| arg.as_ptr()).collect::<Vec<_>>();
+        let scanner = unsafe { hrekt_new(pointers.as_ptr(), pointers.len()) };
+        assert!(!scanner.is_null());
+        scanner
+    }
+
+    unsafe fn submit(scanner: *mut HrektScanner, targets: &[&str]) -> c_int {
+        let targets = targets
+            .iter()
+            .map(|target| CString::new(*target).unwrap())
+            .collect::<Vec<CString>>();
+        let pointers = targets
+            .iter()
+            .map(|target| target.as_ptr())
+            .collect::<Vec<_>>();
+        hrekt_submit(scanner, pointers.as_ptr(), pointers.len())
+    }
+
+    #[test]
+    fn turns_invalid_options_into_an_error() {
+        for args in [
+            &["--silent", "--rate", "abc"][..],
+            &["--silent", "--rate", "0"],
+            &["--silent", "--concurrency", "-1"],
+            &["--silent", "--ports", "80,http"],
+            &["--no-such-option"],
+        ] {
+            let scanner = scanner(args);
+            unsafe {
+                assert_eq!(submit(scanner, &["example.com"]), HREKT_ERROR);
+                assert!(!last_error().is_empty());
+
+                // nothing was submitted, so nothing is left to poll
+                let mut result = ptr::null_mut();
+                assert_eq!(hrekt_poll(scanner, 0, &mut result), HREKT_DONE);
+                assert!(result.is_null());
+                hrekt_free(scanner);
+            }
+        }
+    }
+
+    #[test]
+    fn turns_null_pointers_into_an_error() {
+        unsafe {
+            assert_eq!(hrekt_submit(ptr::null_mut(), ptr::null(), 0), HREKT_ERROR);
+            assert_eq!(last_error(), "the scanner is null");
+
+            let scanner = scanner(&[]);
+            assert_eq!(hrekt_poll(scanner, 0, ptr::null_mut()), HREKT_ERROR);
+            assert_eq!(last_error(), "the result is null");
+            hrekt_free(scanner);
+        }
+    }
+
+    #[test]
+    fn turns_a_panic_into_an_error() {
+        let failed = guard(HREKT_ERROR, || panic!("bad input"));
+        assert_eq!(failed, HREKT_ERROR);
+        assert_eq!(last_error(), "hrekt panicked: bad input");
+    }
+}
@@ -0,0 +1,114 @@
+use futures::{Stream, StreamExt};
+use hrekt::{ProbeResult, Prober, ProberConfig};
+use pyo3::{
+    exceptions::{PyRuntimeError, PyValueError},
+    prelude::*,
+    types::{PyBool, PyDict, PyList, PyTuple},
+};
+use std::pin::Pin;
+use tokio::runtime::Runtime;
+
+/**
+ * The results of a scan as a python iterator, the scan runs on a runtime of
+ * its own and stops once the iterator is dropped
+ */
+#[pyclass]
+struct Results {
+    runtime: Runtime,
+    results: Pin<Box<dyn Stream<Item = ProbeResult> + Send>>,
+}
+
+#[pymethods]
+impl Results {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(mut slf: PyRefMut<'_, Self>, py: Python<'_>) -> PyResult<Option<PyObject>> {
+        // let the other python threads run while waiting on the scan
+        let Results { runtime, results } = &mut *slf;
+        let result = py.allow_threads(|| runtime.block_on(results.next()));
+        match result {
+            Some(result) => Ok(Some(to_dict(py, &result)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+/**
+ * A result as a dict of the fields the json output writes
+ */
+fn to_dict(py: Python<'_>, result: &ProbeResult) -> PyResult<PyObject> {
+    let json = serde_json::to_string(&result.record())
+        .map_err(|err| PyRuntimeError::new_err(err.to_string()))?;
+    Ok(py.import("json")?.call_method1("loads", (json,))?.into())
+}
+
+/**
+ * The keyword options as the command line takes them, title=True for
+ * --title, rate=500 for --rate 500, a list repeating the option, and False
+ * or None leaving it out
+ */
+fn option_args(options: Option<&PyDict>) -> PyResult<Vec<(String, Option<String>)>> {
+    let mut args = vec![];
+    for (name, value) in options.into_iter().flatten() {
+        let option = format!("--{}", name.extract::<String>()?.replace('_', "-"));
+        if value.is_none() {
+            continue;
+        }
+        if let Ok(set) = value.downcast::<PyBool>() {
+            if set.is_true() {
+                args.push((option, None));
+            }
+        } else if value.is_instance_of::<PyList>() || value.is_instance_of::<PyTuple>() {
+            for item in value.iter()? {
+                args.push((option.clone(), Some(item?.str()?.to_string())));
+            }
+        } else {
+            args.push((option, Some(value.str()?.to_string())));
+        }
+    }
+    Ok(args)
+}
+
+/**
+ * Probe the targets with the options of the command line given as keywords,
+ * yielding each result as a dict as soon as it's reported
+ *
+ *   for result in hrekt.probe(["example.com"], title=True, ports="80,443,8443"):
+ *       print(result["url"], result["status_code"])
+ */
+#[pyfunction]
+#[pyo3(signature = (targets, **options))]
+fn probe(targets: Vec<String>, options: Option<&PyDict>) -> PyResult<Results> {
+    let mut builder = ProberConfig::builder();
+    for (option, value) in option_args(options)? {
+        builder = builder.arg(&option, value.as_deref());
+    }
+    let config = builder
+        .build()
+        .map_err(|err| PyValueError::new_err(err.to_string()))?;
+
+    let runtime = Runtime::new()?;
+    let results = runtime
+        .block_on(async {
+            let prober = Prober::new(&config, targets).await?;
+            Ok::<_, Box<dyn std::error::Error + Send + Sync>>(prober.run())
+        })
+        .map_err(|err| PyValueError::new_err(err.to_string()))?;
+    Ok(Results {
+        runtime,
+        results: Box::pin(results),
+    })
+}
+
+/**
+ * The hrekt prober, as a python module
+ */
+#[pymodule]
+#[pyo3(name = "hrekt")]
+fn pyhrekt(_py: Python<'_>, module: &PyModule) -> PyResult<()> {
+    module.add_function(wrap_pyfunction!(probe, module)?)?;
+    module.add_class::<Results>()?;
+    Ok(())
+}
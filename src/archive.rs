@@ -0,0 +1,493 @@
+use chrono::Utc;
+use reqwest::{
+    header::{HeaderMap, HeaderValue, ACCEPT, ACCEPT_ENCODING, HOST, RANGE},
+    Request, Response, Version,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    sync::Mutex,
+};
+
+// the start of every archive
+const MAGIC: &[u8; 4] = b"HRA1";
+
+// the end of every archive, after the offset and the length of the index
+const INDEX_MAGIC: &[u8; 4] = b"HRAI";
+
+// the offset of the index, its length and INDEX_MAGIC
+const TRAILER_LEN: usize = 8 + 4 + 4;
+
+// the most of each body kept, the rest is cut off
+const MAX_BODY: usize = 1024 * 1024;
+
+/**
+ * Records every probed request and its response in one compressed file that
+ * can be read back without the scan, laid out as
+ *
+ *   HRA1
+ *   for each exchange: its length as a u32 and a zstd frame of a msgpack map
+ *   the index: a zstd frame of a msgpack array of {url, offset, length}
+ *   the offset of the index as a u64, its length as a u32 and HRAI
+ *
+ * every number little endian, so a reader finds the index from the end of
+ * the file and seeks to any exchange without reading the others. Each
+ * exchange is flushed as it's written, so the archive of a scan killed
+ * before the index can still be read from the start
+ */
+pub struct Archive {
+    out: Mutex<Writer>,
+    // the headers the client sends with every request
+    client_headers: HeaderMap,
+    // whether the client asks for compressed bodies itself
+    decode: bool,
+}
+
+struct Writer {
+    file: BufWriter<File>,
+    offset: u64,
+    index: Vec<IndexEntry>,
+    finished: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+struct IndexEntry {
+    url: String,
+    offset: u64,
+    length: u32,
+}
+
+/**
+ * A request as it went out, kept on its response until the response is
+ * archived
+ */
+#[derive(Clone, Debug)]
+pub struct Sent {
+    method: String,
+    url: String,
+    headers: HeaderMap,
+}
+
+/**
+ * A request and the response it got, as the prober saw them, taken before
+ * the body is read
+ */
+pub struct Exchange {
+    sent: Sent,
+    final_url: String,
+    status_code: u16,
+    version: Version,
+    headers: HeaderMap,
+}
+
+impl Exchange {
+    /**
+     * The exchange of a response, None unless it was sent with --archive
+     */
+    pub fn of(resp: &Response) -> Option<Exchange> {
+        let sent = resp.extensions().get::<Sent>()?.clone();
+        Some(Exchange {
+            sent,
+            final_url: resp.url().to_string(),
+            status_code: resp.status().as_u16(),
+            version: resp.version(),
+            headers: resp.headers().clone(),
+        })
+    }
+}
+
+#[derive(Serialize)]
+struct Entry<'a> {
+    timestamp: String,
+    request: RequestEntry<'a>,
+    response: ResponseEntry<'a>,
+}
+
+#[derive(Serialize)]
+struct RequestEntry<'a> {
+    method: &'a str,
+    url: &'a str,
+    headers: Vec<(String, String)>,
+}
+
+#[derive(Serialize)]
+struct ResponseEntry<'a> {
+    url: &'a str,
+    status: u16,
+    version: String,
+    headers: Vec<(String, String)>,
+    body: Option<&'a str>,
+    body_truncated: bool,
+}
+
+/**
+ * An exchange read back from an archive
+ */
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ArchivedExchange {
+    pub timestamp: String,
+    pub request: ArchivedRequest,
+    pub response: ArchivedResponse,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ArchivedRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+}
+
+/**
+ * A response read back, its body None when the prober never read it
+ */
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ArchivedResponse {
+    pub url: String,
+    pub status: u16,
+    pub version: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Option<String>,
+    pub body_truncated: bool,
+}
+
+/**
+ * The headers in the order they came, repeated ones included
+ */
+fn header_pairs(headers: &HeaderMap) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = String::from_utf8_lossy(value.as_bytes()).to_string();
+            (name.as_str().to_string(), value)
+        })
+        .collect()
+}
+
+/**
+ * The body cut to the limit, on a character boundary
+ */
+fn bounded(body: &str) -> (&str, bool) {
+    if body.len() <= MAX_BODY {
+        return (body, false);
+    }
+    let mut end = MAX_BODY;
+    while !body.is_char_boundary(end) {
+        end -= 1;
+    }
+    (&body[..end], true)
+}
+
+fn invalid(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+impl Archive {
+    /**
+     * Create the archive of a scan whose client sends these headers with
+     * every request, and asks for compressed bodies when decode is set
+     */
+    pub fn create(path: &str, client_headers: HeaderMap, decode: bool) -> io::Result<Archive> {
+        let mut file = BufWriter::new(File::create(path)?);
+        file.write_all(MAGIC)?;
+        file.flush()?;
+        Ok(Archive {
+            out: Mutex::new(Writer {
+                file,
+                offset: MAGIC.len() as u64,
+                index: vec![],
+                finished: false,
+            }),
+            client_headers,
+            decode,
+        })
+    }
+
+    /**
+     * The request as it goes out: the headers of the client under the ones
+     * of the request, with the Host, Accept and Accept-Encoding the http
+     * client adds itself
+     */
+    pub fn sent(&self, request: &Request) -> Sent {
+        let url = request.url();
+        let mut headers = HeaderMap::new();
+        if let Some(host) = url.host_str() {
+            let host = match url.port() {
+                Some(port) => format!("{}:{}", host, port),
+                None => host.to_string(),
+            };
+            if let Ok(host) = HeaderValue::from_str(&host) {
+                headers.insert(HOST, host);
+            }
+        }
+        for (name, value) in &self.client_headers {
+            if !request.headers().contains_key(name) {
+                headers.append(name.clone(), value.clone());
+            }
+        }
+        for (name, value) in request.headers() {
+            headers.append(name.clone(), value.clone());
+        }
+        if !headers.contains_key(ACCEPT) {
+            headers.insert(ACCEPT, HeaderValue::from_static("*/*"));
+        }
+        if self.decode && !headers.contains_key(ACCEPT_ENCODING) && !headers.contains_key(RANGE) {
+            headers.insert(
+                ACCEPT_ENCODING,
+                HeaderValue::from_static("gzip, br, deflate"),
+            );
+        }
+        Sent {
+            method: request.method().to_string(),
+            url: url.to_string(),
+            headers,
+        }
+    }
+
+    /**
+     * Append an exchange with its body when it was read, compressed before
+     * the file is locked so the workers don't wait on each other's
+     * compression
+     */
+    pub fn record(&self, exchange: &Exchange, body: Option<&str>) -> io::Result<()> {
+        let (body, body_truncated) = match body.map(bounded) {
+            Some((body, truncated)) => (Some(body), truncated),
+            None => (None, false),
+        };
+        let entry = Entry {
+            timestamp: Utc::now().to_rfc3339(),
+            request: RequestEntry {
+                method: &exchange.sent.method,
+                url: &exchange.sent.url,
+                headers: header_pairs(&exchange.sent.headers),
+            },
+            response: ResponseEntry {
+                url: &exchange.final_url,
+                status: exchange.status_code,
+                version: format!("{:?}", exchange.version),
+                headers: header_pairs(&exchange.headers),
+                body,
+                body_truncated,
+            },
+        };
+        let encoded = rmp_serde::to_vec_named(&entry).map_err(|err| invalid(err.to_string()))?;
+        let compressed = zstd::encode_all(&encoded[..], 0)?;
+
+        let mut out = self.out.lock().unwrap();
+        if out.finished {
+            return Ok(());
+        }
+        let length = compressed.len() as u32;
+        out.file.write_all(&length.to_le_bytes())?;
+        out.file.write_all(&compressed)?;
+        out.file.flush()?;
+        let offset = out.offset;
+        out.index.push(IndexEntry {
+            url: exchange.sent.url.clone(),
+            offset,
+            length,
+        });
+        out.offset += 4 + compressed.len() as u64;
+        Ok(())
+    }
+
+    /**
+     * Write the index and the trailer, letting a reader seek to any exchange
+     */
+    pub fn finish(&self) -> io::Result<()> {
+        let mut out = self.out.lock().unwrap();
+        if out.finished {
+            return Ok(());
+        }
+        out.finished = true;
+        let encoded =
+            rmp_serde::to_vec_named(&out.index).map_err(|err| invalid(err.to_string()))?;
+        let compressed = zstd::encode_all(&encoded[..], 0)?;
+        let offset = out.offset;
+        out.file.write_all(&compressed)?;
+        out.file.write_all(&offset.to_le_bytes())?;
+        out.file
+            .write_all(&(compressed.len() as u32).to_le_bytes())?;
+        out.file.write_all(INDEX_MAGIC)?;
+        out.file.flush()
+    }
+}
+
+/**
+ * Where the exchanges of an archive end, at the index when the scan wrote
+ * one and at the end of the file otherwise
+ */
+fn exchanges_end(data: &[u8]) -> usize {
+    if data.len() < MAGIC.len() + TRAILER_LEN || !data.ends_with(INDEX_MAGIC) {
+        return data.len();
+    }
+    let trailer = &data[data.len() - TRAILER_LEN..];
+    let offset = u64::from_le_bytes(trailer[..8].try_into().unwrap()) as usize;
+    let length = u32::from_le_bytes(trailer[8..12].try_into().unwrap()) as usize;
+    if offset.checked_add(length) == Some(data.len() - TRAILER_LEN) {
+        offset
+    } else {
+        data.len()
+    }
+}
+
+/**
+ * Read every exchange of an archive back in the order they were written. An
+ * archive left without its index by a killed scan is read up to its last
+ * whole exchange
+ */
+pub fn read_archive(path: &str) -> io::Result<Vec<ArchivedExchange>> {
+    let data = fs::read(path)?;
+    if !data.starts_with(MAGIC) {
+        return Err(invalid(format!("{} isn't an archive", path)));
+    }
+    let end = exchanges_end(&data);
+    let mut exchanges = vec![];
+    let mut offset = MAGIC.len();
+    while offset + 4 <= end {
+        let length = u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap()) as usize;
+        let start = offset + 4;
+        // the exchange the scan was writing when it was killed
+        if start + length > end {
+            break;
+        }
+        let encoded = zstd::decode_all(&data[start..start + length])
+            .map_err(|err| invalid(format!("the exchange at {}: {}", offset, err)))?;
+        let exchange = rmp_serde::from_slice::<ArchivedExchange>(&encoded)
+            .map_err(|err| invalid(format!("the exchange at {}: {}", offset, err)))?;
+        exchanges.push(exchange);
+        offset = start + length;
+    }
+    Ok(exchanges)
+}
+
+/**
+ * Write the exchanges of an archive as json lines, to a file or stdout
+ */
+pub fn dump_archive(path: &str, output: Option<&str>) -> io::Result<()> {
+    let exchanges = read_archive(path)?;
+    let out: Box<dyn Write> = match output {
+        Some(output) => Box::new(File::create(output)?),
+        None => Box::new(io::stdout()),
+    };
+    let mut out = BufWriter::new(out);
+    for exchange in exchanges {
+        let json = serde_json::to_string(&exchange)?;
+        out.write_all(json.as_bytes())?;
+        out.write_all(b"\n")?;
+    }
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::{header::AUTHORIZATION, Method, Url};
+    use std::path::PathBuf;
+
+    fn archive_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("hrekt-{}-{}.hra", name, std::process::id()))
+    }
+
+    fn exchange(archive: &Archive, method: Method, url: &str, status_code: u16) -> Exchange {
+        let mut request = Request::new(method, Url::parse(url).unwrap());
+        request
+            .headers_mut()
+            .insert(AUTHORIZATION, HeaderValue::from_static("Digest abc"));
+        let mut headers = HeaderMap::new();
+        headers.insert("server", HeaderValue::from_static("test/1.0"));
+        Exchange {
+            sent: archive.sent(&request),
+            final_url: url.to_string(),
+            status_code,
+            version: Version::HTTP_11,
+            headers,
+        }
+    }
+
+    fn write(path: &PathBuf, finish: bool) -> Archive {
+        let mut client_headers = HeaderMap::new();
+        client_headers.insert("user-agent", HeaderValue::from_static("hrekt-test"));
+        let archive = Archive::create(path.to_str().unwrap(), client_headers, true).unwrap();
+        let page = exchange(&archive, Method::GET, "http://example.com:8080/admin", 200);
+        archive.record(&page, Some("<title>Admin</title>")).unwrap();
+        let head = exchange(&archive, Method::OPTIONS, "https://example.com/", 204);
+        archive.record(&head, None).unwrap();
+        if finish {
+            archive.finish().unwrap();
+        }
+        archive
+    }
+
+    #[test]
+    fn reads_back_what_it_wrote() {
+        let path = archive_path("round-trip");
+        write(&path, true);
+        let exchanges = read_archive(path.to_str().unwrap()).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(exchanges.len(), 2);
+        let page = &exchanges[0];
+        assert_eq!(page.request.method, "GET");
+        assert_eq!(page.request.url, "http://example.com:8080/admin");
+        assert_eq!(
+            page.request.headers,
+            [
+                ("host", "example.com:8080"),
+                ("user-agent", "hrekt-test"),
+                ("authorization", "Digest abc"),
+                ("accept", "*/*"),
+                ("accept-encoding", "gzip, br, deflate"),
+            ]
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+        );
+        assert_eq!(page.response.status, 200);
+        assert_eq!(page.response.version, "HTTP/1.1");
+        assert_eq!(page.response.body.as_deref(), Some("<title>Admin</title>"));
+        assert!(!page.response.body_truncated);
+
+        let head = &exchanges[1];
+        assert_eq!(head.request.method, "OPTIONS");
+        assert_eq!(
+            head.request.headers[0],
+            ("host".to_string(), "example.com".to_string())
+        );
+        assert_eq!(head.response.status, 204);
+        assert_eq!(head.response.body, None);
+    }
+
+    #[test]
+    fn reads_an_archive_without_its_index() {
+        let path = archive_path("unfinished");
+        let archive = write(&path, false);
+        let exchanges = read_archive(path.to_str().unwrap()).unwrap();
+        drop(archive);
+
+        // the exchange being written when the scan was killed is left out
+        let data = fs::read(&path).unwrap();
+        fs::write(&path, &data[..data.len() - 3]).unwrap();
+        let cut = read_archive(path.to_str().unwrap()).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(exchanges.len(), 2);
+        assert_eq!(cut.len(), 1);
+        assert_eq!(cut[0], exchanges[0]);
+    }
+
+    #[test]
+    fn cuts_a_huge_body() {
+        let (body, truncated) = bounded(&"é".repeat(MAX_BODY));
+        assert!(truncated);
+        assert!(body.len() <= MAX_BODY);
+        assert_eq!(bounded("short"), ("short", false));
+    }
+
+    #[test]
+    fn rejects_what_isnt_an_archive() {
+        let path = archive_path("not-an-archive");
+        fs::write(&path, b"{\"url\":\"http://example.com\"}\n").unwrap();
+        let read = read_archive(path.to_str().unwrap());
+        fs::remove_file(&path).unwrap();
+        assert!(read.is_err());
+    }
+}
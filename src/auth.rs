@@ -0,0 +1,617 @@
+use crate::{fetch_method, try_fetch_method, Shared};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use hmac::{Hmac, Mac};
+use md4::Md4;
+use md5::Md5;
+use rand::Rng;
+use reqwest::{
+    header::{HeaderMap, HeaderValue, AUTHORIZATION, WWW_AUTHENTICATE},
+    Method, Url,
+};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/**
+ * The credentials to answer the http digest challenges with
+ */
+#[derive(Clone, Debug)]
+pub struct DigestAuth {
+    user: String,
+    pass: String,
+}
+
+/**
+ * Split the parameters of a challenge, the quoted values may hold commas
+ */
+fn challenge_params(params: &str) -> HashMap<String, String> {
+    let mut parsed = HashMap::new();
+    let mut rest = params.trim();
+    while !rest.is_empty() {
+        let (key, after) = match rest.split_once('=') {
+            Some(pair) => pair,
+            None => break,
+        };
+        let key = key.trim().trim_start_matches(',').trim().to_lowercase();
+        let after = after.trim_start();
+        let (value, after) = if let Some(quoted) = after.strip_prefix('"') {
+            match quoted.find('"') {
+                Some(end) => (&quoted[..end], &quoted[end + 1..]),
+                None => (quoted, ""),
+            }
+        } else {
+            match after.find(',') {
+                Some(end) => (&after[..end], &after[end..]),
+                None => (after, ""),
+            }
+        };
+        parsed.insert(key, value.trim().to_string());
+        rest = after.trim_start().trim_start_matches(',').trim_start();
+    }
+    parsed
+}
+
+/**
+ * Hash with the challenge's algorithm, md5 unless it asks for sha-256
+ */
+fn hash(algorithm: &str, input: &str) -> String {
+    if algorithm.to_uppercase().starts_with("SHA-256") {
+        format!("{:x}", Sha256::digest(input.as_bytes()))
+    } else {
+        format!("{:x}", Md5::digest(input.as_bytes()))
+    }
+}
+
+impl DigestAuth {
+    /**
+     * Parse the credentials given as user:pass
+     */
+    pub fn parse(credentials: &str) -> Option<DigestAuth> {
+        let (user, pass) = credentials.split_once(':')?;
+        Some(DigestAuth {
+            user: user.to_string(),
+            pass: pass.to_string(),
+        })
+    }
+
+    /**
+     * Answer the digest challenge of a 401 response, None when the response
+     * didn't offer one
+     */
+    pub fn authorization(
+        &self,
+        headers: &HeaderMap,
+        method: &str,
+        url: &str,
+    ) -> Option<HeaderValue> {
+        let cnonce = format!("{:016x}", rand::thread_rng().gen::<u64>());
+        self.answer(headers, method, url, &cnonce)
+    }
+
+    /**
+     * Answer the digest challenge with the given client nonce
+     */
+    fn answer(
+        &self,
+        headers: &HeaderMap,
+        method: &str,
+        url: &str,
+        cnonce: &str,
+    ) -> Option<HeaderValue> {
+        let params = headers.get_all(WWW_AUTHENTICATE).iter().find_map(|value| {
+            let value = value.to_str().ok()?;
+            let (scheme, params) = value.trim().split_once(' ')?;
+            scheme
+                .eq_ignore_ascii_case("digest")
+                .then(|| challenge_params(params))
+        })?;
+        let realm = params.get("realm").cloned().unwrap_or_default();
+        let nonce = params.get("nonce")?;
+        let algorithm = params
+            .get("algorithm")
+            .cloned()
+            .unwrap_or_else(|| "MD5".to_string());
+        let url = Url::parse(url).ok()?;
+        let uri = match url.query() {
+            Some(query) => format!("{}?{}", url.path(), query),
+            None => url.path().to_string(),
+        };
+
+        let nc = "00000001";
+        let mut ha1 = hash(
+            &algorithm,
+            &format!("{}:{}:{}", self.user, realm, self.pass),
+        );
+        if algorithm.to_lowercase().ends_with("-sess") {
+            ha1 = hash(&algorithm, &format!("{}:{}:{}", ha1, nonce, cnonce));
+        }
+        let ha2 = hash(&algorithm, &format!("{}:{}", method, uri));
+
+        // only the plain auth quality of protection is answered
+        let qop = params
+            .get("qop")
+            .filter(|qop| qop.split(',').any(|qop| qop.trim() == "auth"));
+        let response = match qop {
+            Some(_) => hash(
+                &algorithm,
+                &format!("{}:{}:{}:{}:auth:{}", ha1, nonce, nc, cnonce, ha2),
+            ),
+            None => hash(&algorithm, &format!("{}:{}:{}", ha1, nonce, ha2)),
+        };
+
+        let mut authorization = format!(
+            "Digest username=\"{}\", realm=\"{}\", nonce=\"{}\", uri=\"{}\", algorithm={}, response=\"{}\"",
+            self.user, realm, nonce, uri, algorithm, response
+        );
+        if qop.is_some() {
+            authorization.push_str(&format!(", qop=auth, nc={}, cnonce=\"{}\"", nc, cnonce));
+        }
+        if let Some(opaque) = params.get("opaque") {
+            authorization.push_str(&format!(", opaque=\"{}\"", opaque));
+        }
+        HeaderValue::from_str(&authorization).ok()
+    }
+}
+
+// negotiate unicode, ntlm, always sign, extended session security, target
+// info, 128 and 56 bit keys, and ask for the target name
+const NTLM_FLAGS: u32 = 0xa088_8205;
+
+// the seconds between the windows epoch and the unix one
+const WINDOWS_EPOCH_OFFSET: u64 = 11_644_473_600;
+
+/**
+ * The credentials for the ntlm handshake of windows integrated auth
+ */
+#[derive(Clone, Debug)]
+pub struct NtlmAuth {
+    domain: String,
+    user: String,
+    pass: String,
+}
+
+/**
+ * The parts of the server's challenge message the answer is built from
+ */
+struct NtlmChallenge {
+    flags: u32,
+    challenge: [u8; 8],
+    target_info: Vec<u8>,
+}
+
+fn utf16le(text: &str) -> Vec<u8> {
+    text.encode_utf16()
+        .flat_map(|unit| unit.to_le_bytes())
+        .collect()
+}
+
+fn hmac_md5(key: &[u8], parts: &[&[u8]]) -> Vec<u8> {
+    let mut mac = Hmac::<Md5>::new_from_slice(key).expect("hmac takes keys of any size");
+    for part in parts {
+        mac.update(part);
+    }
+    mac.finalize().into_bytes().to_vec()
+}
+
+/**
+ * A length, allocated length and offset triple pointing into the payload
+ */
+fn security_buffer(len: usize, offset: usize) -> Vec<u8> {
+    let mut buffer = vec![];
+    buffer.extend_from_slice(&(len as u16).to_le_bytes());
+    buffer.extend_from_slice(&(len as u16).to_le_bytes());
+    buffer.extend_from_slice(&(offset as u32).to_le_bytes());
+    buffer
+}
+
+/**
+ * The ntlm token of the 401, after the scheme the server offered
+ */
+fn ntlm_token(headers: &HeaderMap) -> Option<(&'static str, Option<String>)> {
+    let mut offered = None;
+    for value in headers.get_all(WWW_AUTHENTICATE) {
+        let value = match value.to_str() {
+            Ok(value) => value.trim(),
+            Err(_) => continue,
+        };
+        let (scheme, token) = match value.split_once(' ') {
+            Some((scheme, token)) => (scheme, Some(token.trim().to_string())),
+            None => (value, None),
+        };
+        if scheme.eq_ignore_ascii_case("ntlm") {
+            return Some(("NTLM", token));
+        }
+        if scheme.eq_ignore_ascii_case("negotiate") {
+            offered = Some(("Negotiate", token));
+        }
+    }
+    offered
+}
+
+impl NtlmChallenge {
+    /**
+     * Parse the type 2 message
+     */
+    fn parse(message: &[u8]) -> Option<NtlmChallenge> {
+        if message.len() < 32 || &message[..8] != b"NTLMSSP\0" || message[8] != 2 {
+            return None;
+        }
+        let u16_at = |at: usize| u16::from_le_bytes([message[at], message[at + 1]]) as usize;
+        let u32_at = |at: usize| {
+            u32::from_le_bytes([
+                message[at],
+                message[at + 1],
+                message[at + 2],
+                message[at + 3],
+            ])
+        };
+        let flags = u32_at(20);
+        let mut challenge = [0u8; 8];
+        challenge.copy_from_slice(&message[24..32]);
+        let target_info = if message.len() >= 48 {
+            let (len, offset) = (u16_at(40), u32_at(44) as usize);
+            message.get(offset..offset + len)?.to_vec()
+        } else {
+            vec![]
+        };
+        Some(NtlmChallenge {
+            flags,
+            challenge,
+            target_info,
+        })
+    }
+}
+
+impl NtlmAuth {
+    /**
+     * Parse the credentials given as DOMAIN/user:pass or DOMAIN\user:pass,
+     * the domain may be left out
+     */
+    pub fn parse(credentials: &str) -> Option<NtlmAuth> {
+        let (account, pass) = credentials.split_once(':')?;
+        let (domain, user) = match account.split_once(|c| c == '/' || c == '\\') {
+            Some((domain, user)) => (domain, user),
+            None => ("", account),
+        };
+        Some(NtlmAuth {
+            domain: domain.to_string(),
+            user: user.to_string(),
+            pass: pass.to_string(),
+        })
+    }
+
+    /**
+     * The type 1 message opening the handshake
+     */
+    fn negotiate_message(&self) -> Vec<u8> {
+        let mut message = b"NTLMSSP\0".to_vec();
+        message.extend_from_slice(&1u32.to_le_bytes());
+        message.extend_from_slice(&NTLM_FLAGS.to_le_bytes());
+        message.extend_from_slice(&security_buffer(0, 32));
+        message.extend_from_slice(&security_buffer(0, 32));
+        message
+    }
+
+    /**
+     * The lmv2 and ntlmv2 responses to the server's challenge, from the
+     * client's challenge and the time in tenths of a microsecond since 1601
+     */
+    fn responses(
+        &self,
+        challenge: &NtlmChallenge,
+        client_challenge: [u8; 8],
+        timestamp: u64,
+    ) -> (Vec<u8>, Vec<u8>) {
+        let nt_hash = Md4::digest(utf16le(&self.pass));
+        let identity = utf16le(&format!("{}{}", self.user.to_uppercase(), self.domain));
+        let v2_hash = hmac_md5(&nt_hash, &[&identity]);
+
+        let mut blob = vec![0x01, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+        blob.extend_from_slice(&timestamp.to_le_bytes());
+        blob.extend_from_slice(&client_challenge);
+        blob.extend_from_slice(&[0u8; 4]);
+        blob.extend_from_slice(&challenge.target_info);
+        blob.extend_from_slice(&[0u8; 4]);
+
+        let mut nt_response = hmac_md5(&v2_hash, &[&challenge.challenge, &blob]);
+        nt_response.extend_from_slice(&blob);
+        let mut lm_response = hmac_md5(&v2_hash, &[&challenge.challenge, &client_challenge]);
+        lm_response.extend_from_slice(&client_challenge);
+        (lm_response, nt_response)
+    }
+
+    /**
+     * The type 3 message answering the challenge with ntlmv2 responses
+     */
+    fn authenticate_message(&self, challenge: &NtlmChallenge) -> Vec<u8> {
+        let client_challenge = rand::thread_rng().gen::<[u8; 8]>();
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|now| (now.as_secs() + WINDOWS_EPOCH_OFFSET) * 10_000_000)
+            .unwrap_or_default();
+        let (lm_response, nt_response) = self.responses(challenge, client_challenge, timestamp);
+
+        let domain = utf16le(&self.domain);
+        let user = utf16le(&self.user);
+        let workstation = utf16le("HREKT");
+
+        // the payload follows the 64 byte header in this order
+        let mut offset = 64;
+        let mut message = b"NTLMSSP\0".to_vec();
+        message.extend_from_slice(&3u32.to_le_bytes());
+        let mut payload = vec![];
+        for field in [&lm_response, &nt_response, &domain, &user, &workstation] {
+            message.extend_from_slice(&security_buffer(field.len(), offset));
+            payload.extend_from_slice(field);
+            offset += field.len();
+        }
+        message.extend_from_slice(&security_buffer(0, offset));
+        message.extend_from_slice(&(challenge.flags & NTLM_FLAGS).to_le_bytes());
+        message.extend_from_slice(&payload);
+        message
+    }
+
+    /**
+     * Go through the handshake after a 401 offering ntlm, the three messages
+     * have to travel over the same kept alive connection, None when the
+     * server didn't offer ntlm or the handshake broke off
+     */
+    pub async fn handshake(
+        &self,
+        client: &reqwest::Client,
+        url: &str,
+        headers: &HeaderMap,
+        challenged: &HeaderMap,
+        timeout: Option<usize>,
+        shared: &Shared,
+    ) -> Option<reqwest::Response> {
+        let (scheme, _) = ntlm_token(challenged)?;
+
+        let mut negotiate_headers = headers.clone();
+        let negotiate = format!("{} {}", scheme, STANDARD.encode(self.negotiate_message()));
+        negotiate_headers.insert(AUTHORIZATION, HeaderValue::from_str(&negotiate).ok()?);
+        let resp =
+            fetch_method(client, Method::GET, url, negotiate_headers, timeout, shared).await?;
+        let token = ntlm_token(resp.headers())?.1?;
+        // read the body so the connection goes back to the pool for the answer
+        let _ = resp.bytes().await;
+        let challenge = NtlmChallenge::parse(&STANDARD.decode(token).ok()?)?;
+
+        let mut authenticate_headers = headers.clone();
+        let authenticate = format!(
+            "{} {}",
+            scheme,
+            STANDARD.encode(self.authenticate_message(&challenge))
+        );
+        authenticate_headers.insert(AUTHORIZATION, HeaderValue::from_str(&authenticate).ok()?);
+        // archived by the probe along with the page it reads
+        try_fetch_method(
+            client,
+            Method::GET,
+            url,
+            authenticate_headers,
+            timeout,
+            shared,
+        )
+        .await
+        .ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    fn unhex(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|at| u8::from_str_radix(&hex[at..at + 2], 16).unwrap())
+            .collect()
+    }
+
+    fn challenged(challenge: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(WWW_AUTHENTICATE, HeaderValue::from_str(challenge).unwrap());
+        headers
+    }
+
+    // the challenge of the RFC 7616 examples, in 3.9.1
+    const RFC_7616_CHALLENGE: &str = "Digest realm=\"http-auth@example.org\", qop=\"auth, auth-int\", algorithm={}, nonce=\"7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v\", opaque=\"FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS\"";
+
+    const RFC_7616_CNONCE: &str = "f2/wE4q74E6zIJEtWaHKaf5wv/H5QzzpXusqGemxURZJ";
+
+    fn answer_rfc_7616(algorithm: &str) -> String {
+        let auth = DigestAuth::parse("Mufasa:Circle of Life").unwrap();
+        let headers = challenged(&RFC_7616_CHALLENGE.replace("{}", algorithm));
+        auth.answer(
+            &headers,
+            "GET",
+            "http://www.example.org/dir/index.html",
+            RFC_7616_CNONCE,
+        )
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .to_string()
+    }
+
+    #[test]
+    fn answers_the_md5_example_of_rfc_7616() {
+        assert_eq!(
+            answer_rfc_7616("MD5"),
+            "Digest username=\"Mufasa\", realm=\"http-auth@example.org\", \
+             nonce=\"7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v\", uri=\"/dir/index.html\", \
+             algorithm=MD5, response=\"8ca523f5e9506fed4657c9700eebdbec\", qop=auth, \
+             nc=00000001, cnonce=\"f2/wE4q74E6zIJEtWaHKaf5wv/H5QzzpXusqGemxURZJ\", \
+             opaque=\"FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS\""
+        );
+    }
+
+    #[test]
+    fn answers_the_sha_256_example_of_rfc_7616() {
+        assert!(answer_rfc_7616("SHA-256").contains(
+            "response=\"753927fa0e85d155564e2e272a28d1802ca10daf4496794697cf8db5856cb6c1\""
+        ));
+    }
+
+    #[test]
+    fn answers_the_example_of_rfc_2617() {
+        let auth = DigestAuth::parse("Mufasa:Circle Of Life").unwrap();
+        let headers = challenged(
+            "Digest realm=\"testrealm@host.com\", qop=\"auth,auth-int\", \
+             nonce=\"dcd98b7102dd2f0e8b11d0f600bfb0c093\", \
+             opaque=\"5ccc069c403ebaf9f0171e9517f40e41\"",
+        );
+        let authorization = auth
+            .answer(
+                &headers,
+                "GET",
+                "http://www.nowhere.org/dir/index.html",
+                "0a4f113b",
+            )
+            .unwrap();
+        assert!(authorization
+            .to_str()
+            .unwrap()
+            .contains("response=\"6629fae49393a05397450978507c4ef1\""));
+    }
+
+    #[test]
+    fn answers_without_a_qop_or_a_challenge() {
+        let auth = DigestAuth::parse("Mufasa:Circle Of Life").unwrap();
+        // RFC 2069, the response is only over ha1, the nonce and ha2
+        let headers = challenged(
+            "Digest realm=\"testrealm@host.com\", nonce=\"dcd98b7102dd2f0e8b11d0f600bfb0c093\"",
+        );
+        let authorization = auth
+            .answer(
+                &headers,
+                "GET",
+                "http://www.nowhere.org/dir/index.html?a=1",
+                "x",
+            )
+            .unwrap();
+        let authorization = authorization.to_str().unwrap();
+        assert!(authorization.contains("uri=\"/dir/index.html?a=1\""));
+        assert!(authorization.contains("response=\"1f8c515b0f68f1da3ec9d48180443244\""));
+        assert!(!authorization.contains("qop"));
+        assert!(!authorization.contains("opaque"));
+
+        // a basic challenge, a digest one without a nonce
+        for challenge in ["Basic realm=\"x\"", "Digest realm=\"x\""] {
+            assert!(auth
+                .answer(&challenged(challenge), "GET", "http://x/", "x")
+                .is_none());
+        }
+        assert!(DigestAuth::parse("Mufasa").is_none());
+    }
+
+    #[test]
+    fn splits_the_quoted_parameters() {
+        let params = challenge_params("realm=\"a, b\", qop=auth ,Nonce=\"n\", stale=false");
+        assert_eq!(params["realm"], "a, b");
+        assert_eq!(params["qop"], "auth");
+        assert_eq!(params["nonce"], "n");
+        assert_eq!(params["stale"], "false");
+        // an unterminated quote runs to the end
+        assert_eq!(challenge_params("realm=\"open")["realm"], "open");
+        assert!(challenge_params("garbage").is_empty());
+    }
+
+    // the target info of the MS-NLMP examples, the domain and server names
+    const SPEC_TARGET_INFO: &str =
+        "02000c0044006f006d00610069006e0001000c0053006500720076006500720000000000";
+
+    fn challenge_message(flags: u32, challenge: &[u8], target_info: &[u8]) -> Vec<u8> {
+        let mut message = b"NTLMSSP\0".to_vec();
+        message.extend_from_slice(&2u32.to_le_bytes());
+        message.extend_from_slice(&security_buffer(0, 48));
+        message.extend_from_slice(&flags.to_le_bytes());
+        message.extend_from_slice(challenge);
+        message.extend_from_slice(&[0u8; 8]);
+        message.extend_from_slice(&security_buffer(target_info.len(), 48));
+        message.extend_from_slice(target_info);
+        message
+    }
+
+    #[test]
+    fn answers_the_ntlmv2_example_of_the_spec() {
+        // MS-NLMP 4.2.4, the time and client challenge fixed
+        let auth = NtlmAuth::parse("Domain\\User:Password").unwrap();
+        let message = challenge_message(
+            NTLM_FLAGS,
+            &unhex("0123456789abcdef"),
+            &unhex(SPEC_TARGET_INFO),
+        );
+        let challenge = NtlmChallenge::parse(&message).unwrap();
+        let (lm_response, nt_response) = auth.responses(&challenge, [0xaa; 8], 0);
+
+        assert_eq!(
+            hex(&lm_response),
+            "86c35097ac9cec102554764a57cccc19aaaaaaaaaaaaaaaa"
+        );
+        // the NTProofStr, then the blob it proves
+        assert_eq!(hex(&nt_response[..16]), "68cd0ab851e51c96aabc927bebef6a1c");
+        assert_eq!(
+            hex(&nt_response[16..]),
+            format!(
+                "01010000000000000000000000000000aaaaaaaaaaaaaaaa00000000{}00000000",
+                SPEC_TARGET_INFO
+            )
+        );
+    }
+
+    #[test]
+    fn lays_out_the_authenticate_message() {
+        let auth = NtlmAuth::parse("Domain/User:Password").unwrap();
+        let challenge =
+            NtlmChallenge::parse(&challenge_message(0xffff_ffff, &[1; 8], &[])).unwrap();
+        let message = auth.authenticate_message(&challenge);
+
+        assert_eq!(&message[..12], b"NTLMSSP\0\x03\0\0\0");
+        // only the flags both sides offered
+        assert_eq!(&message[60..64], &NTLM_FLAGS.to_le_bytes());
+        // the domain and user point at themselves in the payload
+        let field = |at: usize| {
+            let len = u16::from_le_bytes([message[at], message[at + 1]]) as usize;
+            let offset = u32::from_le_bytes(message[at + 4..at + 8].try_into().unwrap()) as usize;
+            message[offset..offset + len].to_vec()
+        };
+        assert_eq!(field(28), utf16le("Domain"));
+        assert_eq!(field(36), utf16le("User"));
+        assert_eq!(field(44), utf16le("HREKT"));
+    }
+
+    #[test]
+    fn rejects_a_malformed_challenge() {
+        let message = challenge_message(NTLM_FLAGS, &[1; 8], &unhex(SPEC_TARGET_INFO));
+        // too short, the wrong signature, a negotiate message
+        assert!(NtlmChallenge::parse(&message[..31]).is_none());
+        assert!(NtlmChallenge::parse(&[b"NTLMSSQ\0".as_slice(), &message[8..]].concat()).is_none());
+        let mut negotiate = message.clone();
+        negotiate[8] = 1;
+        assert!(NtlmChallenge::parse(&negotiate).is_none());
+        // a target info running past the end
+        assert!(NtlmChallenge::parse(&message[..message.len() - 1]).is_none());
+    }
+
+    #[test]
+    fn reads_the_domain_of_the_credentials() {
+        for credentials in ["CORP/alice:secret", "CORP\\alice:secret"] {
+            let auth = NtlmAuth::parse(credentials).unwrap();
+            assert_eq!(
+                (auth.domain.as_str(), auth.user.as_str()),
+                ("CORP", "alice")
+            );
+        }
+        let auth = NtlmAuth::parse("alice:se:cret").unwrap();
+        assert_eq!((auth.domain.as_str(), auth.pass.as_str()), ("", "se:cret"));
+        assert!(NtlmAuth::parse("alice").is_none());
+    }
+}
@@ -0,0 +1,69 @@
+use crate::{connect::connect, Settings};
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::io::AsyncReadExt;
+
+// the most of the greeting that is read
+const MAX_BANNER: usize = 1024;
+
+/**
+ * Reads the greeting services such as ssh, ftp and smtp send as soon as
+ * they're connected to, for the ports that didn't answer in http
+ */
+pub struct Banners {
+    settings: Arc<Settings>,
+    // each port is only read once, whichever of its schemes failed first
+    seen: Mutex<HashSet<String>>,
+}
+
+/**
+ * The first line of the greeting with the control characters dropped
+ */
+fn first_line(greeting: &[u8]) -> Option<String> {
+    let line = greeting.split(|b| *b == b'\n').next()?;
+    let line = String::from_utf8_lossy(line)
+        .chars()
+        .filter(|c| !c.is_control())
+        .collect::<String>();
+    let line = line.trim();
+    if line.is_empty() || line.starts_with("HTTP/") {
+        return None;
+    }
+    Some(line.to_string())
+}
+
+impl Banners {
+    pub fn new(settings: Arc<Settings>) -> Banners {
+        Banners {
+            settings,
+            seen: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /**
+     * The banner of the port of the url, None when the port was already
+     * read or says nothing until it's spoken to
+     */
+    pub async fn grab(&self, url: &str) -> Option<String> {
+        let url = reqwest::Url::parse(url).ok()?;
+        let addr = format!("{}:{}", url.host_str()?, url.port_or_known_default()?);
+        if !self.seen.lock().unwrap().insert(addr.clone()) {
+            return None;
+        }
+
+        let timeout = Duration::from_secs((self.settings.timeout as u64).clamp(1, 5));
+        let mut stream = tokio::time::timeout(timeout, connect(&addr, &self.settings))
+            .await
+            .ok()?
+            .ok()?;
+        let mut greeting = vec![0u8; MAX_BANNER];
+        let read = tokio::time::timeout(timeout, stream.read(&mut greeting))
+            .await
+            .ok()?
+            .ok()?;
+        first_line(&greeting[..read])
+    }
+}
@@ -0,0 +1,111 @@
+use colored::Colorize;
+use futures::StreamExt;
+use std::time::{Duration, Instant};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    task,
+};
+
+// the number of requests sent to the local server
+const BENCH_REQUESTS: usize = 20000;
+
+const RESPONSE: &[u8] = b"HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: 39\r\n\r\n<html><title>hrekt bench</title></html>";
+
+/**
+ * Answer every request on the connection with the same small page
+ */
+async fn serve(mut stream: TcpStream) {
+    let mut buf = vec![0u8; 8192];
+    let mut pending = Vec::new();
+    loop {
+        let n = match stream.read(&mut buf).await {
+            Ok(0) | Err(_) => return,
+            Ok(n) => n,
+        };
+        pending.extend_from_slice(&buf[..n]);
+        // the requests carry no body, so each ends with an empty line
+        while let Some(end) = pending.windows(4).position(|w| w == b"\r\n\r\n") {
+            pending.drain(..end + 4);
+            if stream.write_all(RESPONSE).await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+/**
+ * Probe a built-in local server to measure the throughput this machine can reach
+ */
+pub async fn run_bench(concurrency: usize, timeout: usize) {
+    let listener = match TcpListener::bind("127.0.0.1:0").await {
+        Ok(listener) => listener,
+        Err(err) => {
+            eprintln!(
+                "{}",
+                format!("could not start the bench server: {}", err).red()
+            );
+            return;
+        }
+    };
+    let addr = match listener.local_addr() {
+        Ok(addr) => addr,
+        Err(_) => return,
+    };
+    let server = task::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            task::spawn(serve(stream));
+        }
+    });
+
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(timeout as u64))
+        .build()
+        .unwrap();
+    let url = format!("http://{}/", addr);
+
+    eprintln!(
+        "{}",
+        format!(
+            "[bench] sending {} requests with a concurrency of {}",
+            BENCH_REQUESTS, concurrency
+        )
+        .white()
+    );
+    let started = Instant::now();
+    let mut latencies = futures::stream::iter(0..BENCH_REQUESTS)
+        .map(|_| {
+            let client = &client;
+            let url = &url;
+            async move {
+                let sent = Instant::now();
+                let resp = client.get(url).send().await.ok()?;
+                resp.bytes().await.ok()?;
+                Some(sent.elapsed())
+            }
+        })
+        .buffer_unordered(concurrency.max(1))
+        .filter_map(|latency| async move { latency })
+        .collect::<Vec<Duration>>()
+        .await;
+    let elapsed = started.elapsed();
+    server.abort();
+
+    latencies.sort();
+    let errors = BENCH_REQUESTS - latencies.len();
+    let percentile = |p: usize| {
+        latencies
+            .get((latencies.len() * p / 100).min(latencies.len().saturating_sub(1)))
+            .map(|latency| latency.as_millis())
+            .unwrap_or_default()
+    };
+    println!(
+        "{} requests in {:.2}s, {:.0} requests per second, {} errors, p50 {}ms, p99 {}ms",
+        latencies.len(),
+        elapsed.as_secs_f64(),
+        latencies.len() as f64 / elapsed.as_secs_f64(),
+        errors,
+        percentile(50),
+        percentile(99)
+    );
+}
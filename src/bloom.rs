@@ -0,0 +1,141 @@
+use std::{
+    collections::{hash_map::DefaultHasher, HashSet},
+    hash::{Hash, Hasher},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+};
+
+// the number of bit positions set for every item
+const HASHES: u64 = 7;
+
+/**
+ * A fixed size bloom filter, with m bits and n items inserted the chance of
+ * a new item being mistaken for a seen one is about (1 - e^(-7n/m))^7
+ */
+pub struct BloomFilter {
+    bits: Vec<AtomicU64>,
+    size: u64,
+}
+
+impl BloomFilter {
+    pub fn new(size: u64) -> Self {
+        let size = size.max(64);
+        let words = size.div_ceil(64) as usize;
+        BloomFilter {
+            bits: (0..words).map(|_| AtomicU64::new(0)).collect(),
+            size,
+        }
+    }
+
+    /**
+     * Set the item's bits, returns true when at least one of them was unset
+     */
+    pub fn insert(&self, item: &str) -> bool {
+        let mut first = DefaultHasher::new();
+        item.hash(&mut first);
+        let first = first.finish();
+        let mut second = DefaultHasher::new();
+        (item, 0x9e37_79b9_7f4a_7c15u64).hash(&mut second);
+        // the step must never be zero or every position would be the same
+        let second = second.finish() | 1;
+
+        let mut new = false;
+        for i in 0..HASHES {
+            let bit = first.wrapping_add(i.wrapping_mul(second)) % self.size;
+            let mask = 1u64 << (bit % 64);
+            let previous = self.bits[(bit / 64) as usize].fetch_or(mask, Ordering::Relaxed);
+            if previous & mask == 0 {
+                new = true;
+            }
+        }
+        new
+    }
+}
+
+/**
+ * Drops the items already seen, exactly or approximately with far less memory
+ */
+pub enum Dedupe {
+    Exact(Mutex<HashSet<String>>),
+    Bloom(BloomFilter),
+}
+
+impl Dedupe {
+    /**
+     * Create the dedupe for the mode, "exact" or "bloom"
+     */
+    pub fn new(mode: &str, bits: u64) -> Option<Dedupe> {
+        match mode {
+            "exact" => Some(Dedupe::Exact(Mutex::new(HashSet::new()))),
+            "bloom" => Some(Dedupe::Bloom(BloomFilter::new(bits))),
+            _ => None,
+        }
+    }
+
+    /**
+     * Returns true the first time an item is seen
+     */
+    pub fn first_seen(&self, item: &str) -> bool {
+        match self {
+            Dedupe::Exact(seen) => seen.lock().unwrap().insert(item.to_string()),
+            Dedupe::Bloom(filter) => filter.insert(item),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn never_forgets_an_item() {
+        let filter = BloomFilter::new(1 << 16);
+        for i in 0..5_000 {
+            filter.insert(&format!("https://host{}.example.com", i));
+        }
+        for i in 0..5_000 {
+            assert!(!filter.insert(&format!("https://host{}.example.com", i)));
+        }
+    }
+
+    #[test]
+    fn keeps_to_its_false_positive_rate() {
+        // 7 bits for each of 50k items in 1Mbit, about 1 in 6000 by the formula
+        let filter = BloomFilter::new(1 << 20);
+        for i in 0..50_000 {
+            filter.insert(&format!("seen-{}", i));
+        }
+        let mistaken = (0..10_000)
+            .filter(|i| !filter.insert(&format!("unseen-{}", i)))
+            .count();
+        assert!(mistaken < 20, "{} of 10000 mistaken", mistaken);
+    }
+
+    #[test]
+    fn takes_a_size_too_small_to_hold_anything() {
+        for size in [0, 1, 63] {
+            let filter = BloomFilter::new(size);
+            assert_eq!(filter.size, 64);
+            assert!(filter.insert(""));
+            assert!(!filter.insert(""));
+        }
+        // a size that isn't a whole number of words
+        let filter = BloomFilter::new(65);
+        assert_eq!(filter.bits.len(), 2);
+        assert!(filter.insert("a"));
+    }
+
+    #[test]
+    fn dedupes_by_mode() {
+        for mode in ["exact", "bloom"] {
+            let dedupe = Dedupe::new(mode, 1 << 16).unwrap();
+            assert!(dedupe.first_seen("https://example.com"));
+            assert!(!dedupe.first_seen("https://example.com"));
+            assert!(dedupe.first_seen("http://example.com"));
+        }
+        assert!(Dedupe::new("fuzzy", 1 << 16).is_none());
+        assert!(Dedupe::new("", 1 << 16).is_none());
+    }
+}
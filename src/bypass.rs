@@ -0,0 +1,170 @@
+use crate::{fetch_method, Shared};
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue},
+    Method, Url,
+};
+
+// the headers some proxies and frameworks trust to say where a request came from
+const IP_HEADERS: [&str; 5] = [
+    "X-Forwarded-For",
+    "X-Real-IP",
+    "X-Originating-IP",
+    "X-Client-IP",
+    "X-Custom-IP-Authorization",
+];
+
+// the headers some frameworks route by instead of the path of the request
+const REWRITE_HEADERS: [&str; 2] = ["X-Original-URL", "X-Rewrite-URL"];
+
+/**
+ * A request that may get past a 403, named the way it's reported
+ */
+struct Technique {
+    name: String,
+    method: Method,
+    url: String,
+    headers: HeaderMap,
+}
+
+impl Technique {
+    fn new(name: String, url: String) -> Technique {
+        Technique {
+            name,
+            method: Method::GET,
+            url,
+            headers: HeaderMap::new(),
+        }
+    }
+
+    fn header(mut self, name: &str, value: &str) -> Technique {
+        if let (Ok(name), Ok(value)) = (
+            HeaderName::from_bytes(name.as_bytes()),
+            HeaderValue::from_str(value),
+        ) {
+            self.headers.insert(name, value);
+        }
+        self
+    }
+}
+
+/**
+ * The variations of the request, the path tricks are the ones that survive
+ * the url parser, which drops the %2e segments before anything is sent
+ */
+fn techniques(url: &Url) -> Vec<Technique> {
+    let path = url.path().to_string();
+    let trimmed = path.trim_end_matches('/');
+    let mut root = url.clone();
+    root.set_path("/");
+    root.set_query(None);
+    let with_path = |new_path: &str| {
+        let query = url
+            .query()
+            .map(|query| format!("?{}", query))
+            .unwrap_or_default();
+        format!(
+            "{}{}{}",
+            root.as_str().trim_end_matches('/'),
+            new_path,
+            query
+        )
+    };
+
+    let mut techniques = vec![];
+    for header in IP_HEADERS {
+        techniques.push(
+            Technique::new(format!("header:{}", header), url.to_string())
+                .header(header, "127.0.0.1"),
+        );
+    }
+    // the front end sees the root, the application the path of the header
+    if trimmed.len() > 1 {
+        for header in REWRITE_HEADERS {
+            techniques.push(
+                Technique::new(format!("header:{}", header), root.to_string())
+                    .header(header, &path),
+            );
+        }
+    }
+
+    let mut paths = vec![format!("/.;{}", path), format!("/{}", path)];
+    if !trimmed.is_empty() {
+        paths.extend([
+            format!("{}/", trimmed),
+            format!("{}/.", trimmed),
+            format!("{}..;/", trimmed),
+            format!("{};/", trimmed),
+            format!("{}%20", trimmed),
+            format!("{}?", trimmed),
+            trimmed.to_uppercase(),
+        ]);
+    }
+    for new_path in paths {
+        if new_path == path {
+            continue;
+        }
+        techniques.push(Technique::new(
+            format!("path:{}", new_path),
+            with_path(&new_path),
+        ));
+    }
+
+    let mut post = Technique::new("method:POST".to_string(), url.to_string());
+    post.method = Method::POST;
+    techniques.push(post.header("Content-Length", "0"));
+    let mut head = Technique::new("method:HEAD".to_string(), url.to_string());
+    head.method = Method::HEAD;
+    techniques.push(head);
+    techniques
+}
+
+/**
+ * Try the known ways around a 403 and report the ones that changed the
+ * status, as the technique and the status it got
+ */
+pub async fn try_bypasses(
+    client: &reqwest::Client,
+    url: &str,
+    timeout: Option<usize>,
+    shared: &Shared,
+) -> Vec<String> {
+    let url = match Url::parse(url) {
+        Ok(url) => url,
+        Err(_) => return vec![],
+    };
+
+    let mut bypasses = vec![];
+    for technique in techniques(&url) {
+        let resp = match fetch_method(
+            client,
+            technique.method,
+            &technique.url,
+            technique.headers,
+            timeout,
+            shared,
+        )
+        .await
+        {
+            Some(resp) => resp,
+            None => continue,
+        };
+        let status = resp.status().as_u16();
+        if status != 403 {
+            bypasses.push(format!("{}={}", technique.name, status));
+        }
+    }
+    bypasses
+}
+
+/**
+ * Whether any of the bypasses got a page instead of an error
+ */
+pub fn has_success(bypasses: &[String]) -> bool {
+    bypasses.iter().any(|bypass| {
+        bypass
+            .rsplit('=')
+            .next()
+            .and_then(|status| status.parse::<u16>().ok())
+            .map_or(false, |status| (200..400).contains(&status))
+    })
+}
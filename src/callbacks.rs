@@ -0,0 +1,35 @@
+use crate::ProbeResult;
+
+/**
+ * How far a scan got, as handed to Callbacks::on_progress
+ */
+#[derive(Clone, Debug)]
+pub struct Progress {
+    // the targets fully probed, out of the ones given
+    pub completed: u64,
+    pub total: usize,
+    pub requests: u64,
+    pub errors: u64,
+}
+
+/**
+ * Called by the workers as the scan goes, to send the results to a sink of
+ * one's own. Each method does nothing unless it's implemented, and they're
+ * called from the workers themselves, so a slow one slows the scan down
+ */
+pub trait Callbacks: Send + Sync {
+    /**
+     * A result as it's written to the output
+     */
+    fn on_result(&self, _result: &ProbeResult) {}
+
+    /**
+     * A probe that failed, with --include-errors or without it
+     */
+    fn on_error(&self, _result: &ProbeResult) {}
+
+    /**
+     * A target was fully probed
+     */
+    fn on_progress(&self, _progress: &Progress) {}
+}
@@ -0,0 +1,93 @@
+use openssl::{asn1::Asn1Time, x509::X509};
+use reqwest::{tls::TlsInfo, Response};
+use serde::{Deserialize, Serialize};
+
+/**
+ * How close the certificate of a response is to expiring
+ */
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CertExpiry {
+    Expired,
+    Expiring(i32),
+}
+
+impl CertExpiry {
+    /**
+     * Format the expiry as an output field
+     */
+    pub fn to_field(&self) -> String {
+        match self {
+            CertExpiry::Expired => "cert-expired".to_string(),
+            CertExpiry::Expiring(days) => format!("cert-expires-in:{}d", days),
+        }
+    }
+}
+
+/**
+ * The certificate the server presented, read from the connection itself so
+ * no extra request is needed
+ */
+pub struct PeerCertificate {
+    cert: X509,
+}
+
+impl PeerCertificate {
+    /**
+     * The certificate of the connection the response came over, None for
+     * plain http
+     */
+    pub fn from_response(resp: &Response) -> Option<PeerCertificate> {
+        let der = resp.extensions().get::<TlsInfo>()?.peer_certificate()?;
+        let cert = X509::from_der(der).ok()?;
+        Some(PeerCertificate { cert })
+    }
+
+    /**
+     * The dns names the certificate covers, the wildcards reduced to their
+     * parent domain
+     */
+    pub fn names(&self) -> Vec<String> {
+        let mut names = vec![];
+        if let Some(alt_names) = self.cert.subject_alt_names() {
+            for alt_name in alt_names.iter() {
+                if let Some(name) = alt_name.dnsname() {
+                    let name = name.trim_start_matches("*.").trim_end_matches('.');
+                    let name = name.to_lowercase();
+                    if !name.is_empty() && !names.contains(&name) {
+                        names.push(name);
+                    }
+                }
+            }
+        }
+        names
+    }
+
+    /**
+     * Whether the certificate has expired or expires within the given days
+     */
+    pub fn expiry(&self, warn_days: i32) -> Option<CertExpiry> {
+        let now = Asn1Time::days_from_now(0).ok()?;
+        let left = now.diff(self.cert.not_after()).ok()?;
+        if left.days < 0 || (left.days == 0 && left.secs < 0) {
+            Some(CertExpiry::Expired)
+        } else if left.days < warn_days {
+            Some(CertExpiry::Expiring(left.days))
+        } else {
+            None
+        }
+    }
+}
+
+/**
+ * Parse a number of days such as 30d, a bare number is taken as days
+ */
+pub fn parse_days(days: &str) -> Option<i32> {
+    let days = days.trim();
+    days.strip_suffix('d')
+        .unwrap_or(days)
+        .trim()
+        .parse::<i32>()
+        .ok()
+        .filter(|days| *days >= 0)
+}
@@ -0,0 +1,127 @@
+use crate::{stats::Summary, Shared};
+use chrono::Utc;
+use colored::Colorize;
+use serde::Serialize;
+use std::{
+    collections::BTreeSet,
+    fs, io,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+/**
+ * Writes how far the scan got every interval or every so many results, so
+ * a crash loses at most the work since the last checkpoint
+ */
+pub struct Checkpoint {
+    path: String,
+    hosts: Vec<String>,
+    every_results: Option<u64>,
+    results: AtomicU64,
+    progress: Mutex<Progress>,
+    // held while writing, the timer and the results may both write at once
+    writing: Mutex<()>,
+}
+
+/**
+ * The targets done so far, those past the first one still running are kept
+ * apart until the ones before them are done too
+ */
+#[derive(Default)]
+struct Progress {
+    offset: usize,
+    done: BTreeSet<usize>,
+}
+
+/**
+ * The checkpoint as it's written
+ */
+#[derive(Serialize)]
+struct Snapshot<'a> {
+    timestamp: String,
+    targets: usize,
+    offset: usize,
+    next_target: Option<&'a str>,
+    results: u64,
+    stats: Summary,
+}
+
+impl Checkpoint {
+    pub fn new(path: &str, hosts: Vec<String>, every_results: Option<u64>) -> Checkpoint {
+        Checkpoint {
+            path: path.to_string(),
+            hosts,
+            every_results,
+            results: AtomicU64::new(0),
+            progress: Mutex::new(Progress::default()),
+            writing: Mutex::new(()),
+        }
+    }
+
+    /**
+     * Mark the target at this index of the input as done
+     */
+    pub fn complete(&self, index: usize) {
+        let mut progress = self.progress.lock().unwrap();
+        let progress = &mut *progress;
+        if index < progress.offset {
+            return;
+        }
+        progress.done.insert(index);
+        while progress.done.remove(&progress.offset) {
+            progress.offset += 1;
+        }
+    }
+
+    /**
+     * Count a reported result, writing the checkpoint when enough of them
+     * came in since the last one
+     */
+    pub fn record_result(&self, shared: &Shared) -> io::Result<()> {
+        let results = self.results.fetch_add(1, Ordering::Relaxed) + 1;
+        match self.every_results {
+            Some(every) if results % every == 0 => self.write(shared),
+            _ => Ok(()),
+        }
+    }
+
+    /**
+     * Flush the results and write the checkpoint to a temporary file moved
+     * in place, so a crash while writing leaves the previous one whole
+     */
+    pub fn write(&self, shared: &Shared) -> io::Result<()> {
+        shared.output.flush()?;
+        let offset = self.progress.lock().unwrap().offset;
+        let snapshot = Snapshot {
+            timestamp: Utc::now().to_rfc3339(),
+            targets: self.hosts.len(),
+            offset,
+            next_target: self.hosts.get(offset).map(|host| host.as_str()),
+            results: self.results.load(Ordering::Relaxed),
+            stats: shared.stats.summary(),
+        };
+        let json = serde_json::to_string_pretty(&snapshot)?;
+        let _writing = self.writing.lock().unwrap();
+        let temporary = format!("{}.tmp", self.path);
+        fs::write(&temporary, format!("{}\n", json))?;
+        fs::rename(&temporary, &self.path)
+    }
+
+    /**
+     * Write the checkpoint every interval for as long as the scan runs
+     */
+    pub async fn run(self: Arc<Self>, shared: Arc<Shared>, interval: Duration) {
+        loop {
+            tokio::time::sleep(interval).await;
+            if let Err(err) = self.write(&shared) {
+                eprintln!(
+                    "{}",
+                    format!("could not write the checkpoint {}: {}", self.path, err).red()
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,178 @@
+use crate::{memory, Settings};
+use colored::Colorize;
+use headless_chrome::{Browser, LaunchOptions};
+use std::{
+    env,
+    ffi::OsStr,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+use tokio::task;
+
+const NO_SANDBOX: &str = "--no-sandbox";
+
+// how often the browsers are checked
+const HEALTH_INTERVAL: Duration = Duration::from_secs(30);
+
+// how long a browser gets to answer the check
+const HEALTH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/**
+ * The chrome binary to launch, --chrome-path over the CHROME variable, None
+ * to leave it to the auto discovery
+ */
+pub fn chrome_path(chrome_path: Option<&str>) -> Result<Option<PathBuf>, String> {
+    let path = match chrome_path {
+        Some(path) => PathBuf::from(path),
+        None => match env::var_os("CHROME") {
+            Some(path) if !path.is_empty() => PathBuf::from(path),
+            _ => return Ok(None),
+        },
+    };
+    if !path.is_file() {
+        return Err(format!("chrome isn't at {}", path.display()));
+    }
+    Ok(Some(path))
+}
+
+/**
+ * Launch a headless chrome listening for the devtools on a free port, with
+ * the flags of --chrome-args on top of the defaults
+ */
+fn launch(settings: &Settings) -> Result<Browser, String> {
+    // --no-sandbox goes through the sandbox option so it isn't passed twice
+    let sandbox = !settings.chrome_args.iter().any(|arg| arg == NO_SANDBOX);
+    let args = settings
+        .chrome_args
+        .iter()
+        .filter(|arg| *arg != NO_SANDBOX)
+        .map(OsStr::new)
+        .collect::<Vec<&OsStr>>();
+    let options = LaunchOptions::default_builder()
+        .port(port_selector::random_free_tcp_port())
+        .path(settings.chrome_path.clone())
+        .sandbox(sandbox)
+        .args(args)
+        .build()
+        .map_err(|err| err.to_string())?;
+    Browser::new(options).map_err(|err| err.to_string())
+}
+
+/**
+ * The chrome of a worker, killed and launched again when it stops answering
+ * or has loaded too many pages or grown too big
+ */
+pub struct Chrome {
+    browser: Mutex<Browser>,
+    settings: Arc<Settings>,
+    pages: AtomicUsize,
+    relaunching: tokio::sync::Mutex<()>,
+}
+
+impl Chrome {
+    /**
+     * Launch the chrome of a worker
+     */
+    pub fn launch(settings: Arc<Settings>) -> Result<Arc<Chrome>, String> {
+        let browser = launch(&settings)?;
+        Ok(Arc::new(Chrome {
+            browser: Mutex::new(browser),
+            settings,
+            pages: AtomicUsize::new(0),
+            relaunching: tokio::sync::Mutex::new(()),
+        }))
+    }
+
+    /**
+     * The browser running now
+     */
+    pub fn browser(&self) -> Browser {
+        self.browser.lock().unwrap().clone()
+    }
+
+    /**
+     * Whether the browser still answers over the devtools websocket
+     */
+    async fn healthy(&self) -> bool {
+        let browser = self.browser();
+        let check = task::spawn_blocking(move || browser.get_version().is_ok());
+        matches!(
+            tokio::time::timeout(HEALTH_TIMEOUT, check).await,
+            Ok(Ok(true))
+        )
+    }
+
+    /**
+     * Replace the browser with a new one, the old one is killed once the
+     * pages still open in it let go of it
+     */
+    pub async fn relaunch(&self) -> bool {
+        let _relaunching = self.relaunching.lock().await;
+        let settings = self.settings.clone();
+        let browser = match task::spawn_blocking(move || launch(&settings)).await {
+            Ok(Ok(browser)) => browser,
+            Ok(Err(err)) => {
+                if !self.settings.silent {
+                    eprintln!(
+                        "{}",
+                        format!("[chrome] could not relaunch chrome: {}", err).yellow()
+                    );
+                }
+                return false;
+            }
+            Err(_) => return false,
+        };
+        let old = std::mem::replace(&mut *self.browser.lock().unwrap(), browser);
+        task::spawn_blocking(move || drop(old));
+        self.pages.store(0, Ordering::Relaxed);
+        true
+    }
+
+    /**
+     * Count a page loaded in the browser and start a fresh one once it has
+     * loaded --browser-max-pages or its processes pass --browser-max-mem,
+     * chrome never gives all the memory of the closed tabs back
+     */
+    pub async fn page_done(&self) {
+        let pages = self.pages.fetch_add(1, Ordering::Relaxed) + 1;
+        let worn_out = match self.settings.browser_max_pages {
+            Some(max_pages) => pages >= max_pages,
+            None => false,
+        };
+        let too_big = match self.settings.browser_max_mem {
+            Some(max_mem) if !worn_out => self
+                .browser()
+                .get_process_id()
+                .and_then(memory::tree_resident_bytes)
+                .map_or(false, |resident| resident > max_mem),
+            _ => false,
+        };
+        if worn_out || too_big {
+            self.relaunch().await;
+        }
+    }
+
+    /**
+     * Check the browser every so often and relaunch it when it hangs or its
+     * websocket has died
+     */
+    pub async fn watch(self: Arc<Self>) {
+        loop {
+            tokio::time::sleep(HEALTH_INTERVAL).await;
+            if self.healthy().await {
+                continue;
+            }
+            if !self.settings.silent {
+                eprintln!(
+                    "{}",
+                    "[chrome] chrome stopped answering, relaunching it".yellow()
+                );
+            }
+            self.relaunch().await;
+        }
+    }
+}
@@ -0,0 +1,386 @@
+use crate::{build_cli, input, output::Compression, patterns::Pattern, Job, Settings};
+use std::{error::Error, fmt, iter, str::FromStr};
+
+/**
+ * The options of a scan run from code, checked when they're built so a
+ * wrong one fails there rather than once the scan starts
+ *
+ *   let config = ProberConfig::builder()
+ *       .rate(500)
+ *       .ports([80, 443, 8443])
+ *       .tech_detect(true)
+ *       .build()?;
+ */
+#[derive(Clone, Debug)]
+pub struct ProberConfig {
+    rate: Option<u32>,
+    concurrency: Option<i32>,
+    timeout: Option<usize>,
+    ports: Option<Vec<u16>>,
+    path: Option<String>,
+    title: bool,
+    tech_detect: bool,
+    status_code: bool,
+    server: bool,
+    content_type: bool,
+    content_length: bool,
+    follow_redirects: bool,
+    body_regex: Vec<Pattern>,
+    tags: Vec<String>,
+    output: Option<String>,
+    // the options without a method of their own, as on the command line
+    args: Vec<String>,
+}
+
+/**
+ * Collects the options of a ProberConfig, every one left unset keeps the
+ * default of the command line
+ */
+#[derive(Clone, Debug, Default)]
+pub struct ProberConfigBuilder {
+    rate: Option<u32>,
+    concurrency: Option<usize>,
+    timeout: Option<usize>,
+    ports: Option<Vec<u16>>,
+    path: Option<String>,
+    title: bool,
+    tech_detect: bool,
+    status_code: bool,
+    server: bool,
+    content_type: bool,
+    content_length: bool,
+    follow_redirects: bool,
+    body_regex: Vec<String>,
+    tags: Vec<String>,
+    output: Option<String>,
+    // the options given with arg, with their values
+    args: Vec<(String, Option<String>)>,
+}
+
+/**
+ * Why the options of a ProberConfig can't make a scan
+ */
+#[derive(Debug)]
+pub enum ConfigError {
+    // a count or a duration that has to be more than 0
+    Zero(&'static str),
+    NoPorts,
+    BodyRegex(String, regex::Error),
+    // a value that isn't a number or too large for its option
+    Invalid(String, String),
+    Path(String),
+    // what the command line rejects, such as an option needing another
+    Options(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Zero(option) => write!(f, "{} has to be more than 0", option),
+            ConfigError::NoPorts => write!(f, "no ports to probe, or port 0"),
+            ConfigError::BodyRegex(value, err) => {
+                write!(f, "invalid body regex {}: {}", value, err)
+            }
+            ConfigError::Invalid(option, value) => write!(f, "invalid {}: {}", option, value),
+            ConfigError::Path(message) => write!(f, "invalid path: {}", message),
+            ConfigError::Options(message) => write!(f, "{}", message.trim()),
+        }
+    }
+}
+
+impl Error for ConfigError {}
+
+impl ProberConfig {
+    pub fn builder() -> ProberConfigBuilder {
+        ProberConfigBuilder::default()
+    }
+
+    /**
+     * The options given with arg, read by configure along with the defaults
+     * of the options the config leaves unset
+     */
+    pub(crate) fn args(&self) -> &[String] {
+        &self.args
+    }
+
+    /**
+     * Set the options the config has methods for straight on the scan
+     * configure read from the other ones, over the defaults and the
+     * --stealth preset
+     */
+    pub(crate) fn apply(&self, job: &mut Job, settings: &mut Settings) {
+        if let Some(rate) = self.rate {
+            settings.rate = rate;
+            // the rate is the ceiling of --auto-rate unless --rate-max is given
+            if !self.args.iter().any(|arg| arg == "--rate-max") {
+                settings.rate_max = rate;
+            }
+        }
+        if let Some(concurrency) = self.concurrency {
+            settings.concurrency = concurrency;
+        }
+        if let Some(timeout) = self.timeout {
+            settings.timeout = timeout;
+        }
+        if let Some(ports) = &self.ports {
+            job.ports = ports
+                .iter()
+                .map(|port| port.to_string())
+                .collect::<Vec<String>>()
+                .join(",");
+        }
+        if let Some(path) = &self.path {
+            job.path = path.clone();
+        }
+        job.display_title |= self.title;
+        job.display_tech |= self.tech_detect;
+        job.status_codes |= self.status_code;
+        job.server |= self.server;
+        job.content_type |= self.content_type;
+        job.content_length |= self.content_length;
+        settings.follow_redirects |= self.follow_redirects;
+        job.body_regex.extend(self.body_regex.iter().cloned());
+        job.tags.extend(self.tags.iter().cloned());
+        if let Some(output) = &self.output {
+            settings.output = Some(output.clone());
+            if settings.output_compress.is_none() {
+                settings.output_compress = Compression::from_path(output);
+            }
+        }
+    }
+}
+
+/**
+ * Parse the value of an option given with arg into its number
+ */
+fn number<T: FromStr>(option: &str, value: Option<&str>) -> Result<T, ConfigError> {
+    let value = value.unwrap_or_default();
+    value
+        .trim()
+        .parse::<T>()
+        .map_err(|_| ConfigError::Invalid(option.to_string(), value.to_string()))
+}
+
+impl ProberConfigBuilder {
+    pub fn rate(mut self, rate: u32) -> Self {
+        self.rate = Some(rate);
+        self
+    }
+
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = Some(concurrency);
+        self
+    }
+
+    /**
+     * The timeout of each request, in seconds
+     */
+    pub fn timeout(mut self, timeout: usize) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn ports<I: IntoIterator<Item = u16>>(mut self, ports: I) -> Self {
+        self.ports = Some(ports.into_iter().collect());
+        self
+    }
+
+    /**
+     * The path probed, braces expanding to several as with --path
+     */
+    pub fn path(mut self, path: &str) -> Self {
+        self.path = Some(path.to_string());
+        self
+    }
+
+    pub fn title(mut self, title: bool) -> Self {
+        self.title = title;
+        self
+    }
+
+    pub fn tech_detect(mut self, tech_detect: bool) -> Self {
+        self.tech_detect = tech_detect;
+        self
+    }
+
+    pub fn status_code(mut self, status_code: bool) -> Self {
+        self.status_code = status_code;
+        self
+    }
+
+    pub fn server(mut self, server: bool) -> Self {
+        self.server = server;
+        self
+    }
+
+    pub fn content_type(mut self, content_type: bool) -> Self {
+        self.content_type = content_type;
+        self
+    }
+
+    pub fn content_length(mut self, content_length: bool) -> Self {
+        self.content_length = content_length;
+        self
+    }
+
+    pub fn follow_redirects(mut self, follow_redirects: bool) -> Self {
+        self.follow_redirects = follow_redirects;
+        self
+    }
+
+    /**
+     * A regex to match in the bodies, labelled with label:regex, can be
+     * given more than once
+     */
+    pub fn body_regex(mut self, regex: &str) -> Self {
+        self.body_regex.push(regex.to_string());
+        self
+    }
+
+    pub fn tag(mut self, tag: &str) -> Self {
+        self.tags.push(tag.to_string());
+        self
+    }
+
+    /**
+     * Also write the results to this file, as with -o
+     */
+    pub fn output(mut self, output: &str) -> Self {
+        self.output = Some(output.to_string());
+        self
+    }
+
+    /**
+     * Any other option of the command line, with its value when it takes
+     * one, such as .arg("--cache-info", None) or .arg("--proxy", Some(url)).
+     * The options with a method of their own are checked as if it was called
+     */
+    pub fn arg(mut self, option: &str, value: Option<&str>) -> Self {
+        // --rate=500 is --rate 500
+        match (option.split_once('='), value) {
+            (Some((option, value)), None) if option.starts_with("--") => {
+                self.args
+                    .push((option.to_string(), Some(value.to_string())));
+            }
+            _ => self
+                .args
+                .push((option.to_string(), value.map(|value| value.to_string()))),
+        }
+        self
+    }
+
+    /**
+     * Check the options and turn them into a config
+     */
+    pub fn build(mut self) -> Result<ProberConfig, ConfigError> {
+        // the options with a method given with arg, the others are left to
+        // the command line
+        let mut args = vec![];
+        for (option, value) in std::mem::take(&mut self.args) {
+            let value = value.as_deref();
+            match option.as_str() {
+                "-r" | "--rate" => self.rate = Some(number(&option, value)?),
+                "-c" | "--concurrency" => self.concurrency = Some(number(&option, value)?),
+                "-t" | "--timeout" => self.timeout = Some(number(&option, value)?),
+                "-p" | "--ports" => {
+                    let ports = value.unwrap_or_default();
+                    if !input::valid_ports(ports) {
+                        return Err(ConfigError::Invalid(option, ports.to_string()));
+                    }
+                    self.ports = Some(
+                        ports
+                            .split(',')
+                            .filter_map(|port| port.trim().parse::<u16>().ok())
+                            .collect(),
+                    );
+                }
+                "-x" | "--path" => self.path = value.map(|path| path.to_string()),
+                "--body-regex" => self.body_regex.extend(value.map(|regex| regex.to_string())),
+                "--tag" => self.tags.extend(value.map(|tag| tag.to_string())),
+                "-o" | "--output" => self.output = value.map(|output| output.to_string()),
+                "--title" => self.title = true,
+                "--tech-detect" => self.tech_detect = true,
+                "--status-code" => self.status_code = true,
+                "--server" => self.server = true,
+                "--content-type" => self.content_type = true,
+                "--content-length" => self.content_length = true,
+                "-l" | "--follow-redirects" => self.follow_redirects = true,
+                _ => {
+                    args.push(option);
+                    args.extend(value.map(|value| value.to_string()));
+                }
+            }
+        }
+
+        let concurrency = match self.concurrency {
+            Some(0) => return Err(ConfigError::Zero("concurrency")),
+            Some(concurrency) => match i32::try_from(concurrency) {
+                Ok(concurrency) => Some(concurrency),
+                Err(_) => {
+                    return Err(ConfigError::Invalid(
+                        "concurrency".to_string(),
+                        concurrency.to_string(),
+                    ))
+                }
+            },
+            None => None,
+        };
+        if self.rate == Some(0) {
+            return Err(ConfigError::Zero("rate"));
+        }
+        if self.timeout == Some(0) {
+            return Err(ConfigError::Zero("timeout"));
+        }
+        if let Some(ports) = &self.ports {
+            if ports.is_empty() || ports.contains(&0) {
+                return Err(ConfigError::NoPorts);
+            }
+        }
+        if let Some(path) = &self.path {
+            input::expand_braces(path).map_err(ConfigError::Path)?;
+        }
+        let mut body_regex = vec![];
+        for value in &self.body_regex {
+            match Pattern::parse(value) {
+                Ok(pattern) => body_regex.push(pattern),
+                Err(err) => return Err(ConfigError::BodyRegex(value.clone(), err)),
+            }
+        }
+
+        // the other options are checked as the command line checks them,
+        // with the flags some of them need or rule out
+        let needed = [
+            ("--tech-detect", self.tech_detect),
+            ("--follow-redirects", self.follow_redirects),
+        ]
+        .into_iter()
+        .filter(|(_, set)| *set)
+        .map(|(flag, _)| flag.to_string());
+        build_cli()
+            .try_get_matches_from(
+                iter::once("hrekt".to_string())
+                    .chain(args.iter().cloned())
+                    .chain(needed),
+            )
+            .map_err(|err| ConfigError::Options(err.to_string()))?;
+
+        Ok(ProberConfig {
+            rate: self.rate,
+            concurrency,
+            timeout: self.timeout,
+            ports: self.ports,
+            path: self.path,
+            title: self.title,
+            tech_detect: self.tech_detect,
+            status_code: self.status_code,
+            server: self.server,
+            content_type: self.content_type,
+            content_length: self.content_length,
+            follow_redirects: self.follow_redirects,
+            body_regex,
+            tags: self.tags,
+            output: self.output,
+            args,
+        })
+    }
+}
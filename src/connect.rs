@@ -0,0 +1,86 @@
+use crate::{input::Target, rate, Job, Settings};
+use futures::StreamExt;
+use governor::RateLimiter;
+use std::{io, net::SocketAddr, time::Duration};
+use tokio::net::{self, TcpSocket, TcpStream};
+
+/**
+ * Connect from the source ip and interface, when they are set, to the
+ * pinned address of the host when it has one
+ */
+pub async fn connect(addr: &str, settings: &Settings) -> io::Result<TcpStream> {
+    let pinned = settings.static_hosts.lookup(addr);
+    if settings.source_ip.is_none() && settings.interface.is_none() {
+        return match pinned {
+            Some(addrs) => TcpStream::connect(&addrs[..]).await,
+            None => TcpStream::connect(addr).await,
+        };
+    }
+    let addrs = match pinned {
+        Some(addrs) => addrs,
+        None => net::lookup_host(addr).await?.collect(),
+    };
+
+    // only an address of the same family as the source ip can be reached from it
+    let addr = addrs
+        .into_iter()
+        .find(|addr| match settings.source_ip {
+            Some(source_ip) => source_ip.is_ipv4() == addr.is_ipv4(),
+            None => true,
+        })
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no usable address"))?;
+    let socket = if addr.is_ipv4() {
+        TcpSocket::new_v4()?
+    } else {
+        TcpSocket::new_v6()?
+    };
+    if let Some(source_ip) = settings.source_ip {
+        socket.bind(SocketAddr::new(source_ip, 0))?;
+    }
+    #[cfg(any(target_os = "android", target_os = "fuchsia", target_os = "linux"))]
+    if let Some(interface) = &settings.interface {
+        socket.bind_device(Some(interface.as_bytes()))?;
+    }
+    socket.connect(addr).await
+}
+
+/**
+ * Report which host:port pairs accept a tcp connection without sending any http requests
+ */
+pub async fn connect_only(targets: Vec<Target>, job: &Job, settings: &Settings) {
+    let default_ports = job.ports.clone();
+    let timeout = Duration::from_secs(settings.timeout as u64);
+    let lim = RateLimiter::direct(rate::per_second(settings.rate));
+
+    let pairs = targets.into_iter().flat_map(|target| {
+        let ports = target
+            .ports
+            .clone()
+            .unwrap_or_else(|| default_ports.clone());
+        ports
+            .split(',')
+            .map(|port| port.trim().to_string())
+            .filter(|port| !port.is_empty())
+            .map(|port| format!("{}:{}", target.host, port))
+            .collect::<Vec<String>>()
+    });
+
+    futures::stream::iter(pairs)
+        .map(|addr| {
+            let lim = &lim;
+            async move {
+                lim.until_ready().await;
+                match tokio::time::timeout(timeout, connect(&addr, settings)).await {
+                    Ok(Ok(_)) => Some(addr),
+                    _ => None,
+                }
+            }
+        })
+        .buffer_unordered(settings.concurrency.max(1) as usize)
+        .for_each(|addr| async move {
+            if let Some(addr) = addr {
+                println!("{}", addr);
+            }
+        })
+        .await;
+}
@@ -0,0 +1,270 @@
+use crate::{output::Record, xml::XmlReport};
+use flate2::read::MultiGzDecoder;
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Read, Write},
+};
+
+// the columns of the csv, in order
+const CSV_COLUMNS: [&str; 15] = [
+    "timestamp",
+    "url",
+    "status_code",
+    "title",
+    "ip",
+    "content_type",
+    "content_length",
+    "words",
+    "lines",
+    "server",
+    "tech",
+    "body_hash",
+    "redirect_host",
+    "tags",
+    "error",
+];
+
+/**
+ * What the results are converted to
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Csv,
+    Html,
+    Xml,
+    Txt,
+}
+
+impl Format {
+    pub fn parse(format: &str) -> Result<Format, String> {
+        match format.trim().to_lowercase().as_str() {
+            "csv" => Ok(Format::Csv),
+            "html" => Ok(Format::Html),
+            "xml" => Ok(Format::Xml),
+            "txt" | "text" => Ok(Format::Txt),
+            format => Err(format!("{} isn't csv, html, xml or txt", format)),
+        }
+    }
+}
+
+/**
+ * Read the json lines written with --json, decompressed by the extension the
+ * --output-compress files get, or from stdin for -
+ */
+pub fn read_records(path: &str) -> io::Result<Vec<Record>> {
+    let reader: Box<dyn Read> = if path == "-" {
+        Box::new(io::stdin())
+    } else if path.ends_with(".gz") {
+        Box::new(MultiGzDecoder::new(File::open(path)?))
+    } else if path.ends_with(".zst") {
+        Box::new(zstd::stream::read::Decoder::new(File::open(path)?)?)
+    } else {
+        Box::new(File::open(path)?)
+    };
+
+    let mut records = vec![];
+    for (number, line) in BufReader::new(reader).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record = serde_json::from_str::<Record>(&line).map_err(|err| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("line {} isn't a result: {}", number + 1, err),
+            )
+        })?;
+        records.push(record);
+    }
+    Ok(records)
+}
+
+/**
+ * Quote a csv cell when it needs to be, and keep the spreadsheets from
+ * running the titles and servers of hostile pages as formulas
+ */
+fn csv_cell(value: &str) -> String {
+    let value = if value.starts_with(['=', '+', '-', '@']) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    };
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+/**
+ * Escape a value for the html report
+ */
+fn html_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/**
+ * The status of a record, or why it has none
+ */
+fn status(record: &Record) -> String {
+    match &record.error {
+        Some(error) if record.failed => format!("failed:{}", error.kind),
+        Some(error) => format!("error:{}", error.kind),
+        None => record.status_code.to_string(),
+    }
+}
+
+fn csv_row(record: &Record) -> Vec<String> {
+    let optional = |value: &Option<String>| value.clone().unwrap_or_default();
+    let number = |value: Option<u64>| value.map(|value| value.to_string()).unwrap_or_default();
+    vec![
+        record.timestamp.clone(),
+        record.url.clone(),
+        status(record),
+        optional(&record.title),
+        optional(&record.ip),
+        optional(&record.content_type),
+        number(record.content_length),
+        number(record.words),
+        number(record.lines),
+        optional(&record.server),
+        record.tech.join(","),
+        optional(&record.body_hash),
+        optional(&record.redirect_host),
+        record.tags.join(","),
+        record
+            .error
+            .as_ref()
+            .map(|error| error.message.clone())
+            .unwrap_or_default(),
+    ]
+}
+
+pub fn write_csv(records: &[Record], out: &mut dyn Write) -> io::Result<()> {
+    writeln!(out, "{}", CSV_COLUMNS.join(","))?;
+    for record in records {
+        let row = csv_row(record)
+            .iter()
+            .map(|cell| csv_cell(cell))
+            .collect::<Vec<String>>();
+        writeln!(out, "{}", row.join(","))?;
+    }
+    Ok(())
+}
+
+pub fn write_html(records: &[Record], out: &mut dyn Write) -> io::Result<()> {
+    writeln!(out, "<!DOCTYPE html>")?;
+    writeln!(out, "<html>\n<head>\n<meta charset=\"utf-8\">")?;
+    writeln!(out, "<title>hrekt results</title>")?;
+    writeln!(
+        out,
+        "<style>body{{font-family:sans-serif}}table{{border-collapse:collapse}}th,td{{border:1px solid #ccc;padding:4px 8px;text-align:left;vertical-align:top}}th{{background:#eee}}</style>"
+    )?;
+    writeln!(out, "</head>\n<body>")?;
+    writeln!(out, "<h1>hrekt results ({})</h1>", records.len())?;
+    writeln!(out, "<table>")?;
+    writeln!(
+        out,
+        "<tr><th>url</th><th>status</th><th>title</th><th>tech</th><th>content type</th><th>length</th><th>server</th><th>ip</th><th>tags</th></tr>"
+    )?;
+    for record in records {
+        let cell = |value: &Option<String>| html_escape(value.as_deref().unwrap_or_default());
+        writeln!(
+            out,
+            "<tr><td><a href=\"{url}\">{url}</a></td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            html_escape(&status(record)),
+            cell(&record.title),
+            html_escape(&record.tech.join(", ")),
+            cell(&record.content_type),
+            record
+                .content_length
+                .map(|length| length.to_string())
+                .unwrap_or_default(),
+            cell(&record.server),
+            cell(&record.ip),
+            html_escape(&record.tags.join(", ")),
+            url = html_escape(&record.url),
+        )?;
+    }
+    writeln!(out, "</table>\n</body>\n</html>")
+}
+
+/**
+ * A record as a line of the text output, without the colors
+ */
+fn txt_line(record: &Record) -> String {
+    let mut line = record.url.clone();
+    if let Some(title) = &record.title {
+        line.push_str(&format!(" [{}]", title));
+    }
+    line.push_str(&format!(" [{}]", status(record)));
+    if let Some(redirect_host) = &record.redirect_host {
+        line.push_str(&format!(" [redirect-host:{}]", redirect_host));
+    }
+    if !record.tech.is_empty() {
+        line.push_str(&format!(" [{}]", record.tech.join(",")));
+    }
+    if let Some(content_type) = &record.content_type {
+        line.push_str(&format!(" [{}]", content_type));
+    }
+    if let Some(content_length) = record.content_length {
+        line.push_str(&format!(" [{}]", content_length));
+    }
+    if let Some(server) = &record.server {
+        line.push_str(&format!(" [{}]", server));
+    }
+    if let Some(ip) = &record.ip {
+        line.push_str(&format!(" [{}]", ip));
+    }
+    for tag in &record.tags {
+        line.push_str(&format!(" [tag:{}]", tag));
+    }
+    line
+}
+
+fn write_txt(records: &[Record], out: &mut dyn Write) -> io::Result<()> {
+    for record in records {
+        writeln!(out, "{}", txt_line(record))?;
+    }
+    Ok(())
+}
+
+/**
+ * Write through a buffer, flushed so the errors of the last write show
+ */
+fn write_buffered(
+    records: &[Record],
+    out: Box<dyn Write + Send>,
+    write: fn(&[Record], &mut dyn Write) -> io::Result<()>,
+) -> io::Result<()> {
+    let mut out = BufWriter::new(out);
+    write(records, &mut out)?;
+    out.flush()
+}
+
+/**
+ * Write the records in the format, to the file or to stdout
+ */
+pub fn convert(records: &[Record], format: Format, output: Option<&str>) -> io::Result<()> {
+    let out: Box<dyn Write + Send> = match output {
+        Some(path) => Box::new(File::create(path)?),
+        None => Box::new(io::stdout()),
+    };
+    match format {
+        Format::Csv => write_buffered(records, out, write_csv),
+        Format::Html => write_buffered(records, out, write_html),
+        Format::Txt => write_buffered(records, out, write_txt),
+        Format::Xml => {
+            let xml = XmlReport::to_writer(out)?;
+            for record in records {
+                xml.record(record)?;
+            }
+            xml.finish()
+        }
+    }
+}
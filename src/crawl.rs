@@ -0,0 +1,213 @@
+use crate::{input::Target, Job};
+use reqwest::Url;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
+use tokio::task;
+
+// the links that point at static files aren't worth probing
+const STATIC_EXTENSIONS: [&str; 16] = [
+    ".png", ".jpg", ".jpeg", ".gif", ".svg", ".ico", ".webp", ".css", ".woff", ".woff2", ".ttf",
+    ".eot", ".mp4", ".mp3", ".pdf", ".zip",
+];
+
+/**
+ * Feeds the same-host links found in the pages, and the names found in the
+ * certificates, back into the job queue, closing the queue once every job,
+ * crawled or not, has been probed
+ */
+pub struct Crawl {
+    tx: async_channel::Sender<Job>,
+    max_depth: usize,
+    max_pages: usize,
+    pending: AtomicUsize,
+    seeding: AtomicBool,
+    seen: Mutex<HashSet<String>>,
+    pages: Mutex<HashMap<String, usize>>,
+    san_feedback: bool,
+    san_scope: Vec<String>,
+    hosts: Mutex<HashSet<String>>,
+}
+
+/**
+ * Marks a job as done when dropped, even when the worker panicked
+ */
+pub struct PendingGuard(Arc<Crawl>);
+
+impl Drop for PendingGuard {
+    fn drop(&mut self) {
+        self.0.job_done();
+    }
+}
+
+impl Crawl {
+    pub fn new(tx: async_channel::Sender<Job>, max_depth: usize, max_pages: usize) -> Self {
+        Crawl {
+            tx,
+            max_depth,
+            max_pages,
+            pending: AtomicUsize::new(0),
+            seeding: AtomicBool::new(true),
+            seen: Mutex::new(HashSet::new()),
+            pages: Mutex::new(HashMap::new()),
+            san_feedback: false,
+            san_scope: vec![],
+            hosts: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /**
+     * Also queue the certificate names not among the targets, only the ones
+     * ending in a scope suffix when any are given
+     */
+    pub fn with_san_feedback(mut self, san_scope: Vec<String>, targets: &[Target]) -> Self {
+        self.san_feedback = true;
+        self.san_scope = san_scope;
+        self.hosts = Mutex::new(
+            targets
+                .iter()
+                .map(|target| target.host.to_lowercase())
+                .collect(),
+        );
+        self
+    }
+
+    /**
+     * Count a job about to be queued
+     */
+    pub fn add_pending(&self) {
+        self.pending.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /**
+     * Guard a job received by a worker
+     */
+    pub fn pending_guard(self: &Arc<Self>) -> PendingGuard {
+        PendingGuard(self.clone())
+    }
+
+    /**
+     * Called once every target has been queued
+     */
+    pub fn seeding_done(&self) {
+        self.seeding.store(false, Ordering::SeqCst);
+        self.close_when_idle();
+    }
+
+    fn job_done(&self) {
+        self.pending.fetch_sub(1, Ordering::SeqCst);
+        self.close_when_idle();
+    }
+
+    fn close_when_idle(&self) {
+        if !self.seeding.load(Ordering::SeqCst) && self.pending.load(Ordering::SeqCst) == 0 {
+            self.tx.close();
+        }
+    }
+
+    /**
+     * Remember a url so it isn't crawled again, returns false when it was already seen
+     */
+    pub fn mark_seen(&self, url: &str) -> bool {
+        let url = match Url::parse(url) {
+            Ok(url) => url.to_string(),
+            Err(_) => url.to_string(),
+        };
+        let mut seen = self.seen.lock().unwrap();
+        seen.insert(url)
+    }
+
+    /**
+     * Queue the same-host links of a page found at the given depth
+     */
+    pub fn enqueue_links(self: &Arc<Self>, job: &Job, page: &Url, links: Vec<String>) {
+        let depth = job.depth;
+        if depth >= self.max_depth {
+            return;
+        }
+        for link in links {
+            let url = match Url::parse(&link) {
+                Ok(url) => url,
+                Err(_) => continue,
+            };
+            if url.host_str() != page.host_str()
+                || url.port_or_known_default() != page.port_or_known_default()
+            {
+                continue;
+            }
+            let path = url.path().to_lowercase();
+            if STATIC_EXTENSIONS.iter().any(|ext| path.ends_with(ext)) {
+                continue;
+            }
+            if !self.mark_seen(url.as_str()) {
+                continue;
+            }
+
+            // stop once the host has had its share of pages
+            let host = format!(
+                "{}:{}",
+                url.host_str().unwrap_or_default(),
+                url.port_or_known_default().unwrap_or_default()
+            );
+            {
+                let mut pages = self.pages.lock().unwrap();
+                let count = pages.entry(host).or_insert(0);
+                if *count >= self.max_pages {
+                    return;
+                }
+                *count += 1;
+            }
+
+            let mut msg = job.clone();
+            msg.url = Some(url.to_string());
+            msg.depth = depth + 1;
+
+            // send from a task as the queue may be full of jobs waiting on this worker
+            self.add_pending();
+            let crawl = self.clone();
+            task::spawn(async move {
+                if crawl.tx.send(msg).await.is_err() {
+                    crawl.job_done();
+                }
+            });
+        }
+    }
+
+    /**
+     * Queue the certificate names that weren't probed yet as new targets
+     */
+    pub fn enqueue_hosts(self: &Arc<Self>, job: &Job, names: Vec<String>) {
+        if !self.san_feedback {
+            return;
+        }
+        for name in names {
+            let in_scope = self.san_scope.is_empty()
+                || self
+                    .san_scope
+                    .iter()
+                    .any(|suffix| name == *suffix || name.ends_with(&format!(".{}", suffix)));
+            if !in_scope || !self.hosts.lock().unwrap().insert(name.clone()) {
+                continue;
+            }
+
+            let mut msg = job.clone();
+            msg.host = Some(name);
+            msg.url = None;
+            msg.depth = 0;
+            // not a target of the input, the checkpoint doesn't count it
+            msg.target_index = None;
+
+            self.add_pending();
+            let crawl = self.clone();
+            task::spawn(async move {
+                if crawl.tx.send(msg).await.is_err() {
+                    crawl.job_done();
+                }
+            });
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use crate::ProbeResult;
+use std::{collections::HashMap, sync::Mutex};
+
+/**
+ * Remembers which bodies have been emitted so duplicates can be dropped,
+ * holding the first result of each body back until the scan ends so it can
+ * carry the count of the ones it stood in for
+ */
+#[derive(Debug, Default)]
+pub struct UniqueFilter {
+    // how many results had each body hash
+    seen: Mutex<HashMap<String, usize>>,
+    // the first result of each body, with whether it prints its status code
+    held: Mutex<Vec<(ProbeResult, bool)>>,
+}
+
+impl UniqueFilter {
+    /**
+     * Returns true the first time a body hash is seen, later calls only count the duplicate
+     */
+    pub fn first_seen(&self, hash: &str) -> bool {
+        let mut seen = self.seen.lock().unwrap();
+        match seen.get_mut(hash) {
+            Some(count) => {
+                *count += 1;
+                false
+            }
+            None => {
+                seen.insert(hash.to_string(), 1);
+                true
+            }
+        }
+    }
+
+    /**
+     * Keep the first result of a body until release, false for the results
+     * already counted or that have no body
+     */
+    pub fn hold(&self, result: &ProbeResult, status_codes: bool) -> bool {
+        if result.unique_hash.is_none() || result.duplicates.is_some() {
+            return false;
+        }
+        self.held
+            .lock()
+            .unwrap()
+            .push((result.clone(), status_codes));
+        true
+    }
+
+    /**
+     * The held results, each with how many other results had its body
+     */
+    pub fn release(&self) -> Vec<(ProbeResult, bool)> {
+        let seen = self.seen.lock().unwrap();
+        let mut held = std::mem::take(&mut *self.held.lock().unwrap());
+        for (result, _) in &mut held {
+            let count = result
+                .unique_hash
+                .as_ref()
+                .and_then(|hash| seen.get(hash))
+                .copied()
+                .unwrap_or(1);
+            result.duplicates = Some(count - 1);
+        }
+        held
+    }
+}
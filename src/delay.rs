@@ -0,0 +1,127 @@
+use rand::Rng;
+use std::time::Duration;
+
+/**
+ * A pause taken before every request, a fixed delay plus a random jitter
+ */
+#[derive(Clone, Debug)]
+pub struct Delay {
+    fixed: Duration,
+    jitter: Option<(Duration, Duration)>,
+}
+
+/**
+ * Parse a duration such as 200ms, 2s or 1m, a bare number is taken as
+ * milliseconds
+ */
+pub fn parse_duration(duration: &str) -> Option<Duration> {
+    let duration = duration.trim();
+    let (number, millis) = if let Some(number) = duration.strip_suffix("ms") {
+        (number, 1)
+    } else if let Some(number) = duration.strip_suffix('s') {
+        (number, 1000)
+    } else if let Some(number) = duration.strip_suffix('m') {
+        (number, 60_000)
+    } else {
+        (duration, 1)
+    };
+    let number = number.trim().parse::<u64>().ok()?;
+    Some(Duration::from_millis(number.checked_mul(millis)?))
+}
+
+/**
+ * Parse a jitter range such as 0-500ms or 100ms-1s, a unit given only at the
+ * end applies to both ends
+ */
+fn parse_range(range: &str) -> Option<(Duration, Duration)> {
+    let (low, high) = range.split_once('-')?;
+    let low = if low.trim().chars().all(|c| c.is_ascii_digit()) {
+        let unit = high.trim().trim_start_matches(|c: char| c.is_ascii_digit());
+        parse_duration(&format!("{}{}", low.trim(), unit))?
+    } else {
+        parse_duration(low)?
+    };
+    let high = parse_duration(high)?;
+    if low > high {
+        return None;
+    }
+    Some((low, high))
+}
+
+impl Delay {
+    /**
+     * Build the delay from --delay and --jitter, None when neither is set
+     */
+    pub fn parse(delay: Option<&str>, jitter: Option<&str>) -> Result<Option<Delay>, String> {
+        if delay.is_none() && jitter.is_none() {
+            return Ok(None);
+        }
+        let fixed = match delay {
+            Some(delay) => parse_duration(delay).ok_or(format!("invalid delay: {}", delay))?,
+            None => Duration::ZERO,
+        };
+        let jitter = match jitter {
+            Some(jitter) => Some(parse_range(jitter).ok_or(format!("invalid jitter: {}", jitter))?),
+            None => None,
+        };
+        Ok(Some(Delay { fixed, jitter }))
+    }
+
+    /**
+     * The delay plus a random pick from the jitter range
+     */
+    pub fn pause(&self) -> Duration {
+        let mut pause = self.fixed;
+        if let Some((low, high)) = self.jitter {
+            pause += rand::thread_rng().gen_range(low..=high);
+        }
+        pause
+    }
+
+    /**
+     * Sleep for the delay plus a random pick from the jitter range
+     */
+    pub async fn wait(&self) {
+        let pause = self.pause();
+        if !pause.is_zero() {
+            tokio::time::sleep(pause).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_the_units() {
+        assert_eq!(parse_duration("200ms"), Some(Duration::from_millis(200)));
+        assert_eq!(parse_duration(" 2s "), Some(Duration::from_secs(2)));
+        assert_eq!(parse_duration("1m"), Some(Duration::from_secs(60)));
+        assert_eq!(parse_duration("150"), Some(Duration::from_millis(150)));
+        for duration in ["", "ms", "-1s", "1h", "1.5s"] {
+            assert_eq!(parse_duration(duration), None, "{}", duration);
+        }
+    }
+
+    #[test]
+    fn refuses_a_duration_too_long_to_count() {
+        assert_eq!(parse_duration("307445734561826m"), None);
+        assert_eq!(parse_duration("18446744073709552s"), None);
+        assert!(parse_duration("307445734561825m").is_some());
+    }
+
+    #[test]
+    fn parses_the_jitter_range() {
+        assert_eq!(
+            parse_range("0-500ms"),
+            Some((Duration::ZERO, Duration::from_millis(500)))
+        );
+        assert_eq!(
+            parse_range("100ms-1s"),
+            Some((Duration::from_millis(100), Duration::from_secs(1)))
+        );
+        assert_eq!(parse_range("2s-1s"), None);
+        assert_eq!(parse_range("500ms"), None);
+    }
+}
@@ -0,0 +1,143 @@
+use crate::{merge::merge_records, output::Record};
+use colored::Colorize;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/**
+ * Whether a url is only in the new results, only in the old ones, or in
+ * both with other values
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    New,
+    Gone,
+    Changed,
+}
+
+/**
+ * How a url changed between two runs
+ */
+#[derive(Serialize)]
+pub struct Change {
+    pub change: ChangeKind,
+    pub url: String,
+    pub fields: Vec<FieldChange>,
+}
+
+/**
+ * A field of a url that changed, as it was and as it is
+ */
+#[derive(Serialize)]
+pub struct FieldChange {
+    pub field: &'static str,
+    pub old: String,
+    pub new: String,
+}
+
+/**
+ * The fields compared, by name, as text
+ */
+fn fields(record: &Record) -> Vec<(&'static str, String)> {
+    let optional = |value: &Option<String>| value.clone().unwrap_or_default();
+    let mut tech = record.tech.clone();
+    tech.sort();
+    vec![
+        ("status_code", record.status_code.to_string()),
+        ("title", optional(&record.title)),
+        (
+            "content_length",
+            record
+                .content_length
+                .map(|length| length.to_string())
+                .unwrap_or_default(),
+        ),
+        ("body_hash", optional(&record.body_hash)),
+        ("server", optional(&record.server)),
+        ("tech", tech.join(",")),
+        (
+            "error",
+            record
+                .error
+                .as_ref()
+                .map(|error| error.kind.clone())
+                .unwrap_or_default(),
+        ),
+    ]
+}
+
+/**
+ * The urls that are new, gone or changed from the old results to the new
+ * ones, each side taken at its most recent result of a url
+ */
+pub fn diff(old: Vec<Record>, new: Vec<Record>) -> Vec<Change> {
+    let old = merge_records(old);
+    let new = merge_records(new);
+    let old_by_url: HashMap<&str, &Record> = old
+        .iter()
+        .map(|record| (record.url.as_str(), record))
+        .collect();
+    let new_by_url: HashMap<&str, &Record> = new
+        .iter()
+        .map(|record| (record.url.as_str(), record))
+        .collect();
+
+    let mut changes = vec![];
+    for record in &new {
+        match old_by_url.get(record.url.as_str()) {
+            None => changes.push(Change {
+                change: ChangeKind::New,
+                url: record.url.clone(),
+                fields: vec![],
+            }),
+            Some(before) => {
+                let changed = fields(before)
+                    .into_iter()
+                    .zip(fields(record))
+                    .filter(|((_, old), (_, new))| old != new)
+                    .map(|((field, old), (_, new))| FieldChange { field, old, new })
+                    .collect::<Vec<FieldChange>>();
+                if !changed.is_empty() {
+                    changes.push(Change {
+                        change: ChangeKind::Changed,
+                        url: record.url.clone(),
+                        fields: changed,
+                    });
+                }
+            }
+        }
+    }
+    for record in &old {
+        if !new_by_url.contains_key(record.url.as_str()) {
+            changes.push(Change {
+                change: ChangeKind::Gone,
+                url: record.url.clone(),
+                fields: vec![],
+            });
+        }
+    }
+    changes
+}
+
+/**
+ * A change as a line of the text output
+ */
+pub fn format_change(change: &Change) -> String {
+    match change.change {
+        ChangeKind::New => format!("{} {}", "[new]".green(), change.url),
+        ChangeKind::Gone => format!("{} {}", "[gone]".red(), change.url),
+        ChangeKind::Changed => {
+            let fields = change
+                .fields
+                .iter()
+                .map(|field| format!("[{}:{} -> {}]", field.field, field.old, field.new))
+                .collect::<Vec<String>>();
+            format!(
+                "{} {} {}",
+                "[changed]".yellow(),
+                change.url,
+                fields.join(" ")
+            )
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use crate::{fetch_method, Shared};
+use rand::{distributions::Alphanumeric, Rng};
+use reqwest::{header::HeaderMap, Method};
+use std::{
+    collections::HashSet,
+    fs::File,
+    io::{self, BufRead, BufReader},
+    sync::Mutex,
+};
+
+/**
+ * A small wordlist tried on every origin that answers, each origin once
+ */
+pub struct Discover {
+    words: Vec<String>,
+    origins: Mutex<HashSet<String>>,
+}
+
+/**
+ * What the origin answers for a path that can't exist
+ */
+struct Baseline {
+    status: u16,
+    length: usize,
+}
+
+/**
+ * A path made up to see how the origin answers for what it doesn't have
+ */
+fn random_path(extension: &str) -> String {
+    let name: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(16)
+        .map(char::from)
+        .collect();
+    format!("/{}{}", name.to_lowercase(), extension)
+}
+
+/**
+ * The status and the size of the body of a path
+ */
+async fn fetch(
+    client: &reqwest::Client,
+    url: &str,
+    timeout: Option<usize>,
+    shared: &Shared,
+) -> Option<(u16, usize)> {
+    let resp = fetch_method(client, Method::GET, url, HeaderMap::new(), timeout, shared).await?;
+    let status = resp.status().as_u16();
+    let length = resp.bytes().await.ok()?.len();
+    Some((status, length))
+}
+
+impl Baseline {
+    /**
+     * Whether a response is the same not found page, the length may differ
+     * by the path echoed back in it
+     */
+    fn matches(&self, status: u16, length: usize, word: &str) -> bool {
+        let tolerance = word.len() * 2 + self.length / 50;
+        status == self.status && length.abs_diff(self.length) <= tolerance
+    }
+}
+
+impl Discover {
+    /**
+     * Read the first limit words of the wordlist, the blank lines and the
+     * comments skipped
+     */
+    pub fn load(path: &str, limit: usize) -> io::Result<Discover> {
+        let mut words = vec![];
+        for line in BufReader::new(File::open(path)?).lines() {
+            if words.len() >= limit {
+                break;
+            }
+            let line = line?;
+            let word = line.trim().trim_start_matches('/');
+            if !word.is_empty() && !word.starts_with('#') {
+                words.push(word.to_string());
+            }
+        }
+        Ok(Discover {
+            words,
+            origins: Mutex::new(HashSet::new()),
+        })
+    }
+
+    /**
+     * Mark the origin as brute-forced, returns false when it already was
+     */
+    pub fn first_visit(&self, origin: &str) -> bool {
+        self.origins.lock().unwrap().insert(origin.to_string())
+    }
+
+    /**
+     * The paths of the wordlist the origin answers with something else than
+     * its not found page, calibrated on two paths that can't exist since so
+     * many hosts answer 200 for everything
+     */
+    pub async fn found_paths(
+        &self,
+        client: &reqwest::Client,
+        base: &str,
+        timeout: Option<usize>,
+        shared: &Shared,
+    ) -> Vec<String> {
+        let mut baselines = vec![];
+        for path in [random_path(""), random_path(".html")] {
+            match fetch(client, &format!("{}{}", base, path), timeout, shared).await {
+                Some((status, length)) => baselines.push(Baseline { status, length }),
+                // a host that can't answer twice in a row isn't worth the wordlist
+                None => return vec![],
+            }
+        }
+
+        let mut found = vec![];
+        for word in &self.words {
+            let path = format!("/{}", word);
+            let (status, length) =
+                match fetch(client, &format!("{}{}", base, path), timeout, shared).await {
+                    Some(answer) => answer,
+                    None => continue,
+                };
+            if status == 404
+                || baselines
+                    .iter()
+                    .any(|baseline| baseline.matches(status, length, word))
+            {
+                continue;
+            }
+            found.push(path);
+        }
+        found
+    }
+}
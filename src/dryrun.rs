@@ -0,0 +1,91 @@
+use crate::{
+    input::{self, Target},
+    schemes, Job, Settings,
+};
+use colored::Colorize;
+
+/**
+ * The urls a target expands to, before any dns lookups
+ */
+fn expand_target(target: &Target, job: &Job, settings: &Settings) -> Vec<String> {
+    let ports = target.ports.clone().unwrap_or_else(|| job.ports.clone());
+    let path = target.path.clone().unwrap_or_else(|| job.path.clone());
+    let paths = input::expand_braces(&path).unwrap_or_else(|_| vec![path]);
+
+    let mut urls = vec![];
+    for port in ports.split(',').map(|port| port.trim()) {
+        if port.is_empty() {
+            continue;
+        }
+        let schemes = match target.scheme.as_deref() {
+            Some(scheme) => vec![scheme],
+            // the unknown ports can't be detected without connecting
+            None => settings
+                .port_schemes
+                .schemes(port)
+                .unwrap_or_else(|| schemes::BOTH.to_vec()),
+        };
+        for scheme in schemes {
+            for path in &paths {
+                urls.push(format!("{}://{}:{}{}", scheme, target.host, port, path));
+            }
+        }
+    }
+    urls
+}
+
+/**
+ * Print the urls the scan would probe along with the number of requests and
+ * how long sending them would take at the configured rate
+ */
+pub fn dry_run(targets: &[Target], job: &Job, settings: &Settings) {
+    let mut urls = 0;
+    for target in targets {
+        for url in expand_target(target, job, settings) {
+            println!("{}", url);
+            urls += 1;
+        }
+    }
+
+    // the extra requests made for every url
+    let mut per_url = 1;
+    if job.methods_discovery {
+        per_url += 1;
+    }
+    if job.js_scrape {
+        per_url += job.js_max_files;
+    }
+
+    let seconds = targets.len() as u64 / settings.rate.max(1) as u64;
+    eprintln!(
+        "{}",
+        format!(
+            "[dry-run] {} targets expand to {} urls, up to {} requests",
+            targets.len(),
+            urls,
+            urls * per_url
+        )
+        .white()
+    );
+    eprintln!(
+        "{}",
+        format!(
+            "[dry-run] about {:02}:{:02}:{:02} to send them at {} targets per second",
+            seconds / 3600,
+            seconds % 3600 / 60,
+            seconds % 60,
+            settings.rate
+        )
+        .white()
+    );
+    if settings.crawl_depth > 0 {
+        eprintln!(
+            "{}",
+            format!(
+                "[dry-run] the crawl may add up to {} pages per host",
+                settings.crawl_max_pages
+            )
+            .white()
+        );
+    }
+}
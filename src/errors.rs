@@ -0,0 +1,113 @@
+use crate::schemes;
+use serde::{Deserialize, Serialize};
+use std::{error::Error, io};
+
+/**
+ * Why a probe failed, as --include-errors reports it
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum ErrorKind {
+    DnsNxdomain,
+    DnsTimeout,
+    // the host resolved, but not to an address of the family probed
+    DnsNoAddress,
+    TcpRefused,
+    TcpReset,
+    TcpTimeout,
+    TlsError,
+    HttpTimeout,
+    TooManyRedirects,
+    NonHttp,
+    Other,
+}
+
+/**
+ * A probe that failed, with the kind and the message of the error
+ */
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ProbeError {
+    pub kind: ErrorKind,
+    pub message: String,
+}
+
+impl ErrorKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorKind::DnsNxdomain => "dns_nxdomain",
+            ErrorKind::DnsTimeout => "dns_timeout",
+            ErrorKind::DnsNoAddress => "dns_no_address",
+            ErrorKind::TcpRefused => "tcp_refused",
+            ErrorKind::TcpReset => "tcp_reset",
+            ErrorKind::TcpTimeout => "tcp_timeout",
+            ErrorKind::TlsError => "tls_error",
+            ErrorKind::HttpTimeout => "http_timeout",
+            ErrorKind::TooManyRedirects => "too_many_redirects",
+            ErrorKind::NonHttp => "non_http",
+            ErrorKind::Other => "other",
+        }
+    }
+}
+
+impl ProbeError {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> ProbeError {
+        ProbeError {
+            kind,
+            message: message.into(),
+        }
+    }
+
+    /**
+     * Classify a failed request by the errors it was caused by, the client
+     * only tells the timeouts and redirects apart itself
+     */
+    pub fn from_reqwest(err: &reqwest::Error) -> ProbeError {
+        let mut messages = vec![err.to_string()];
+        let mut io_kind = None;
+        let mut source = err.source();
+        while let Some(err) = source {
+            messages.push(err.to_string());
+            if let Some(err) = err.downcast_ref::<io::Error>() {
+                io_kind = io_kind.or(Some(err.kind()));
+            }
+            source = err.source();
+        }
+        let message = messages.join(": ");
+        let lowercase = message.to_lowercase();
+
+        let kind = if err.is_redirect() {
+            ErrorKind::TooManyRedirects
+        } else if schemes::is_non_http(err) {
+            ErrorKind::NonHttp
+        } else if lowercase.contains("dns error") || lowercase.contains("failed to lookup") {
+            if lowercase.contains("timed out") || lowercase.contains("temporary") {
+                ErrorKind::DnsTimeout
+            } else {
+                ErrorKind::DnsNxdomain
+            }
+        } else if io_kind == Some(io::ErrorKind::ConnectionRefused) {
+            ErrorKind::TcpRefused
+        } else if matches!(
+            io_kind,
+            Some(io::ErrorKind::ConnectionReset | io::ErrorKind::ConnectionAborted)
+        ) {
+            ErrorKind::TcpReset
+        } else if err.is_timeout() || io_kind == Some(io::ErrorKind::TimedOut) {
+            if err.is_connect() {
+                ErrorKind::TcpTimeout
+            } else {
+                ErrorKind::HttpTimeout
+            }
+        } else if ["ssl", "tls", "certificate", "handshake"]
+            .iter()
+            .any(|word| lowercase.contains(word))
+        {
+            ErrorKind::TlsError
+        } else {
+            ErrorKind::Other
+        };
+        ProbeError::new(kind, message)
+    }
+}
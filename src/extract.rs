@@ -0,0 +1,93 @@
+use regex::Regex;
+use reqwest::Url;
+
+/**
+ * Whether the domain is the host, one of its subdomains or one of its parents
+ */
+fn in_scope(domain: &str, host: &str) -> bool {
+    let domain = domain.trim_end_matches('.').to_lowercase();
+    let host = host.trim_end_matches('.').to_lowercase();
+    domain == host
+        || domain.ends_with(&format!(".{}", host))
+        || host.ends_with(&format!(".{}", domain))
+}
+
+/**
+ * Push the value unless it was already found
+ */
+fn push_unique(values: &mut Vec<String>, value: String) {
+    if !values.contains(&value) {
+        values.push(value);
+    }
+}
+
+/**
+ * Find the links in the body, relative ones are resolved against the page url
+ */
+pub fn extract_links(body: &str, base: &Url, scoped: bool) -> Vec<String> {
+    let mut links = Vec::new();
+    let attribute = match Regex::new(r#"(?i)(?:href|src|action)\s*=\s*["']([^"'<>\s]+)["']"#) {
+        Ok(re) => re,
+        Err(_) => return links,
+    };
+    let absolute = match Regex::new(r#"https?://[^\s"'<>()\\]+"#) {
+        Ok(re) => re,
+        Err(_) => return links,
+    };
+
+    let found = attribute
+        .captures_iter(body)
+        .filter_map(|cap| cap.get(1))
+        .chain(absolute.find_iter(body));
+    for link in found {
+        let link = link.as_str();
+        let lower = link.to_lowercase();
+        if link.starts_with('#')
+            || ["javascript:", "mailto:", "data:", "tel:"]
+                .iter()
+                .any(|scheme| lower.starts_with(scheme))
+        {
+            continue;
+        }
+        let mut url = match base.join(link) {
+            Ok(url) => url,
+            Err(_) => continue,
+        };
+        url.set_fragment(None);
+        if url.scheme() != "http" && url.scheme() != "https" {
+            continue;
+        }
+        if scoped {
+            match (url.host_str(), base.host_str()) {
+                (Some(domain), Some(host)) if in_scope(domain, host) => {}
+                _ => continue,
+            }
+        }
+        push_unique(&mut links, url.to_string());
+    }
+    links
+}
+
+/**
+ * Find the email addresses in the body
+ */
+pub fn extract_emails(body: &str, host: &str, scoped: bool) -> Vec<String> {
+    let mut emails = Vec::new();
+    let re =
+        match Regex::new(r"[A-Za-z0-9._%+-]+@([A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,})") {
+            Ok(re) => re,
+            Err(_) => return emails,
+        };
+    for cap in re.captures_iter(body) {
+        let email = cap[0].to_lowercase();
+        // skip the retina images, e.g. logo@2x.png
+        if email.ends_with(".png") || email.ends_with(".jpg") || email.ends_with(".gif") {
+            continue;
+        }
+        if scoped && !in_scope(&cap[1], host) {
+            continue;
+        }
+        push_unique(&mut emails, email);
+    }
+    emails
+}
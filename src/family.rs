@@ -0,0 +1,77 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+};
+
+/**
+ * An address family a host can be probed over
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IpFamily {
+    V4,
+    V6,
+}
+
+impl IpFamily {
+    /**
+     * The unspecified local address of the family, binding to it keeps the
+     * client to the addresses of that family
+     */
+    pub fn local_address(&self) -> IpAddr {
+        match self {
+            IpFamily::V4 => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            IpFamily::V6 => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+        }
+    }
+
+    fn of(addr: &SocketAddr) -> IpFamily {
+        if addr.is_ipv4() {
+            IpFamily::V4
+        } else {
+            IpFamily::V6
+        }
+    }
+}
+
+impl fmt::Display for IpFamily {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IpFamily::V4 => write!(f, "ipv4"),
+            IpFamily::V6 => write!(f, "ipv6"),
+        }
+    }
+}
+
+/**
+ * Which of the address families a host resolved to get probed
+ */
+#[derive(Clone, Copy, Debug)]
+pub enum IpPreference {
+    Ipv4,
+    Ipv6,
+    Both,
+}
+
+impl IpPreference {
+    /**
+     * The families to probe a host over, the preferred one when both
+     * resolved, the other one when it is all there is, or every one of them
+     */
+    pub fn families(&self, addrs: &[SocketAddr]) -> Vec<IpFamily> {
+        let has = |family: IpFamily| addrs.iter().any(|addr| IpFamily::of(addr) == family);
+        let (first, second) = match self {
+            IpPreference::Ipv4 | IpPreference::Both => (IpFamily::V4, IpFamily::V6),
+            IpPreference::Ipv6 => (IpFamily::V6, IpFamily::V4),
+        };
+        let mut families = vec![];
+        if has(first) {
+            families.push(first);
+        }
+        if has(second) && (families.is_empty() || matches!(self, IpPreference::Both)) {
+            families.push(second);
+        }
+        families
+    }
+}
@@ -0,0 +1,307 @@
+use regex::Regex;
+use reqwest::{header::HeaderMap, Url};
+use serde_json::{Map, Value};
+use std::fs;
+
+/**
+ * A technology of the fingerprint file and the rules that give it away
+ */
+struct Technology {
+    name: String,
+    categories: Vec<String>,
+    headers: Vec<(String, Rule)>,
+    cookies: Vec<(String, Rule)>,
+    meta: Vec<(String, Rule)>,
+    html: Vec<Rule>,
+    script_src: Vec<Rule>,
+    url: Vec<Rule>,
+    implies: Vec<(String, u8)>,
+}
+
+/**
+ * A pattern with how sure a match of it makes the technology
+ */
+struct Rule {
+    regex: Regex,
+    confidence: u8,
+}
+
+/**
+ * A technology found in a response
+ */
+pub struct Detection {
+    pub name: String,
+    pub categories: Vec<String>,
+    pub confidence: u8,
+}
+
+/**
+ * The technologies of a fingerprint file in the wappalyzer format, matched
+ * against the responses next to the built-in set of --tech-detect
+ */
+pub struct Fingerprints {
+    technologies: Vec<Technology>,
+    // the patterns using regex features the regex crate doesn't have
+    pub skipped: usize,
+}
+
+/**
+ * Split the \;version: and \;confidence: tags off a pattern, the confidence
+ * is 100 unless it says otherwise
+ */
+fn split_tags(pattern: &str) -> (&str, u8) {
+    let mut tags = pattern.split("\\;");
+    let pattern = tags.next().unwrap_or_default();
+    let confidence = tags
+        .find_map(|tag| tag.strip_prefix("confidence:"))
+        .and_then(|confidence| confidence.parse::<u8>().ok())
+        .unwrap_or(100)
+        .min(100);
+    (pattern, confidence)
+}
+
+/**
+ * The strings of a field that is either a string or an array of them
+ */
+fn strings(value: Option<&Value>) -> Vec<&str> {
+    match value {
+        Some(Value::String(value)) => vec![value.as_str()],
+        Some(Value::Array(values)) => values.iter().filter_map(|value| value.as_str()).collect(),
+        _ => vec![],
+    }
+}
+
+impl Fingerprints {
+    /**
+     * Read a technologies.json, either the bare map of technologies or one
+     * under a "technologies" or "apps" key with the names of the category
+     * ids under "categories"
+     */
+    pub fn load(path: &str) -> Result<Fingerprints, String> {
+        let contents = fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err))?;
+        let json: Value =
+            serde_json::from_str(&contents).map_err(|err| format!("{}: {}", path, err))?;
+        let technologies = json
+            .get("technologies")
+            .or_else(|| json.get("apps"))
+            .unwrap_or(&json)
+            .as_object()
+            .ok_or_else(|| format!("{}: no technologies found", path))?;
+
+        let empty = Map::new();
+        let categories = json
+            .get("categories")
+            .and_then(|categories| categories.as_object())
+            .unwrap_or(&empty);
+
+        let mut fingerprints = Fingerprints {
+            technologies: vec![],
+            skipped: 0,
+        };
+        for (name, rules) in technologies {
+            let technology = fingerprints.technology(name, rules, categories);
+            fingerprints.technologies.push(technology);
+        }
+        Ok(fingerprints)
+    }
+
+    fn technology(
+        &mut self,
+        name: &str,
+        rules: &Value,
+        categories: &Map<String, Value>,
+    ) -> Technology {
+        Technology {
+            name: name.to_string(),
+            // the ids are left as they are when the file doesn't name them
+            categories: match rules.get("cats").and_then(|cats| cats.as_array()) {
+                Some(cats) => cats
+                    .iter()
+                    .map(|id| {
+                        let id = id.to_string();
+                        categories
+                            .get(&id)
+                            .and_then(|category| category.get("name"))
+                            .and_then(|name| name.as_str())
+                            .map_or(id, |name| name.to_string())
+                    })
+                    .collect(),
+                None => vec![],
+            },
+            headers: self.named_patterns(rules.get("headers")),
+            cookies: self.named_patterns(rules.get("cookies")),
+            meta: self.named_patterns(rules.get("meta")),
+            html: self.patterns(rules.get("html")),
+            script_src: self.patterns(rules.get("scriptSrc").or_else(|| rules.get("scripts"))),
+            url: self.patterns(rules.get("url")),
+            implies: strings(rules.get("implies"))
+                .into_iter()
+                .map(|implied| {
+                    let (implied, confidence) = split_tags(implied);
+                    (implied.to_string(), confidence)
+                })
+                .collect(),
+        }
+    }
+
+    /**
+     * Compile a pattern, keeping the confidence of its tags
+     */
+    fn pattern(&mut self, pattern: &str) -> Option<Rule> {
+        let (pattern, confidence) = split_tags(pattern);
+        match Regex::new(&format!("(?i){}", pattern)) {
+            Ok(regex) => Some(Rule { regex, confidence }),
+            Err(_) => {
+                self.skipped += 1;
+                None
+            }
+        }
+    }
+
+    fn patterns(&mut self, value: Option<&Value>) -> Vec<Rule> {
+        strings(value)
+            .into_iter()
+            .filter_map(|pattern| self.pattern(pattern))
+            .collect()
+    }
+
+    fn named_patterns(&mut self, value: Option<&Value>) -> Vec<(String, Rule)> {
+        let empty = Map::new();
+        let named = value.and_then(|value| value.as_object()).unwrap_or(&empty);
+        named
+            .iter()
+            .filter_map(|(name, pattern)| {
+                let pattern = self.pattern(pattern.as_str().unwrap_or_default())?;
+                Some((name.to_lowercase(), pattern))
+            })
+            .collect()
+    }
+
+    /**
+     * The technologies found in the response with the ones they imply, each
+     * as sure as its matched patterns add up to
+     */
+    pub fn detect(&self, url: &Url, headers: &HeaderMap, body: &str) -> Vec<Detection> {
+        let cookies = cookies(headers);
+        let meta = meta_tags(body);
+        let script_src = script_sources(body);
+
+        let mut found: Vec<(&Technology, u8)> = vec![];
+        for technology in &self.technologies {
+            let header_hits = technology.headers.iter().filter(|(name, rule)| {
+                headers
+                    .get_all(name.as_str())
+                    .iter()
+                    .filter_map(|value| value.to_str().ok())
+                    .any(|value| rule.regex.is_match(value))
+            });
+            let cookie_hits = technology.cookies.iter().filter(|(name, rule)| {
+                cookies
+                    .iter()
+                    .any(|(cookie, value)| cookie == name && rule.regex.is_match(value))
+            });
+            let meta_hits = technology.meta.iter().filter(|(name, rule)| {
+                meta.iter()
+                    .any(|(meta, content)| meta == name && rule.regex.is_match(content))
+            });
+            let hits = header_hits
+                .chain(cookie_hits)
+                .chain(meta_hits)
+                .map(|(_, rule)| rule)
+                .chain(
+                    technology
+                        .html
+                        .iter()
+                        .filter(|rule| rule.regex.is_match(body)),
+                )
+                .chain(
+                    technology
+                        .script_src
+                        .iter()
+                        .filter(|rule| script_src.iter().any(|src| rule.regex.is_match(src))),
+                )
+                .chain(
+                    technology
+                        .url
+                        .iter()
+                        .filter(|rule| rule.regex.is_match(url.as_str())),
+                )
+                .map(|rule| rule.confidence as u32)
+                .collect::<Vec<u32>>();
+            if !hits.is_empty() {
+                found.push((technology, hits.iter().sum::<u32>().min(100) as u8));
+            }
+        }
+
+        // what the found technologies run on, and in turn what that runs on,
+        // never surer than what implied them
+        let mut i = 0;
+        while i < found.len() {
+            let (technology, confidence) = found[i];
+            for (implied, implied_confidence) in &technology.implies {
+                let confidence = confidence.min(*implied_confidence);
+                match found.iter_mut().find(|(t, _)| t.name == *implied) {
+                    Some((_, found_confidence)) => {
+                        *found_confidence = (*found_confidence).max(confidence);
+                    }
+                    None => {
+                        if let Some(implied) = self.technologies.iter().find(|t| t.name == *implied)
+                        {
+                            found.push((implied, confidence));
+                        }
+                    }
+                }
+            }
+            i += 1;
+        }
+        found
+            .into_iter()
+            .map(|(technology, confidence)| Detection {
+                name: technology.name.clone(),
+                categories: technology.categories.clone(),
+                confidence,
+            })
+            .collect()
+    }
+}
+
+/**
+ * The names and values of the cookies the response sets
+ */
+fn cookies(headers: &HeaderMap) -> Vec<(String, String)> {
+    headers
+        .get_all("set-cookie")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .filter_map(|cookie| {
+            let (name, value) = cookie.split(';').next()?.split_once('=')?;
+            Some((name.trim().to_lowercase(), value.trim().to_string()))
+        })
+        .collect()
+}
+
+/**
+ * The name and content of the meta tags of the page
+ */
+fn meta_tags(body: &str) -> Vec<(String, String)> {
+    let tag = Regex::new(r"(?i)<meta\s[^>]*>").unwrap();
+    let name = Regex::new(r#"(?i)\b(?:name|property)\s*=\s*["']([^"']+)["']"#).unwrap();
+    let content = Regex::new(r#"(?i)\bcontent\s*=\s*["']([^"']*)["']"#).unwrap();
+    tag.find_iter(body)
+        .filter_map(|tag| {
+            let name = name.captures(tag.as_str())?.get(1)?.as_str().to_lowercase();
+            let content = content.captures(tag.as_str())?.get(1)?.as_str().to_string();
+            Some((name, content))
+        })
+        .collect()
+}
+
+/**
+ * The src of the script tags of the page
+ */
+fn script_sources(body: &str) -> Vec<String> {
+    let src = Regex::new(r#"(?i)<script\s[^>]*\bsrc\s*=\s*["']([^"']+)["']"#).unwrap();
+    src.captures_iter(body)
+        .filter_map(|captures| captures.get(1).map(|src| src.as_str().to_string()))
+        .collect()
+}
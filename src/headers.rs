@@ -0,0 +1,243 @@
+use chrono::{DateTime, Utc};
+use reqwest::header::{HeaderMap, HeaderValue, IF_MODIFIED_SINCE, IF_NONE_MATCH, SET_COOKIE};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, sync::Mutex};
+
+/**
+ * The Strict-Transport-Security policy of a response
+ */
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Hsts {
+    pub present: bool,
+    pub max_age: Option<u64>,
+    pub include_subdomains: bool,
+    pub preload: bool,
+}
+
+impl Hsts {
+    /**
+     * Parse the Strict-Transport-Security header of a response
+     */
+    pub fn from_headers(headers: &HeaderMap) -> Hsts {
+        let value = match headers
+            .get("Strict-Transport-Security")
+            .and_then(|value| value.to_str().ok())
+        {
+            Some(value) => value,
+            None => return Hsts::default(),
+        };
+
+        let mut hsts = Hsts {
+            present: true,
+            ..Default::default()
+        };
+        for directive in value.split(';') {
+            let directive = directive.trim().to_lowercase();
+            if let Some(max_age) = directive.strip_prefix("max-age") {
+                let max_age = max_age.trim_start().trim_start_matches('=').trim();
+                hsts.max_age = max_age.trim_matches('"').parse::<u64>().ok();
+            } else if directive == "includesubdomains" {
+                hsts.include_subdomains = true;
+            } else if directive == "preload" {
+                hsts.preload = true;
+            }
+        }
+        hsts
+    }
+
+    /**
+     * Format the policy as an output field
+     */
+    pub fn to_field(&self) -> String {
+        if !self.present {
+            return "no-hsts".to_string();
+        }
+        let mut field = match self.max_age {
+            Some(max_age) => format!("hsts:max-age={}", max_age),
+            None => "hsts:invalid-max-age".to_string(),
+        };
+        if self.include_subdomains {
+            field.push_str(",includeSubDomains");
+        }
+        if self.preload {
+            field.push_str(",preload");
+        }
+        field
+    }
+}
+
+/**
+ * The headers chrome sends when a page is opened, user agent included so the
+ * client hints match it. A request carrying nothing but a user agent stands
+ * out to a waf
+ */
+pub fn browser_headers() -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    for (name, value) in [
+        (
+            "sec-ch-ua",
+            "\"Not.A/Brand\";v=\"8\", \"Chromium\";v=\"114\", \"Google Chrome\";v=\"114\"",
+        ),
+        ("sec-ch-ua-mobile", "?0"),
+        ("sec-ch-ua-platform", "\"Windows\""),
+        ("upgrade-insecure-requests", "1"),
+        (
+            "user-agent",
+            "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/114.0.0.0 Safari/537.36",
+        ),
+        (
+            "accept",
+            "text/html,application/xhtml+xml,application/xml;q=0.9,image/avif,image/webp,image/apng,*/*;q=0.8,application/signed-exchange;v=b3;q=0.7",
+        ),
+        ("sec-fetch-site", "none"),
+        ("sec-fetch-mode", "navigate"),
+        ("sec-fetch-user", "?1"),
+        ("sec-fetch-dest", "document"),
+        ("accept-encoding", "gzip, deflate, br"),
+        ("accept-language", "en-US,en;q=0.9"),
+    ] {
+        headers.insert(name, HeaderValue::from_static(value));
+    }
+    headers
+}
+
+/**
+ * The caching headers of a response
+ */
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct CacheInfo {
+    pub cache_control: Option<String>,
+    pub expires: Option<String>,
+    pub vary: Option<String>,
+    pub sets_cookie: bool,
+}
+
+impl CacheInfo {
+    /**
+     * Collect the Cache-Control, Expires and Vary headers of a response
+     */
+    pub fn from_headers(headers: &HeaderMap) -> CacheInfo {
+        let value = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(|value| value.to_string())
+        };
+        CacheInfo {
+            cache_control: value("Cache-Control"),
+            expires: value("Expires"),
+            vary: value("Vary"),
+            sets_cookie: headers.contains_key(SET_COOKIE),
+        }
+    }
+
+    /**
+     * Whether a shared cache is allowed to store the response
+     */
+    pub fn cacheable(&self) -> bool {
+        if let Some(cache_control) = &self.cache_control {
+            let mut cacheable = None;
+            for directive in cache_control.split(',') {
+                let directive = directive.trim().to_lowercase();
+                if directive == "no-store" || directive == "private" || directive == "no-cache" {
+                    return false;
+                }
+                if directive == "public" {
+                    cacheable = Some(true);
+                }
+                for prefix in ["max-age", "s-maxage"] {
+                    if let Some(age) = directive.strip_prefix(prefix) {
+                        let age = age.trim_start().trim_start_matches('=').trim();
+                        match age.trim_matches('"').parse::<u64>() {
+                            Ok(age) if age > 0 => cacheable = cacheable.or(Some(true)),
+                            _ => cacheable = Some(false),
+                        }
+                    }
+                }
+            }
+            if let Some(cacheable) = cacheable {
+                return cacheable;
+            }
+        }
+        match &self.expires {
+            Some(expires) => match DateTime::parse_from_rfc2822(expires) {
+                Ok(expires) => expires.with_timezone(&Utc) > Utc::now(),
+                Err(_) => false,
+            },
+            None => false,
+        }
+    }
+
+    /**
+     * A cacheable response setting a cookie is likely to be personalised,
+     * the kind of page web cache deception goes after
+     */
+    pub fn cacheable_authenticated(&self) -> bool {
+        self.sets_cookie && self.cacheable()
+    }
+
+    /**
+     * Format the caching headers as output fields
+     */
+    pub fn to_fields(&self) -> Vec<String> {
+        let mut fields = Vec::new();
+        if let Some(cache_control) = &self.cache_control {
+            fields.push(format!("cache-control:{}", cache_control));
+        }
+        if let Some(expires) = &self.expires {
+            fields.push(format!("expires:{}", expires));
+        }
+        if let Some(vary) = &self.vary {
+            fields.push(format!("vary:{}", vary));
+        }
+        if fields.is_empty() {
+            fields.push("no-cache-headers".to_string());
+        }
+        fields
+    }
+}
+
+/**
+ * The validators remembered for each url so later scans can cheaply ask
+ * whether the page has changed
+ */
+#[derive(Debug, Default)]
+pub struct Validators {
+    seen: Mutex<HashMap<String, (Option<String>, Option<String>)>>,
+}
+
+impl Validators {
+    /**
+     * The If-None-Match and If-Modified-Since headers for a url seen in an earlier scan
+     */
+    pub fn conditional_headers(&self, url: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        let seen = self.seen.lock().unwrap();
+        if let Some((etag, last_modified)) = seen.get(url) {
+            if let Some(etag) = etag
+                .as_deref()
+                .and_then(|etag| HeaderValue::from_str(etag).ok())
+            {
+                headers.insert(IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = last_modified
+                .as_deref()
+                .and_then(|last_modified| HeaderValue::from_str(last_modified).ok())
+            {
+                headers.insert(IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+        headers
+    }
+
+    /**
+     * Remember the validators a url responded with
+     */
+    pub fn remember(&self, url: &str, etag: Option<String>, last_modified: Option<String>) {
+        if etag.is_none() && last_modified.is_none() {
+            return;
+        }
+        let mut seen = self.seen.lock().unwrap();
+        seen.insert(url.to_string(), (etag, last_modified));
+    }
+}
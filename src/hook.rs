@@ -0,0 +1,180 @@
+use crate::ProbeResult;
+use regex::{Captures, Regex};
+use reqwest::Url;
+use std::sync::Arc;
+use tokio::{process::Command, sync::Semaphore, task};
+
+// the most commands running at once, the others wait for one to exit
+const MAX_RUNNING: usize = 8;
+
+/**
+ * Quote a value so the shell passes it on as a single argument
+ */
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+fn placeholders() -> Regex {
+    Regex::new(r"\{(url|host|port|ip|status|title)?\}").unwrap()
+}
+
+/**
+ * Whether the command has placeholders to fill in
+ */
+pub fn has_placeholders(template: &str) -> bool {
+    placeholders().is_match(template)
+}
+
+/**
+ * Fill the {} / {url}, {host}, {port}, {ip}, {status} and {title}
+ * placeholders in the command, every value is quoted for the shell
+ */
+pub fn fill_template(template: &str, result: &ProbeResult) -> String {
+    let url = Url::parse(&result.url).ok();
+    let host = url
+        .as_ref()
+        .and_then(|url| url.host_str().map(|host| host.to_string()))
+        .unwrap_or_default();
+    let port = url
+        .as_ref()
+        .and_then(|url| url.port_or_known_default())
+        .map(|port| port.to_string())
+        .unwrap_or_default();
+
+    // a single pass so the values can't bring in placeholders of their own
+    placeholders()
+        .replace_all(template, |caps: &Captures| {
+            match caps.get(1).map(|m| m.as_str()) {
+                None | Some("url") => shell_quote(&result.url),
+                Some("host") => shell_quote(&host),
+                Some("port") => shell_quote(&port),
+                Some("ip") => shell_quote(result.ip.as_deref().unwrap_or_default()),
+                Some("status") => result.status_code.to_string(),
+                _ => shell_quote(result.title.as_deref().unwrap_or_default()),
+            }
+        })
+        .into_owned()
+}
+
+/**
+ * The command of --on-match, run for the results with a few at a time
+ */
+pub struct Hook {
+    template: String,
+    running: Arc<Semaphore>,
+}
+
+impl Hook {
+    pub fn new(template: &str) -> Hook {
+        Hook {
+            template: template.to_string(),
+            running: Arc::new(Semaphore::new(MAX_RUNNING)),
+        }
+    }
+
+    /**
+     * Run the command for a result in the background once one of the
+     * running ones exits, its output goes straight to hrekt's own stdout and
+     * stderr
+     */
+    pub fn run(&self, result: &ProbeResult) {
+        let command = fill_template(&self.template, result);
+        let running = self.running.clone();
+        task::spawn(async move {
+            let _permit = match running.acquire_owned().await {
+                Ok(permit) => permit,
+                Err(_) => return,
+            };
+            match shell(&command).spawn() {
+                Ok(mut child) => {
+                    let _ = child.wait().await;
+                }
+                Err(err) => eprintln!("could not run {}: {}", command, err),
+            }
+        });
+    }
+}
+
+#[cfg(windows)]
+fn shell(command: &str) -> Command {
+    use std::os::windows::process::CommandExt;
+
+    // passed on as written, cmd doesn't read the quotes Command::arg adds
+    let mut child = std::process::Command::new("cmd");
+    child.arg("/C").raw_arg(command);
+    Command::from(child)
+}
+
+#[cfg(not(windows))]
+fn shell(command: &str) -> Command {
+    let mut child = Command::new("sh");
+    child.arg("-c").arg(command);
+    child
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn titled(title: &str) -> ProbeResult {
+        ProbeResult {
+            url: "https://example.com:8443/admin".to_string(),
+            status_code: 200,
+            title: Some(title.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn fills_in_the_placeholders() {
+        assert_eq!(
+            fill_template("x {} {host} {port} {status} {ip} {title}", &titled("Admin")),
+            "x 'https://example.com:8443/admin' 'example.com' '8443' 200 '' 'Admin'"
+        );
+        assert_eq!(fill_template("{nope} {URL}", &titled("")), "{nope} {URL}");
+    }
+
+    #[test]
+    fn quotes_a_hostile_title_as_one_argument() {
+        assert_eq!(fill_template("{title}", &titled("it's")), "'it'\\''s'");
+        // the value is never scanned for placeholders again
+        assert_eq!(fill_template("{title}", &titled("{url} {}")), "'{url} {}'");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn passes_a_hostile_title_through_the_shell_unchanged() {
+        // what could break out of the quotes
+        const HOSTILE_TITLES: [&str; 7] = [
+            "it's",
+            "'; touch /tmp/pwned; echo '",
+            "$(touch /tmp/pwned)",
+            "`touch /tmp/pwned`",
+            "line\nnext; touch /tmp/pwned",
+            "$HOME \\ \"quoted\"",
+            "{url} {}",
+        ];
+        for title in HOSTILE_TITLES {
+            let command = fill_template("printf %s {title}", &titled(title));
+            let output = std::process::Command::new("sh")
+                .arg("-c")
+                .arg(&command)
+                .output()
+                .unwrap();
+            assert_eq!(
+                String::from_utf8_lossy(&output.stdout),
+                title,
+                "{}",
+                command
+            );
+        }
+    }
+
+    #[test]
+    fn tells_a_command_with_placeholders() {
+        assert!(has_placeholders("nuclei -u {}"));
+        assert!(has_placeholders("echo {title}"));
+        assert!(!has_placeholders("notify -silent"));
+        assert!(!has_placeholders("echo {nope}"));
+    }
+}
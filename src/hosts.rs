@@ -0,0 +1,91 @@
+use std::{
+    collections::HashMap,
+    fs,
+    net::{IpAddr, SocketAddr},
+};
+
+/**
+ * The names pinned to addresses with --resolve and --hosts-file, used in
+ * place of dns for origin servers behind a cdn or hosts not in dns yet
+ */
+#[derive(Clone, Debug, Default)]
+pub struct StaticHosts {
+    hosts: HashMap<String, Vec<IpAddr>>,
+}
+
+/**
+ * Parse an address, with or without the brackets around an ipv6 one
+ */
+fn parse_ip(ip: &str) -> Option<IpAddr> {
+    ip.trim()
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse()
+        .ok()
+}
+
+impl StaticHosts {
+    /**
+     * Pin a name to an address from a host:ip mapping, replacing whatever
+     * the hosts file gave it
+     */
+    pub fn add_mapping(&mut self, mapping: &str) -> Result<(), String> {
+        let (host, ip) = mapping
+            .split_once(':')
+            .ok_or_else(|| format!("{} isn't a host:ip mapping", mapping))?;
+        let ip = parse_ip(ip).ok_or_else(|| format!("{} isn't an ip address", ip))?;
+        let host = host.trim().to_lowercase();
+        if host.is_empty() {
+            return Err(format!("{} has no host", mapping));
+        }
+        self.hosts.insert(host, vec![ip]);
+        Ok(())
+    }
+
+    /**
+     * Read a file laid out like /etc/hosts, an address followed by the names
+     * pointing at it on each line
+     */
+    pub fn load_file(&mut self, path: &str) -> Result<(), String> {
+        let contents = fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err))?;
+        for line in contents.lines() {
+            let line = line.split('#').next().unwrap_or_default();
+            let mut fields = line.split_whitespace();
+            let ip = match fields.next() {
+                Some(ip) => parse_ip(ip).ok_or_else(|| format!("{}: {} isn't an ip", path, ip))?,
+                None => continue,
+            };
+            for host in fields {
+                let ips = self.hosts.entry(host.to_lowercase()).or_default();
+                if !ips.contains(&ip) {
+                    ips.push(ip);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /**
+     * Every pinned name with its addresses
+     */
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Vec<IpAddr>)> {
+        self.hosts.iter()
+    }
+
+    /**
+     * The addresses a name is pinned to
+     */
+    pub fn get(&self, host: &str) -> Option<&Vec<IpAddr>> {
+        self.hosts.get(&host.trim_end_matches('.').to_lowercase())
+    }
+
+    /**
+     * The socket addresses of a host:port when the host is pinned
+     */
+    pub fn lookup(&self, addr: &str) -> Option<Vec<SocketAddr>> {
+        let (host, port) = addr.rsplit_once(':')?;
+        let port = port.parse::<u16>().ok()?;
+        let ips = self.get(host)?;
+        Some(ips.iter().map(|ip| SocketAddr::new(*ip, port)).collect())
+    }
+}
@@ -0,0 +1,17 @@
+use futures::future::BoxFuture;
+use reqwest::{Request, Response};
+
+/**
+ * Sends the requests of a scan. The reqwest client of each worker sends them
+ * unless another one is given to the Prober, such as a mock answering the
+ * requests without the network
+ */
+pub trait HttpClient: Send + Sync {
+    fn execute(&self, request: Request) -> BoxFuture<'_, reqwest::Result<Response>>;
+}
+
+impl HttpClient for reqwest::Client {
+    fn execute(&self, request: Request) -> BoxFuture<'_, reqwest::Result<Response>> {
+        Box::pin(reqwest::Client::execute(self, request))
+    }
+}
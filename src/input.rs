@@ -0,0 +1,787 @@
+use regex::Regex;
+use std::{collections::HashMap, fs, io};
+
+/**
+ * A host to probe along with any settings it overrides
+ */
+#[derive(Clone, Debug, Default)]
+pub struct Target {
+    pub host: String,
+    pub ports: Option<String>,
+    pub path: Option<String>,
+    pub timeout: Option<usize>,
+    pub scheme: Option<String>,
+}
+
+impl Target {
+    pub fn new(host: &str) -> Self {
+        Target {
+            host: host.to_string(),
+            ..Default::default()
+        }
+    }
+}
+
+/**
+ * Whether a line is blank or a comment
+ */
+pub fn is_comment(line: &str) -> bool {
+    line.is_empty() || line.starts_with('#') || line.starts_with("//")
+}
+
+/**
+ * Make a target of a host written any of the ways hosts get written, with a
+ * scheme, credentials, a port, a path, a wildcard, quotes or a trailing dot,
+ * None when what's left isn't a host
+ */
+pub fn normalize_host(raw: &str) -> Option<Target> {
+    let mut rest = raw
+        .trim()
+        .trim_matches(|c| c == '"' || c == '\'' || c == '`');
+    // the innermost scheme of a url glued to another, http://https://host
+    let mut scheme = None;
+    while let Some((prefix, after)) = rest.split_once("://") {
+        if prefix.is_empty() || !prefix.chars().all(|c| c.is_ascii_alphabetic()) {
+            break;
+        }
+        let prefix = prefix.to_lowercase();
+        if prefix == "http" || prefix == "https" {
+            scheme = Some(prefix);
+        }
+        rest = after;
+    }
+
+    // the authority ends where the path, the query or the fragment begins
+    let (authority, path) = match rest.find(|c| c == '/' || c == '?' || c == '#') {
+        Some(i) => (&rest[..i], &rest[i..]),
+        None => (rest, ""),
+    };
+    let authority = match authority.rsplit_once('@') {
+        Some((_, authority)) => authority,
+        None => authority,
+    };
+
+    let (host, port) = if let Some(bracketed) = authority.strip_prefix('[') {
+        let (address, after) = bracketed.split_once(']')?;
+        // only a port may follow the address
+        let port = match after {
+            "" => None,
+            after => Some(after.strip_prefix(':')?),
+        };
+        (format!("[{}]", address), port)
+    } else if authority.matches(':').count() > 1 {
+        // a bare ipv6 address can't have a port after it
+        (format!("[{}]", authority), None)
+    } else {
+        match authority.split_once(':') {
+            Some((host, port)) => (host.to_string(), Some(port)),
+            None => (authority.to_string(), None),
+        }
+    };
+    let host = host
+        .trim_start_matches("*.")
+        .trim_end_matches('.')
+        .to_lowercase();
+    let valid = host.starts_with('[')
+        || host
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.' || c == '_');
+    if host.is_empty() || !valid {
+        return None;
+    }
+
+    let mut target = Target::new(&host);
+    target.ports = match port.filter(|port| !port.is_empty()) {
+        Some(port) => Some(
+            port.parse::<u16>()
+                .ok()
+                .filter(|port| *port != 0)?
+                .to_string(),
+        ),
+        // a url is probed on the port its scheme implies
+        None => match scheme.as_deref() {
+            Some("https") => Some("443".to_string()),
+            Some("http") => Some("80".to_string()),
+            _ => None,
+        },
+    };
+    target.scheme = scheme;
+    // the exact path and query of a url, the root is the same page as none
+    // and the fragment never reaches the server
+    let path = path.split('#').next().unwrap_or_default();
+    if !path.is_empty() && path != "/" {
+        let path = if path.starts_with('?') {
+            format!("/{}", path)
+        } else {
+            path.to_string()
+        };
+        target.path = Some(path);
+    }
+    Some(target)
+}
+
+/**
+ * Parse a line of stdin, the first column of a csv or tab separated line,
+ * the rest are left alone
+ */
+pub fn parse_input_line(line: &str) -> Option<Target> {
+    let line = line.trim();
+    if is_comment(line) {
+        return None;
+    }
+    let host = line.split(|c| c == ',' || c == '\t' || c == ' ').next()?;
+    normalize_host(host)
+}
+
+/**
+ * Whether a list of ports such as 80,443 only holds ports from 1 to 65535
+ */
+pub fn valid_ports(ports: &str) -> bool {
+    ports
+        .split(',')
+        .all(|port| matches!(port.trim().parse::<u16>(), Ok(port) if port > 0))
+}
+
+/**
+ * Gather the host:port lines of the same host into one target, the way port
+ * scanners list every open port on a line of its own, so the host is only
+ * resolved once
+ */
+pub fn merge_ports(targets: Vec<Target>) -> Vec<Target> {
+    let mut merged: Vec<Target> = vec![];
+    let mut index: HashMap<String, usize> = HashMap::new();
+    for target in targets {
+        let bare = target.scheme.is_none() && target.path.is_none() && target.timeout.is_none();
+        let port = match &target.ports {
+            Some(port) if bare => port.clone(),
+            _ => {
+                merged.push(target);
+                continue;
+            }
+        };
+        match index.get(&target.host) {
+            Some(&i) => {
+                let ports = merged[i].ports.get_or_insert_with(String::new);
+                if !ports.split(',').any(|p| p == port) {
+                    ports.push(',');
+                    ports.push_str(&port);
+                }
+            }
+            None => {
+                index.insert(target.host.clone(), merged.len());
+                merged.push(target);
+            }
+        }
+    }
+    merged
+}
+
+// the most paths a brace pattern may expand to
+const MAX_EXPANSION: usize = 10000;
+
+/**
+ * The position of the brace closing the one at the start of the pattern,
+ * None when it's never closed
+ */
+fn closing_brace(pattern: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (i, c) in pattern.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/**
+ * Split the inside of a brace on the commas that aren't in a nested one
+ */
+fn split_alternatives(inner: &str) -> Vec<&str> {
+    let mut alternatives = vec![];
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in inner.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            ',' if depth == 0 => {
+                alternatives.push(&inner[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    alternatives.push(&inner[start..]);
+    alternatives
+}
+
+/**
+ * The bounds of a {1..9} range and the width of its numbers, zero padded
+ * like the bounds when they are
+ */
+fn parse_range(inner: &str) -> Option<(i64, i64, usize)> {
+    let (from, to) = inner.split_once("..")?;
+    let start = from.parse::<i64>().ok()?;
+    let end = to.parse::<i64>().ok()?;
+    let padded = |bound: &str| bound.trim_start_matches('-').len() > 1 && bound.starts_with('0');
+    let width = if padded(from) || padded(to) {
+        from.len().max(to.len())
+    } else {
+        0
+    };
+    Some((start, end, width))
+}
+
+/**
+ * Expand the braces of a path the way a shell does, /{admin,login} into
+ * /admin and /login and /v{1..3} into /v1, /v2 and /v3, nested braces
+ * included. A brace without a comma or a range is left as it is.
+ */
+pub fn expand_braces(pattern: &str) -> Result<Vec<String>, String> {
+    let open = match pattern.find('{') {
+        Some(open) => open,
+        None => return Ok(vec![pattern.to_string()]),
+    };
+    let close = match closing_brace(&pattern[open..]) {
+        Some(close) => open + close,
+        None => return Err(format!("{} has a brace that isn't closed", pattern)),
+    };
+    let (prefix, inner, suffix) = (
+        &pattern[..open],
+        &pattern[open + 1..close],
+        &pattern[close + 1..],
+    );
+
+    let alternatives = match parse_range(inner) {
+        Some((start, end, width)) => {
+            if start.abs_diff(end) >= MAX_EXPANSION as u64 {
+                return Err(format!(
+                    "{} expands to more than {} paths",
+                    pattern, MAX_EXPANSION
+                ));
+            }
+            // counting down when the first bound is the bigger one
+            let numbers: Vec<i64> = if start <= end {
+                (start..=end).collect()
+            } else {
+                (end..=start).rev().collect()
+            };
+            numbers
+                .into_iter()
+                .map(|number| format!("{:0width$}", number, width = width))
+                .collect()
+        }
+        None => {
+            let alternatives = split_alternatives(inner);
+            if alternatives.len() == 1 {
+                // a literal brace, only what follows it may still expand
+                return Ok(expand_braces(suffix)?
+                    .into_iter()
+                    .map(|suffix| format!("{}{{{}}}{}", prefix, inner, suffix))
+                    .collect());
+            }
+            let mut expanded = vec![];
+            for alternative in alternatives {
+                expanded.extend(expand_braces(alternative)?);
+            }
+            expanded
+        }
+    };
+    let suffixes = expand_braces(suffix)?;
+    if alternatives.len() * suffixes.len() > MAX_EXPANSION {
+        return Err(format!(
+            "{} expands to more than {} paths",
+            pattern, MAX_EXPANSION
+        ));
+    }
+    let mut paths = vec![];
+    for alternative in &alternatives {
+        for suffix in &suffixes {
+            paths.push(format!("{}{}{}", prefix, alternative, suffix));
+        }
+    }
+    Ok(paths)
+}
+
+/**
+ * Parse a `host,ports,path,timeout` line, every column after the host is optional
+ */
+pub fn parse_target_line(line: &str) -> Option<Target> {
+    let line = line.trim();
+    if is_comment(line) {
+        return None;
+    }
+
+    let mut columns = line.split(',').map(|column| column.trim());
+    let host = columns.next()?;
+    if host.is_empty() {
+        return None;
+    }
+    // the columns win over what the host says
+    let mut target = match normalize_host(host) {
+        Some(target) => target,
+        None => {
+            eprintln!("could not make out a host in {}, skipping it", host);
+            return None;
+        }
+    };
+    if let Some(ports) = columns.next().filter(|ports| !ports.is_empty()) {
+        // ports are separated with ';' as ',' separates the columns
+        target.ports = Some(ports.replace(';', ","));
+    }
+    if let Some(path) = columns.next().filter(|path| !path.is_empty()) {
+        target.path = Some(path.to_string());
+    }
+    if let Some(timeout) = columns.next().filter(|timeout| !timeout.is_empty()) {
+        match timeout.parse::<usize>() {
+            Ok(timeout) => target.timeout = Some(timeout),
+            Err(_) => eprintln!("could not parse timeout for {}, using the default", host),
+        }
+    }
+    Some(target)
+}
+
+/**
+ * Read the targets and their overrides from a file
+ */
+pub fn read_targets_file(path: &str) -> io::Result<Vec<Target>> {
+    let contents = fs::read_to_string(path)?;
+    Ok(contents.lines().filter_map(parse_target_line).collect())
+}
+
+/**
+ * Read the open http ports from an nmap xml scan, the service nmap
+ * detected decides whether http or https is tried
+ */
+pub fn read_nmap_xml(path: &str) -> Result<Vec<Target>, String> {
+    let contents = fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err))?;
+    let doc = roxmltree::Document::parse(&contents).map_err(|err| format!("{}: {}", path, err))?;
+
+    let mut targets = vec![];
+    for host in doc.descendants().filter(|node| node.has_tag_name("host")) {
+        // prefer the name nmap was given over the address it resolved
+        let hostname = host
+            .descendants()
+            .filter(|node| node.has_tag_name("hostname"))
+            .find(|node| node.attribute("type") == Some("user"))
+            .or_else(|| {
+                host.descendants()
+                    .find(|node| node.has_tag_name("hostname"))
+            })
+            .and_then(|node| node.attribute("name"));
+        let address = host
+            .children()
+            .filter(|node| node.has_tag_name("address"))
+            .find(|node| node.attribute("addrtype") != Some("mac"))
+            .and_then(|node| node.attribute("addr"));
+        let name = match hostname.or(address) {
+            Some(name) => name,
+            None => continue,
+        };
+
+        for port in host.descendants().filter(|node| node.has_tag_name("port")) {
+            let open = port
+                .children()
+                .find(|node| node.has_tag_name("state"))
+                .and_then(|node| node.attribute("state"))
+                == Some("open");
+            let portid = match port.attribute("portid") {
+                Some(portid) if open => portid,
+                _ => continue,
+            };
+            let service = match port.children().find(|node| node.has_tag_name("service")) {
+                Some(service) => service,
+                None => continue,
+            };
+            let service_name = service.attribute("name").unwrap_or_default();
+            if !service_name.contains("http") {
+                continue;
+            }
+            let tls = service.attribute("tunnel") == Some("ssl")
+                || service_name.contains("https")
+                || service_name.starts_with("ssl");
+
+            let mut target = Target::new(name);
+            target.ports = Some(portid.to_string());
+            target.scheme = Some(if tls { "https" } else { "http" }.to_string());
+            targets.push(target);
+        }
+    }
+    Ok(targets)
+}
+
+/**
+ * Read the open ports from masscan's -oJ or -oL output, the addresses are
+ * probed as they are so nothing gets resolved
+ */
+pub fn read_masscan(path: &str) -> io::Result<Vec<Target>> {
+    let contents = fs::read_to_string(path)?;
+    let ip_re = Regex::new(r#""ip"\s*:\s*"([^"]+)""#).unwrap();
+    let port_re = Regex::new(r#"\{[^{}]*"port"\s*:\s*(\d+)[^{}]*\}"#).unwrap();
+
+    let mut targets: Vec<Target> = vec![];
+    let mut index: HashMap<String, usize> = HashMap::new();
+    let mut add = |ip: &str, port: &str| {
+        if !valid_ports(port) {
+            return;
+        }
+        // ipv6 addresses need brackets before a port can follow them
+        let host = if ip.contains(':') {
+            format!("[{}]", ip)
+        } else {
+            ip.to_string()
+        };
+        // masscan reports each port on its own, so gather them per address
+        match index.get(&host) {
+            Some(&i) => {
+                let ports = targets[i].ports.get_or_insert_with(String::new);
+                if !ports.split(',').any(|p| p == port) {
+                    ports.push(',');
+                    ports.push_str(port);
+                }
+            }
+            None => {
+                let mut target = Target::new(&host);
+                target.ports = Some(port.to_string());
+                index.insert(host, targets.len());
+                targets.push(target);
+            }
+        }
+    };
+
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        // open tcp 80 1.2.3.4 1390000000
+        if line.starts_with("open ") {
+            let columns = line.split_whitespace().collect::<Vec<&str>>();
+            if columns.len() >= 4 && columns[1] == "tcp" {
+                add(columns[3], columns[2]);
+            }
+            continue;
+        }
+        // { "ip": "1.2.3.4", "ports": [ {"port": 80, "proto": "tcp", "status": "open"} ] }
+        if let Some(ip) = ip_re.captures(line) {
+            for port in port_re.captures_iter(line) {
+                let entry = &port[0];
+                if entry.contains("\"tcp\"") && !entry.contains("\"closed\"") {
+                    add(&ip[1], &port[1]);
+                }
+            }
+        }
+    }
+    Ok(targets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn scan_file(name: &str, contents: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("hrekt-{}-{}", std::process::id(), name));
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    fn summary(targets: &[Target]) -> Vec<(String, Option<String>, Option<String>)> {
+        targets
+            .iter()
+            .map(|target| {
+                (
+                    target.host.clone(),
+                    target.ports.clone(),
+                    target.scheme.clone(),
+                )
+            })
+            .collect()
+    }
+
+    fn owned(
+        host: &str,
+        ports: &str,
+        scheme: Option<&str>,
+    ) -> (String, Option<String>, Option<String>) {
+        (
+            host.to_string(),
+            Some(ports.to_string()),
+            scheme.map(str::to_string),
+        )
+    }
+
+    #[test]
+    fn reads_the_open_http_ports_of_an_nmap_scan() {
+        let path = scan_file(
+            "scan.xml",
+            r#"<?xml version="1.0"?>
+<nmaprun>
+  <host>
+    <address addr="10.0.0.1" addrtype="ipv4"/>
+    <address addr="00:11:22:33:44:55" addrtype="mac"/>
+    <hostnames>
+      <hostname name="ptr.example.com" type="PTR"/>
+      <hostname name="app.example.com" type="user"/>
+    </hostnames>
+    <ports>
+      <port protocol="tcp" portid="80"><state state="open"/><service name="http"/></port>
+      <port protocol="tcp" portid="443"><state state="open"/><service name="http" tunnel="ssl"/></port>
+      <port protocol="tcp" portid="8443"><state state="open"/><service name="https-alt"/></port>
+      <port protocol="tcp" portid="8080"><state state="filtered"/><service name="http-proxy"/></port>
+      <port protocol="tcp" portid="22"><state state="open"/><service name="ssh"/></port>
+      <port protocol="tcp" portid="9000"><state state="open"/></port>
+    </ports>
+  </host>
+  <host>
+    <address addr="00:11:22:33:44:66" addrtype="mac"/>
+    <address addr="10.0.0.2" addrtype="ipv4"/>
+    <ports>
+      <port protocol="tcp" portid="8080"><state state="open"/><service name="http-proxy"/></port>
+    </ports>
+  </host>
+  <host>
+    <ports>
+      <port protocol="tcp" portid="80"><state state="open"/><service name="http"/></port>
+    </ports>
+  </host>
+</nmaprun>"#,
+        );
+        let targets = read_nmap_xml(path.to_str().unwrap()).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            summary(&targets),
+            [
+                // the name nmap was given, not the one it looked up
+                owned("app.example.com", "80", Some("http")),
+                owned("app.example.com", "443", Some("https")),
+                owned("app.example.com", "8443", Some("https")),
+                // the ip address, never the mac
+                owned("10.0.0.2", "8080", Some("http")),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_what_isnt_an_nmap_scan() {
+        let path = scan_file("broken.xml", "<nmaprun><host>");
+        let err = read_nmap_xml(path.to_str().unwrap()).unwrap_err();
+        fs::remove_file(&path).unwrap();
+        assert!(err.starts_with(path.to_str().unwrap()), "{}", err);
+
+        // xml of something else holds no targets
+        let path = scan_file("other.xml", "<feed><entry/></feed>");
+        let targets = read_nmap_xml(path.to_str().unwrap()).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert!(targets.is_empty());
+
+        assert!(read_nmap_xml("/nonexistent/scan.xml").is_err());
+    }
+
+    #[test]
+    fn reads_the_open_ports_of_masscan_json() {
+        let path = scan_file(
+            "masscan.json",
+            r#"[
+{   "ip": "10.0.0.1",   "timestamp": "1602851404", "ports": [ {"port": 80, "proto": "tcp", "status": "open", "reason": "syn-ack", "ttl": 64} ] }
+,
+{   "ip": "10.0.0.1",   "timestamp": "1602851405", "ports": [ {"port": 8080, "proto": "tcp", "status": "open", "reason": "syn-ack", "ttl": 64} ] }
+,
+{   "ip": "10.0.0.1",   "timestamp": "1602851406", "ports": [ {"port": 80, "proto": "tcp", "status": "open", "reason": "syn-ack", "ttl": 64} ] }
+,
+{   "ip": "2001:db8::1",   "timestamp": "1602851407", "ports": [ {"port": 443, "proto": "tcp", "status": "open", "reason": "syn-ack", "ttl": 64} ] }
+,
+{   "ip": "10.0.0.2",   "timestamp": "1602851408", "ports": [ {"port": 53, "proto": "udp", "status": "open", "reason": "none", "ttl": 64} ] }
+,
+{   "ip": "10.0.0.3",   "timestamp": "1602851409", "ports": [ {"port": 443, "proto": "tcp", "status": "closed", "reason": "rst", "ttl": 64} ] }
+,
+{   "ip": "10.0.0.4",   "timestamp": "1602851410", "ports": [ {"port": 99999, "proto": "tcp", "status": "open", "reason": "syn-ack", "ttl": 64} ] }
+]"#,
+        );
+        let targets = read_masscan(path.to_str().unwrap()).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            summary(&targets),
+            [
+                // each port of an address gathered once
+                owned("10.0.0.1", "80,8080", None),
+                owned("[2001:db8::1]", "443", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_the_open_ports_of_a_masscan_list() {
+        let path = scan_file(
+            "masscan.txt",
+            "#masscan\n\
+             open tcp 80 10.0.0.1 1390000000\n\
+             open tcp 443 10.0.0.1 1390000001\n\
+             open udp 53 10.0.0.1 1390000002\n\
+             open tcp 0 10.0.0.2 1390000003\n\
+             open tcp http 10.0.0.3 1390000004\n\
+             open tcp 80\n\
+             garbage line\n\
+             \n\
+             # end\n",
+        );
+        let targets = read_masscan(path.to_str().unwrap()).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(summary(&targets), [owned("10.0.0.1", "80,443", None)]);
+        assert!(read_masscan("/nonexistent/masscan.json").is_err());
+    }
+
+    fn normalized(raw: &str) -> Option<(String, Option<String>, Option<String>, Option<String>)> {
+        normalize_host(raw).map(|target| (target.host, target.ports, target.scheme, target.path))
+    }
+
+    fn host(
+        host: &str,
+        ports: Option<&str>,
+        scheme: Option<&str>,
+        path: Option<&str>,
+    ) -> Option<(String, Option<String>, Option<String>, Option<String>)> {
+        Some((
+            host.to_string(),
+            ports.map(str::to_string),
+            scheme.map(str::to_string),
+            path.map(str::to_string),
+        ))
+    }
+
+    #[test]
+    fn normalizes_the_ways_hosts_get_written() {
+        assert_eq!(
+            normalized("Example.COM"),
+            host("example.com", None, None, None)
+        );
+        assert_eq!(
+            normalized("  \"*.example.com.\" "),
+            host("example.com", None, None, None)
+        );
+        assert_eq!(
+            normalized("HTTPS://user:p@ss@Example.com/Admin?x=1#top"),
+            host(
+                "example.com",
+                Some("443"),
+                Some("https"),
+                Some("/Admin?x=1")
+            )
+        );
+        assert_eq!(
+            normalized("http://https://example.com:8443/"),
+            host("example.com", Some("8443"), Some("https"), None)
+        );
+        assert_eq!(
+            normalized("example.com?debug=1"),
+            host("example.com", None, None, Some("/?debug=1"))
+        );
+        assert_eq!(
+            normalized("ftp://example.com"),
+            host("example.com", None, None, None)
+        );
+        assert_eq!(
+            normalized("[2001:db8::1]:8080"),
+            host("[2001:db8::1]", Some("8080"), None, None)
+        );
+        assert_eq!(
+            normalized("2001:db8::1"),
+            host("[2001:db8::1]", None, None, None)
+        );
+        assert_eq!(
+            normalized("example.com:"),
+            host("example.com", None, None, None)
+        );
+    }
+
+    #[test]
+    fn rejects_what_isnt_a_host() {
+        for raw in [
+            "",
+            "\"\"",
+            "http://",
+            "https:///admin",
+            "*.",
+            "exa mple.com",
+            "exa<mple>.com",
+            "example.com:0",
+            "example.com:65536",
+            "example.com:http",
+            "[2001:db8::1",
+            "[2001:db8::1]8080",
+            "[2001:db8::1]:port",
+        ] {
+            assert_eq!(normalized(raw), None, "{:?}", raw);
+        }
+    }
+
+    #[test]
+    fn reads_the_first_column_of_an_input_line() {
+        for line in ["", "   ", "# a comment", "// another"] {
+            assert!(parse_input_line(line).is_none(), "{:?}", line);
+        }
+        for line in [
+            "example.com,200,nginx",
+            "example.com\tdev",
+            " example.com extra words ",
+        ] {
+            assert_eq!(parse_input_line(line).unwrap().host, "example.com");
+        }
+        assert!(parse_input_line(",example.com").is_none());
+    }
+
+    #[test]
+    fn expands_alternatives_and_ranges() {
+        assert_eq!(expand_braces("/admin").unwrap(), ["/admin"]);
+        assert_eq!(
+            expand_braces("/{admin,login}.php").unwrap(),
+            ["/admin.php", "/login.php"]
+        );
+        assert_eq!(expand_braces("/v{1..3}").unwrap(), ["/v1", "/v2", "/v3"]);
+        assert_eq!(expand_braces("/{3..1}").unwrap(), ["/3", "/2", "/1"]);
+        assert_eq!(expand_braces("/{-1..1}").unwrap(), ["/-1", "/0", "/1"]);
+        assert_eq!(expand_braces("/{08..10}").unwrap(), ["/08", "/09", "/10"]);
+        // the alternatives times what follows them, nested and empty ones too
+        assert_eq!(
+            expand_braces("/{a,b{1..2}}/{x,}").unwrap(),
+            ["/a/x", "/a/", "/b1/x", "/b1/", "/b2/x", "/b2/"]
+        );
+    }
+
+    #[test]
+    fn leaves_a_brace_without_a_comma_or_a_range() {
+        assert_eq!(expand_braces("/{id}").unwrap(), ["/{id}"]);
+        assert_eq!(
+            expand_braces("/{id}/{a,b}").unwrap(),
+            ["/{id}/a", "/{id}/b"]
+        );
+        for literal in ["/{}", "/{1..}", "/{a..c}", "/{1..5..2}", "/}{a,b}"] {
+            assert!(expand_braces(literal).is_ok(), "{}", literal);
+        }
+        assert_eq!(expand_braces("/{1..}").unwrap(), ["/{1..}"]);
+    }
+
+    #[test]
+    fn rejects_a_malformed_or_huge_pattern() {
+        for pattern in [
+            "/{a,b",
+            "/{a,{b}",
+            "/{0..10000}",
+            "/{-9223372036854775808..9223372036854775807}",
+            "/{1..100}{1..100}{1..2}",
+        ] {
+            assert!(expand_braces(pattern).is_err(), "{}", pattern);
+        }
+        assert!(expand_braces("/{1..100}{1..100}").is_ok());
+    }
+}
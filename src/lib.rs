@@ -0,0 +1,5060 @@
+use chrono::Local;
+use clap::{parser::ValueSource, value_parser, Arg, ArgAction, ArgMatches, Command};
+use clap_complete::{generate, Shell};
+use colored::Colorize;
+use futures::{stream::FuturesUnordered, StreamExt};
+use governor::RateLimiter;
+use rand::{seq::SliceRandom, Rng};
+use regex;
+use regex::Regex;
+use reqwest::{header::HeaderMap, redirect, Method};
+use std::{
+    collections::BTreeMap,
+    env,
+    error::Error,
+    io::{self, IsTerminal},
+    net::{IpAddr, SocketAddr},
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+use tokio::{
+    io::{AsyncBufReadExt, BufReader},
+    net,
+    runtime::Builder,
+    sync::watch,
+    task,
+};
+use wappalyzer::{self};
+
+mod archive;
+mod auth;
+mod banner;
+mod bench;
+mod bloom;
+mod bypass;
+mod callbacks;
+mod cert;
+mod checkpoint;
+mod chrome;
+mod config;
+mod connect;
+mod convert;
+mod crawl;
+mod dedup;
+mod delay;
+mod diff;
+mod discover;
+mod dryrun;
+mod errors;
+mod extract;
+mod family;
+mod fingerprints;
+mod headers;
+mod hook;
+mod hosts;
+mod http;
+mod input;
+mod memory;
+mod merge;
+mod methods;
+mod output;
+mod patterns;
+mod plugin;
+mod prober;
+mod proto;
+mod proxy;
+mod rate;
+mod render;
+mod resolve;
+mod schedule;
+mod schemes;
+mod script;
+mod scripts;
+mod seen;
+mod serve;
+mod stats;
+mod store;
+mod summary;
+mod throttle;
+mod timing;
+mod tor;
+mod unresolved;
+mod xml;
+
+use archive::{Archive, Exchange};
+use auth::{DigestAuth, NtlmAuth};
+use banner::Banners;
+use bloom::Dedupe;
+use cert::PeerCertificate;
+use checkpoint::Checkpoint;
+use chrome::Chrome;
+use crawl::Crawl;
+use dedup::UniqueFilter;
+use delay::Delay;
+use discover::Discover;
+use family::IpPreference;
+use fingerprints::{Detection, Fingerprints};
+use headers::Validators;
+use hook::Hook;
+use hosts::StaticHosts;
+use input::Target;
+use memory::MemoryGuard;
+use output::{Compression, Encoding, GroupBy, Output, Sort};
+use patterns::Pattern;
+use plugin::{Plugins, Verdict};
+use proxy::ProxyPool;
+use rate::AdaptiveRate;
+use schedule::Schedule;
+use schemes::{NonHttp, PortSchemes};
+use script::Script;
+use seen::SeenDb;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use stats::Stats;
+use throttle::{HostDelay, HostThrottle};
+use tor::TorControl;
+use unresolved::Unresolved;
+use xml::XmlReport;
+
+pub use callbacks::{Callbacks, Progress};
+pub use cert::CertExpiry;
+pub use config::{ConfigError, ProberConfig, ProberConfigBuilder};
+pub use errors::{ErrorKind, ProbeError};
+pub use family::IpFamily;
+pub use headers::{CacheInfo, Hsts};
+pub use http::HttpClient;
+pub use output::{CacheRecord, ErrorRecord, HstsRecord, Record, TimingRecord};
+pub use prober::{Prober, StopHandle};
+pub use timing::Timing;
+
+// how many times a single worker is restarted before giving up on it
+const MAX_WORKER_RESTARTS: usize = 100;
+
+// how long the requests in flight get to finish after ctrl-c
+const STOP_GRACE: Duration = Duration::from_secs(10);
+
+// what --stealth sets the options left unset to
+const STEALTH_RATE: u32 = 10;
+const STEALTH_CONCURRENCY: i32 = 10;
+const STEALTH_HOST_RATE: u32 = 1;
+const STEALTH_JITTER: &str = "0-2s";
+
+/**
+ * What to probe on a target and what to collect from its pages, the same
+ * for every target but the host and the columns a target line overrides
+ */
+#[derive(Clone, Debug)]
+pub struct Job {
+    host: Option<String>,
+    body_regex: Vec<Pattern>,
+    header_regex: String,
+    ports: String,
+    display_title: bool,
+    display_tech: bool,
+    status_codes: bool,
+    content_length: bool,
+    content_type: bool,
+    server: bool,
+    path: String,
+    // the timeout of the target, when its line gives one
+    timeout: Option<usize>,
+    word_count: bool,
+    display_hash: bool,
+    filter_hashes: Vec<String>,
+    hsts: bool,
+    methods_discovery: bool,
+    etag: bool,
+    last_modified: bool,
+    cache_info: bool,
+    hunt_patterns: Vec<Pattern>,
+    extract_links: bool,
+    extract_emails: bool,
+    extract_all_domains: bool,
+    url: Option<String>,
+    depth: usize,
+    // where the target is in the input, for the checkpoints
+    target_index: Option<usize>,
+    js_scrape: bool,
+    js_max_files: usize,
+    js_max_size: usize,
+    scheme: Option<String>,
+    store_dir: Option<String>,
+    cert_expiry_warn: Option<i32>,
+    digest_auth: Option<DigestAuth>,
+    ntlm_auth: Option<NtlmAuth>,
+    tags: Vec<String>,
+    render: bool,
+    tech_filter: Vec<String>,
+    tech_filter_drop: bool,
+    tech_min_confidence: u8,
+    timing: bool,
+    bypass_403: bool,
+    method_diff: bool,
+}
+
+/**
+ * The settings for a whole scan
+ */
+#[derive(Clone, Debug)]
+pub struct Settings {
+    rate: u32,
+    auto_rate: bool,
+    rate_min: u32,
+    rate_max: u32,
+    concurrency: i32,
+    queue_size: usize,
+    timeout: usize,
+    dns_timeout: u64,
+    dns_retries: usize,
+    follow_redirects: bool,
+    crawl_depth: usize,
+    crawl_max_pages: usize,
+    sni: Option<String>,
+    output_xml: Option<String>,
+    seen_db: Option<String>,
+    unresolved: Option<String>,
+    archive: Option<String>,
+    discover: Option<String>,
+    discover_limit: usize,
+    new_only: bool,
+    dedupe: Option<String>,
+    dedupe_bits: u64,
+    max_memory: Option<u64>,
+    plugins: Vec<String>,
+    script: Option<String>,
+    on_match: Option<String>,
+    on_match_codes: Vec<u16>,
+    stats_interval: Option<Duration>,
+    live_stats: bool,
+    stats_json: Option<String>,
+    checkpoint: Option<String>,
+    checkpoint_interval: Duration,
+    checkpoint_results: Option<u64>,
+    include_errors: bool,
+    include_failed: bool,
+    verify_tls: bool,
+    ca_certs: Vec<reqwest::Certificate>,
+    tls_info: bool,
+    san_feedback: bool,
+    san_scope: Vec<String>,
+    source_ip: Option<IpAddr>,
+    interface: Option<String>,
+    ip_preference: Option<IpPreference>,
+    port_schemes: PortSchemes,
+    scheme_detect: bool,
+    one_per_ip: bool,
+    ip_group: bool,
+    delay: Option<Delay>,
+    encoding: Encoding,
+    output: Option<String>,
+    output_compress: Option<Compression>,
+    sort: Option<Sort>,
+    group_by: Option<GroupBy>,
+    host_rate: u32,
+    browser_headers: bool,
+    proxy_list: Option<String>,
+    proxy_rotate: usize,
+    tor: Option<String>,
+    tor_newnym: usize,
+    tor_control: String,
+    tor_password: Option<String>,
+    static_hosts: StaticHosts,
+    same_host_only: bool,
+    chrome_path: Option<PathBuf>,
+    chrome_args: Vec<String>,
+    browser_max_pages: Option<usize>,
+    browser_max_mem: Option<u64>,
+    fingerprints: Option<String>,
+    banner: bool,
+    resolve_only: bool,
+    connect_only: bool,
+    unique: bool,
+    silent: bool,
+}
+
+/**
+ * The state shared by every worker in a scan
+ */
+pub struct Shared {
+    stats: Arc<Stats>,
+    unique: Option<UniqueFilter>,
+    validators: Option<Arc<Validators>>,
+    crawl: Option<Arc<Crawl>>,
+    xml: Option<XmlReport>,
+    output: Output,
+    host_throttle: Option<HostThrottle>,
+    // the delay of each host when the hosts are throttled, instead of delay
+    host_delay: Option<HostDelay>,
+    proxies: Option<ProxyPool>,
+    tor_control: Option<Arc<TorControl>>,
+    seen_db: Option<SeenDb>,
+    unresolved: Option<Unresolved>,
+    archive: Option<Archive>,
+    checkpoint: Option<Arc<Checkpoint>>,
+    // where the results go when the scan is run as a stream
+    results: Option<async_channel::Sender<ProbeResult>>,
+    callbacks: Option<Arc<dyn Callbacks>>,
+    // sends the requests in place of the client of each worker
+    http: Option<Arc<dyn HttpClient>>,
+    // the targets given, the crawled and discovered pages left out
+    total: usize,
+    discover: Option<Discover>,
+    dedupe: Option<Dedupe>,
+    one_per_ip: Option<Dedupe>,
+    delay: Option<Delay>,
+    memory: Option<Arc<MemoryGuard>>,
+    hook: Option<Hook>,
+    plugins: Option<Plugins>,
+    script: Option<Script>,
+    fingerprints: Option<Fingerprints>,
+    banners: Option<Banners>,
+    non_http: NonHttp,
+    stopping: AtomicBool,
+}
+
+/**
+ * A technology detected on a page, with how sure the detection is out of 100
+ */
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct TechMatch {
+    pub name: String,
+    pub confidence: u8,
+}
+
+/**
+ * What the tls connection of a result showed, the certificate error with
+ * --verify-tls and its expiry with --cert-expiry-warn
+ */
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct TlsInfo {
+    pub error: Option<String>,
+    pub cert_expiry: Option<CertExpiry>,
+}
+
+/**
+ * The details collected while probing a single url
+ */
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ProbeResult {
+    pub url: String,
+    pub ip: Option<String>,
+    pub status_code: u16,
+    pub title: Option<String>,
+    pub tech: Vec<TechMatch>,
+    pub content_type: Option<String>,
+    pub content_length: Option<u64>,
+    pub words: Option<usize>,
+    pub lines: Option<usize>,
+    pub body_hash: Option<String>,
+    pub server: Option<String>,
+    pub hsts: Option<Hsts>,
+    pub tls: Option<TlsInfo>,
+    pub family: Option<IpFamily>,
+    pub ip_group: Option<String>,
+    pub methods: Option<Vec<String>>,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub cache_info: Option<CacheInfo>,
+    pub matches: Vec<String>,
+    pub links: Vec<String>,
+    pub emails: Vec<String>,
+    pub js_endpoints: Vec<String>,
+    pub js_secrets: Vec<String>,
+    pub plugin_fields: Vec<String>,
+    pub script_tags: Vec<String>,
+    pub tags: Vec<String>,
+    pub redirect_host: Option<String>,
+    pub banner: Option<String>,
+    pub timing: Option<Timing>,
+    // the size of the body and the time to the first byte, only kept to
+    // order the results by with --sort
+    pub length: Option<u64>,
+    pub latency: Option<Duration>,
+    pub bypasses: Vec<String>,
+    pub method_diff: BTreeMap<String, u16>,
+    // how many other results had the same body, with --unique
+    pub duplicates: Option<usize>,
+    pub error: Option<ProbeError>,
+    // a host that resolved but none of its probes got an answer
+    pub failed: bool,
+    // the body hash --unique counts the duplicates under
+    #[serde(skip)]
+    pub(crate) unique_hash: Option<String>,
+}
+
+impl ProbeResult {
+    /**
+     * The result as the structured output writes it, flattened and stamped
+     * with the current time
+     */
+    pub fn record(&self) -> Record {
+        Record::from_result(self)
+    }
+}
+
+/**
+ * Print the ascii banner
+ */
+fn print_banner() {
+    const BANNER: &str = r#"   
+    __              __   __ 
+   / /_  ________  / /__/ /_
+  / __ \/ ___/ _ \/ //_/ __/
+ / / / / /  /  __/ ,< / /_  
+/_/ /_/_/   \___/_/|_|\__/  
+                            
+                v0.1.6
+    "#;
+    eprintln!("{}", BANNER.white());
+}
+
+/**
+ * The options of a scan, taken by hrekt itself and by hrekt probe
+ */
+fn probe_args(command: Command) -> Command {
+    command
+        .arg(
+            Arg::new("rate")
+                .short('r')
+                .long("rate")
+                .default_value("1000")
+                .display_order(1)
+                .help("Maximum in-flight requests per second"),
+        )
+        .arg(
+            Arg::new("concurrency")
+                .short('c')
+                .long("concurrency")
+                .default_value("100")
+                .display_order(2)
+                .help("The amount of concurrent requests"),
+        )
+        .arg(
+            Arg::new("timeout")
+                .short('t')
+                .long("timeout")
+                .default_value("3")
+                .display_order(3)
+                .help("The delay between each request"),
+        )
+        .arg(
+            Arg::new("workers")
+                .short('w')
+                .long("workers")
+                .default_value("1")
+                .display_order(4)
+                .help("The amount of workers"),
+        )
+        .arg(
+            Arg::new("ports")
+                .short('p')
+                .long("ports")
+                .default_value("80,443")
+                .display_order(5)
+                .help("the ports to probe default ports are (80,443)"),
+        )
+        .arg(
+            Arg::new("title")
+                .long("title")
+                .hide_short_help(true)
+                .action(ArgAction::SetTrue)
+                .display_order(6)
+                .help("display the page titles"),
+        )
+        .arg(
+            Arg::new("tech-detect")
+                .long("tech-detect")
+                .hide_short_help(true)
+                .action(ArgAction::SetTrue)
+                .display_order(7)
+                .help("display the technology used"),
+        )
+        .arg(
+            Arg::new("status-code")
+                .long("status-code")
+                .hide_short_help(true)
+                .action(ArgAction::SetTrue)
+                .display_order(8)
+                .help("display the status-codes"),
+        )
+        .arg(
+            Arg::new("server")
+                .long("server")
+                .action(ArgAction::SetTrue)
+                .display_order(9)
+                .help("displays the server"),
+        )
+        .arg(
+            Arg::new("content-type")
+                .long("content-type")
+                .hide_short_help(true)
+                .action(ArgAction::SetTrue)
+                .display_order(10)
+                .help("displays the content type"),
+        )
+        .arg(
+            Arg::new("content-length")
+                .long("content-length")
+                .hide_short_help(true)
+                .action(ArgAction::SetTrue)
+                .display_order(11)
+                .help("displays the content length"),
+        )
+        .arg(
+            Arg::new("path")
+                .long("path")
+                .short('x')
+                .default_value("")
+                .display_order(12)
+                .help("probe the specified path, braces expand to several such as /{admin,login} or /v{1..3}"),
+        )
+        .arg(
+            Arg::new("body-regex")
+                .long("body-regex")
+                .hide_short_help(true)
+                .action(ArgAction::Append)
+                .display_order(13)
+                .help("regex to match in the response, repeatable and labelled with label:regex"),
+        )
+        .arg(
+            Arg::new("header-regex")
+                .long("header-regex")
+                .hide_short_help(true)
+                .default_value("")
+                .display_order(14)
+                .help("regex to be used to match a specific pattern in the header"),
+        )
+        .arg(
+            Arg::new("follow-redirects")
+                .short('l')
+                .long("follow-redirects")
+                .action(ArgAction::SetTrue)
+                .display_order(15)
+                .help("follow http redirects"),
+        )
+        .arg(
+            Arg::new("silent")
+                .short('q')
+                .long("silent")
+                .action(ArgAction::SetTrue)
+                .display_order(16)
+                .help("suppress output"),
+        )
+        .arg(
+            Arg::new("targets-file")
+                .long("targets-file")
+                .short('i')
+                .display_order(17)
+                .help("read the targets from a file of host,ports,path,timeout lines instead of stdin"),
+        )
+        .arg(
+            Arg::new("auto-rate")
+                .long("auto-rate")
+                .action(ArgAction::SetTrue)
+                .display_order(18)
+                .help("adjust the rate between --rate-min and --rate-max when hosts time out, fail or throttle"),
+        )
+        .arg(
+            Arg::new("rate-min")
+                .long("rate-min")
+                .hide_short_help(true)
+                .default_value("10")
+                .display_order(19)
+                .help("the lowest rate --auto-rate will back off to"),
+        )
+        .arg(
+            Arg::new("rate-max")
+                .long("rate-max")
+                .hide_short_help(true)
+                .default_value("5000")
+                .display_order(20)
+                .help("the highest rate --auto-rate will climb to, the --rate when only that is given"),
+        )
+        .arg(
+            Arg::new("wc")
+                .long("wc")
+                .hide_short_help(true)
+                .action(ArgAction::SetTrue)
+                .display_order(21)
+                .help("displays the word and line count of the body"),
+        )
+        .arg(
+            Arg::new("hash")
+                .long("hash")
+                .hide_short_help(true)
+                .action(ArgAction::SetTrue)
+                .display_order(22)
+                .help("displays the sha256 hash of the body"),
+        )
+        .arg(
+            Arg::new("filter-hash")
+                .long("filter-hash")
+                .hide_short_help(true)
+                .display_order(23)
+                .help("drop responses whose body hash is in this comma separated list"),
+        )
+        .arg(
+            Arg::new("unique")
+                .long("unique")
+                .hide_short_help(true)
+                .action(ArgAction::SetTrue)
+                .display_order(24)
+                .help("only display the first result for each distinct body, with how many others had it"),
+        )
+        .arg(
+            Arg::new("queue-size")
+                .long("queue-size")
+                .hide_short_help(true)
+                .default_value("1000")
+                .display_order(25)
+                .help("how many jobs can be queued for the workers before sending waits"),
+        )
+        .arg(
+            Arg::new("dns-timeout")
+                .long("dns-timeout")
+                .hide_short_help(true)
+                .default_value("3")
+                .display_order(26)
+                .help("seconds to wait for each dns lookup"),
+        )
+        .arg(
+            Arg::new("dns-retries")
+                .long("dns-retries")
+                .hide_short_help(true)
+                .default_value("2")
+                .display_order(27)
+                .help("how many times to retry a dns lookup that timed out"),
+        )
+        .arg(
+            Arg::new("resolve-only")
+                .long("resolve-only")
+                .action(ArgAction::SetTrue)
+                .display_order(28)
+                .help("only resolve the hosts and print their addresses and cname chain"),
+        )
+        .arg(
+            Arg::new("connect-only")
+                .long("connect-only")
+                .action(ArgAction::SetTrue)
+                .display_order(29)
+                .help("only report the host:port pairs that accept tcp connections"),
+        )
+        .arg(
+            Arg::new("sni")
+                .long("sni")
+                .hide_short_help(true)
+                .conflicts_with_all(["tor", "proxy-list"])
+                .display_order(30)
+                .help("the tls sni name to send instead of the target host"),
+        )
+        .arg(
+            Arg::new("hsts")
+                .long("hsts")
+                .hide_short_help(true)
+                .action(ArgAction::SetTrue)
+                .display_order(31)
+                .help("displays the strict-transport-security max-age and directives"),
+        )
+        .arg(
+            Arg::new("schedule")
+                .long("schedule")
+                .display_order(32)
+                .help("rerun the scan on a cron schedule, e.g. \"0 3 * * *\""),
+        )
+        .arg(
+            Arg::new("schedule-jitter")
+                .long("schedule-jitter")
+                .hide_short_help(true)
+                .default_value("0")
+                .display_order(33)
+                .help("wait up to this many extra seconds before each scheduled scan"),
+        )
+        .arg(
+            Arg::new("methods-discovery")
+                .long("methods-discovery")
+                .hide_short_help(true)
+                .action(ArgAction::SetTrue)
+                .display_order(34)
+                .help("discover the allowed methods and flag PUT, DELETE and TRACE"),
+        )
+        .arg(
+            Arg::new("etag")
+                .long("etag")
+                .hide_short_help(true)
+                .action(ArgAction::SetTrue)
+                .display_order(35)
+                .help("displays the etag, with --schedule unchanged pages are skipped"),
+        )
+        .arg(
+            Arg::new("last-modified")
+                .long("last-modified")
+                .hide_short_help(true)
+                .action(ArgAction::SetTrue)
+                .display_order(36)
+                .help("displays the last-modified date, with --schedule unchanged pages are skipped"),
+        )
+        .arg(
+            Arg::new("cache-info")
+                .long("cache-info")
+                .hide_short_help(true)
+                .action(ArgAction::SetTrue)
+                .display_order(37)
+                .help("displays the caching headers and flags cacheable responses setting cookies"),
+        )
+        .arg(
+            Arg::new("regex-file")
+                .long("regex-file")
+                .hide_short_help(true)
+                .display_order(38)
+                .help("file of regexes, one label:regex per line, listed when found in the headers or body"),
+        )
+        .arg(
+            Arg::new("detect-secrets")
+                .long("detect-secrets")
+                .hide_short_help(true)
+                .num_args(0..=1)
+                .default_missing_value("all")
+                .display_order(39)
+                .help("hunt for secrets with the built-in packs: aws, gcp, jwt, slack, private-key or all"),
+        )
+        .arg(
+            Arg::new("extract-links")
+                .long("extract-links")
+                .hide_short_help(true)
+                .action(ArgAction::SetTrue)
+                .display_order(40)
+                .help("displays the links found in the response body"),
+        )
+        .arg(
+            Arg::new("extract-emails")
+                .long("extract-emails")
+                .hide_short_help(true)
+                .action(ArgAction::SetTrue)
+                .display_order(41)
+                .help("displays the email addresses found in the response body"),
+        )
+        .arg(
+            Arg::new("extract-all-domains")
+                .long("extract-all-domains")
+                .hide_short_help(true)
+                .action(ArgAction::SetTrue)
+                .display_order(42)
+                .help("keep the extracted links and emails of other domains too"),
+        )
+        .arg(
+            Arg::new("crawl-depth")
+                .long("crawl-depth")
+                .hide_short_help(true)
+                .default_value("0")
+                .display_order(43)
+                .help("follow the same-host links found in the pages up to this depth"),
+        )
+        .arg(
+            Arg::new("crawl-max-pages")
+                .long("crawl-max-pages")
+                .hide_short_help(true)
+                .default_value("50")
+                .display_order(44)
+                .help("the most pages crawled on each host"),
+        )
+        .arg(
+            Arg::new("js-scrape")
+                .long("js-scrape")
+                .hide_short_help(true)
+                .action(ArgAction::SetTrue)
+                .display_order(45)
+                .help("fetch the scripts a page loads and display the endpoints and secrets in them"),
+        )
+        .arg(
+            Arg::new("js-max-files")
+                .long("js-max-files")
+                .hide_short_help(true)
+                .default_value("10")
+                .display_order(46)
+                .help("the most scripts fetched for each page"),
+        )
+        .arg(
+            Arg::new("js-max-size")
+                .long("js-max-size")
+                .hide_short_help(true)
+                .default_value("1024")
+                .display_order(47)
+                .help("the most kilobytes read from each script"),
+        )
+        .arg(
+            Arg::new("output-xml")
+                .long("output-xml")
+                .hide_short_help(true)
+                .display_order(48)
+                .help("also write the results to a file as nmap style xml"),
+        )
+        .arg(
+            Arg::new("nmap-input")
+                .long("nmap-input")
+                .hide_short_help(true)
+                .display_order(49)
+                .help("probe the open http ports of an nmap xml scan instead of reading stdin"),
+        )
+        .arg(
+            Arg::new("masscan-input")
+                .long("masscan-input")
+                .hide_short_help(true)
+                .display_order(50)
+                .help("probe the open ports of masscan -oJ or -oL output instead of reading stdin"),
+        )
+        .arg(
+            Arg::new("seen-db")
+                .long("seen-db")
+                .hide_short_help(true)
+                .display_order(51)
+                .help("file recording every url reported across runs"),
+        )
+        .arg(
+            Arg::new("new-only")
+                .long("new-only")
+                .hide_short_help(true)
+                .action(ArgAction::SetTrue)
+                .requires("seen-db")
+                .display_order(52)
+                .help("only display the urls no earlier run in the --seen-db reported"),
+        )
+        .arg(
+            Arg::new("dedupe")
+                .long("dedupe")
+                .hide_short_help(true)
+                .value_parser(["exact", "bloom"])
+                .display_order(53)
+                .help("drop the repeated input lines and result urls, bloom uses far less memory but may drop a few unique ones"),
+        )
+        .arg(
+            Arg::new("dedupe-bits")
+                .long("dedupe-bits")
+                .hide_short_help(true)
+                .default_value("134217728")
+                .display_order(54)
+                .help("the size of each bloom filter in bits"),
+        )
+        .arg(
+            Arg::new("max-memory")
+                .long("max-memory")
+                .hide_short_help(true)
+                .display_order(55)
+                .help("hold back new jobs as the resident memory nears this size, e.g. 2G"),
+        )
+        .arg(
+            Arg::new("dry-run")
+                .long("dry-run")
+                .hide_short_help(true)
+                .action(ArgAction::SetTrue)
+                .display_order(56)
+                .help("print the urls that would be probed and how long it would take without probing them"),
+        )
+        .arg(
+            Arg::new("bench")
+                .long("bench")
+                .hide_short_help(true)
+                .action(ArgAction::SetTrue)
+                .display_order(57)
+                .help("measure the requests per second this machine reaches against a local server"),
+        )
+        .arg(
+            Arg::new("store-response-dir")
+                .long("store-response-dir")
+                .hide_short_help(true)
+                .display_order(58)
+                .help("save every response in this directory for the analyze command"),
+        )
+        .arg(
+            Arg::new("plugin")
+                .long("plugin")
+                .hide_short_help(true)
+                .action(ArgAction::Append)
+                .display_order(59)
+                .help("run a wasm plugin over every response, repeatable"),
+        )
+        .arg(
+            Arg::new("script")
+                .long("script")
+                .hide_short_help(true)
+                .display_order(60)
+                .help("run a rhai script over every result to filter, tag or rewrite it"),
+        )
+        .arg(
+            Arg::new("on-match")
+                .long("on-match")
+                .hide_short_help(true)
+                .display_order(61)
+                .help("run a shell command for each result, {} {url} {host} {port} {ip} {status} {title} are filled in"),
+        )
+        .arg(
+            Arg::new("on-match-codes")
+                .long("on-match-codes")
+                .hide_short_help(true)
+                .display_order(62)
+                .help("only run --on-match for the results with these status codes, e.g. 200,401"),
+        )
+        .arg(
+            Arg::new("stats")
+                .long("stats")
+                .hide_short_help(true)
+                .action(ArgAction::SetTrue)
+                .display_order(63)
+                .help("keep a live line with the request rate, progress, errors and eta on stderr"),
+        )
+        .arg(
+            Arg::new("stats-interval")
+                .long("stats-interval")
+                .hide_short_help(true)
+                .display_order(64)
+                .help("print the request rate, progress, errors and eta as a new line this often, e.g. 10s"),
+        )
+        .arg(
+            Arg::new("verify-tls")
+                .long("verify-tls")
+                .hide_short_help(true)
+                .action(ArgAction::SetTrue)
+                .display_order(65)
+                .help("validate the certificates and report the invalid ones in a tls-error field"),
+        )
+        .arg(
+            Arg::new("ca-file")
+                .long("ca-file")
+                .hide_short_help(true)
+                .display_order(66)
+                .help("trust the certificates in this pem bundle as well, implies --verify-tls"),
+        )
+        .arg(
+            Arg::new("cert-expiry-warn")
+                .long("cert-expiry-warn")
+                .hide_short_help(true)
+                .display_order(67)
+                .help("flag the certificates that expired or expire within these days, e.g. 30d"),
+        )
+        .arg(
+            Arg::new("san-feedback")
+                .long("san-feedback")
+                .hide_short_help(true)
+                .action(ArgAction::SetTrue)
+                .display_order(68)
+                .help("probe the names found in the certificates that aren't among the targets"),
+        )
+        .arg(
+            Arg::new("san-scope")
+                .long("san-scope")
+                .hide_short_help(true)
+                .requires("san-feedback")
+                .display_order(69)
+                .help("only probe the certificate names ending in these domains, e.g. example.com,example.org"),
+        )
+        .arg(
+            Arg::new("source-ip")
+                .long("source-ip")
+                .hide_short_help(true)
+                .display_order(70)
+                .help("the local address to send the requests from"),
+        )
+        .arg(
+            Arg::new("interface")
+                .long("interface")
+                .hide_short_help(true)
+                .display_order(71)
+                .help("the network interface to send the requests through, e.g. eth1 (linux only)"),
+        )
+        .arg(
+            Arg::new("prefer-ipv4")
+                .long("prefer-ipv4")
+                .hide_short_help(true)
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["prefer-ipv6", "dual-stack", "source-ip"])
+                .display_order(72)
+                .help("probe over ipv4 when a host has both, ipv6 when it's all there is"),
+        )
+        .arg(
+            Arg::new("prefer-ipv6")
+                .long("prefer-ipv6")
+                .hide_short_help(true)
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["dual-stack", "source-ip"])
+                .display_order(73)
+                .help("probe over ipv6 when a host has both, ipv4 when it's all there is"),
+        )
+        .arg(
+            Arg::new("dual-stack")
+                .long("dual-stack")
+                .hide_short_help(true)
+                .action(ArgAction::SetTrue)
+                .conflicts_with("source-ip")
+                .display_order(74)
+                .help("probe over both ipv4 and ipv6 and report each separately"),
+        )
+        .arg(
+            Arg::new("port-scheme")
+                .long("port-scheme")
+                .hide_short_help(true)
+                .display_order(75)
+                .help("the scheme to probe these ports with, e.g. 8443=https,8080=http,9000=both"),
+        )
+        .arg(
+            Arg::new("no-scheme-detect")
+                .long("no-scheme-detect")
+                .hide_short_help(true)
+                .action(ArgAction::SetTrue)
+                .display_order(76)
+                .help("probe the ports missing from the scheme table with both schemes instead of detecting tls"),
+        )
+        .arg(
+            Arg::new("one-per-ip")
+                .long("one-per-ip")
+                .hide_short_help(true)
+                .action(ArgAction::SetTrue)
+                .display_order(77)
+                .help("probe only the first of the hosts resolving to the same address on each port"),
+        )
+        .arg(
+            Arg::new("ip-group")
+                .long("ip-group")
+                .hide_short_help(true)
+                .action(ArgAction::SetTrue)
+                .display_order(78)
+                .help("label every result with the address its host resolved to"),
+        )
+        .arg(
+            Arg::new("delay")
+                .long("delay")
+                .hide_short_help(true)
+                .display_order(79)
+                .help("wait this long before every request a worker sends, e.g. 200ms"),
+        )
+        .arg(
+            Arg::new("jitter")
+                .long("jitter")
+                .hide_short_help(true)
+                .display_order(80)
+                .help("wait a random time in this range before every request as well, e.g. 0-500ms"),
+        )
+        .arg(
+            Arg::new("shuffle")
+                .long("shuffle")
+                .hide_short_help(true)
+                .action(ArgAction::SetTrue)
+                .display_order(81)
+                .help("probe the targets in a random order"),
+        )
+        .arg(
+            Arg::new("digest-auth")
+                .long("digest-auth")
+                .hide_short_help(true)
+                .display_order(82)
+                .help("answer the http digest challenges with these credentials, e.g. admin:admin"),
+        )
+        .arg(
+            Arg::new("ntlm")
+                .long("ntlm")
+                .hide_short_help(true)
+                .display_order(83)
+                .help("go through the ntlm handshake with these credentials, e.g. CORP/user:pass"),
+        )
+        .arg(
+            Arg::new("tag")
+                .long("tag")
+                .hide_short_help(true)
+                .action(ArgAction::Append)
+                .display_order(84)
+                .help("stamp every result with this tag, e.g. the program or the scan batch, can be given more than once"),
+        )
+        .arg(
+            Arg::new("json")
+                .long("json")
+                .hide_short_help(true)
+                .action(ArgAction::SetTrue)
+                .display_order(85)
+                .help("print the results as json lines, see hrekt schema for their layout"),
+        )
+        .arg(
+            Arg::new("output-encoding")
+                .long("output-encoding")
+                .hide_short_help(true)
+                .conflicts_with("json")
+                .display_order(86)
+                .help("write the results as text, json, proto or msgpack, proto being length prefixed messages of schema/result.proto"),
+        )
+        .arg(
+            Arg::new("output")
+                .short('o')
+                .long("output")
+                .hide_short_help(true)
+                .display_order(87)
+                .help("write the results to this file instead of stdout, compressed when it ends in .gz or .zst"),
+        )
+        .arg(
+            Arg::new("output-compress")
+                .long("output-compress")
+                .hide_short_help(true)
+                .display_order(88)
+                .help("compress the results as they are written, gzip or zstd"),
+        )
+        .arg(
+            Arg::new("host-rate")
+                .long("host-rate")
+                .hide_short_help(true)
+                .display_order(89)
+                .help("send each host at most this many requests a second, 0 for no limit"),
+        )
+        .arg(
+            Arg::new("stealth")
+                .long("stealth")
+                .hide_short_help(true)
+                .action(ArgAction::SetTrue)
+                .display_order(90)
+                .help("probe slowly and quietly, a low rate, one request a second per host, a random order, jitter and browser headers"),
+        )
+        .arg(
+            Arg::new("browser-headers")
+                .long("browser-headers")
+                .hide_short_help(true)
+                .action(ArgAction::SetTrue)
+                .display_order(91)
+                .help("send the user agent, accept, client hint and sec-fetch headers chrome sends"),
+        )
+        .arg(
+            Arg::new("proxy-list")
+                .long("proxy-list")
+                .hide_short_help(true)
+                .display_order(93)
+                .help("spread the probes over the http or socks5 proxies in this file, dropping the dead ones"),
+        )
+        .arg(
+            Arg::new("proxy-rotate")
+                .long("proxy-rotate")
+                .hide_short_help(true)
+                .default_value("1")
+                .display_order(94)
+                .help("move to the next proxy after this many probes"),
+        )
+        .arg(
+            Arg::new("tor")
+                .long("tor")
+                .hide_short_help(true)
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all([
+                    "proxy-list",
+                    "tech-detect",
+                    "render",
+                    "resolve-only",
+                    "connect-only",
+                ])
+                .display_order(95)
+                .help("send every request through tor, the hosts are resolved by tor as well"),
+        )
+        .arg(
+            Arg::new("tor-proxy")
+                .long("tor-proxy")
+                .hide_short_help(true)
+                .default_value("127.0.0.1:9050")
+                .display_order(96)
+                .help("the socks port of tor"),
+        )
+        .arg(
+            Arg::new("tor-newnym")
+                .long("tor-newnym")
+                .hide_short_help(true)
+                .display_order(97)
+                .help("ask tor for a new circuit every this many requests"),
+        )
+        .arg(
+            Arg::new("tor-control")
+                .long("tor-control")
+                .hide_short_help(true)
+                .default_value("127.0.0.1:9051")
+                .display_order(98)
+                .help("the control port of tor, used for the new circuits"),
+        )
+        .arg(
+            Arg::new("tor-password")
+                .long("tor-password")
+                .hide_short_help(true)
+                .display_order(99)
+                .help("the password of the tor control port"),
+        )
+        .arg(
+            Arg::new("resolve")
+                .long("resolve")
+                .hide_short_help(true)
+                .action(ArgAction::Append)
+                .display_order(100)
+                .help("resolve the host to this address instead of asking dns, e.g. example.com:1.2.3.4, can be given more than once"),
+        )
+        .arg(
+            Arg::new("hosts-file")
+                .long("hosts-file")
+                .hide_short_help(true)
+                .display_order(101)
+                .help("resolve the hosts in this file laid out like /etc/hosts to their addresses instead of asking dns"),
+        )
+        .arg(
+            Arg::new("same-host-only")
+                .long("same-host-only")
+                .hide_short_help(true)
+                .action(ArgAction::SetTrue)
+                .requires("follow-redirects")
+                .display_order(102)
+                .help("only follow the redirects staying on the host, dropping the targets that redirect elsewhere"),
+        )
+        .arg(
+            Arg::new("render")
+                .long("render")
+                .hide_short_help(true)
+                .action(ArgAction::SetTrue)
+                .display_order(103)
+                .help("load the pages in chrome and match the title and regexes against the rendered dom"),
+        )
+        .arg(
+            Arg::new("chrome-path")
+                .long("chrome-path")
+                .hide_short_help(true)
+                .display_order(104)
+                .help("the chrome or chromium binary to launch, the CHROME variable is used when it's left out"),
+        )
+        .arg(
+            Arg::new("chrome-args")
+                .long("chrome-args")
+                .hide_short_help(true)
+                .allow_hyphen_values(true)
+                .display_order(105)
+                .help("comma separated flags passed to chrome when it's launched, such as --no-sandbox"),
+        )
+        .arg(
+            Arg::new("browser-max-pages")
+                .long("browser-max-pages")
+                .hide_short_help(true)
+                .display_order(106)
+                .help("launch a fresh chrome after this many pages were loaded in it"),
+        )
+        .arg(
+            Arg::new("browser-max-mem")
+                .long("browser-max-mem")
+                .hide_short_help(true)
+                .display_order(107)
+                .help("launch a fresh chrome once its processes use more than this much memory, e.g. 1G"),
+        )
+        .arg(
+            Arg::new("fingerprints")
+                .long("fingerprints")
+                .hide_short_help(true)
+                .requires("tech-detect")
+                .display_order(108)
+                .help("detect the technologies of this wappalyzer technologies.json too"),
+        )
+        .arg(
+            Arg::new("tech-filter")
+                .long("tech-filter")
+                .hide_short_help(true)
+                .requires("tech-detect")
+                .display_order(109)
+                .help("only report the technologies of these comma separated categories or names, e.g. cms,framework"),
+        )
+        .arg(
+            Arg::new("tech-filter-drop")
+                .long("tech-filter-drop")
+                .hide_short_help(true)
+                .action(ArgAction::SetTrue)
+                .requires("tech-filter")
+                .display_order(110)
+                .help("drop the results left without a technology by --tech-filter"),
+        )
+        .arg(
+            Arg::new("tech-min-confidence")
+                .long("tech-min-confidence")
+                .hide_short_help(true)
+                .requires("tech-detect")
+                .display_order(111)
+                .help("only report the technologies detected with at least this confidence, from 0 to 100"),
+        )
+        .arg(
+            Arg::new("banner")
+                .long("banner")
+                .hide_short_help(true)
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["tor", "proxy-list"])
+                .display_order(112)
+                .help("report the first line the ports that don't speak http greet with, such as ssh, ftp and smtp"),
+        )
+        .arg(
+            Arg::new("timing")
+                .long("timing")
+                .hide_short_help(true)
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["tor", "proxy-list"])
+                .display_order(113)
+                .help("report how long the dns lookup, connect, tls handshake, first byte and whole request took"),
+        )
+        .arg(
+            Arg::new("sort")
+                .long("sort")
+                .hide_short_help(true)
+                .display_order(114)
+                .help("hold the results back and write them at the end ordered by status, length, url or latency"),
+        )
+        .arg(
+            Arg::new("group-by")
+                .long("group-by")
+                .hide_short_help(true)
+                .conflicts_with_all(["tor", "proxy-list"])
+                .display_order(115)
+                .help("hold the results back and write them at the end nested under the ip their hosts resolve to"),
+        )
+        .arg(
+            Arg::new("stats-json")
+                .long("stats-json")
+                .hide_short_help(true)
+                .display_order(116)
+                .help("write the request counters and the count of each status code and class to this file as json"),
+        )
+        .arg(
+            Arg::new("include-errors")
+                .long("include-errors")
+                .hide_short_help(true)
+                .action(ArgAction::SetTrue)
+                .display_order(117)
+                .help("report the probes that failed with the kind of error, such as dns_nxdomain, tcp_refused or tls_error"),
+        )
+        .arg(
+            Arg::new("include-failed")
+                .long("include-failed")
+                .hide_short_help(true)
+                .action(ArgAction::SetTrue)
+                .display_order(118)
+                .help("report the hosts that resolved but never answered as failed, with the reason"),
+        )
+        .arg(
+            Arg::new("unresolved")
+                .long("unresolved")
+                .hide_short_help(true)
+                .display_order(119)
+                .help("write the input hosts that have no A or AAAA record to this file, one per line"),
+        )
+        .arg(
+            Arg::new("discover")
+                .long("discover")
+                .hide_short_help(true)
+                .display_order(120)
+                .help("try the paths of this wordlist on every origin that answers, skipping what looks like its not found page"),
+        )
+        .arg(
+            Arg::new("bypass-403")
+                .long("bypass-403")
+                .hide_short_help(true)
+                .action(ArgAction::SetTrue)
+                .display_order(122)
+                .help("retry the 403 pages with known bypasses, such as spoofed ip headers, path tricks and other methods, and report the ones that changed the status"),
+        )
+        .arg(
+            Arg::new("method-diff")
+                .long("method-diff")
+                .hide_short_help(true)
+                .action(ArgAction::SetTrue)
+                .display_order(123)
+                .help("send an empty POST and PUT to each url as well and flag the ones answering them in another status class than GET"),
+        )
+        .arg(
+            Arg::new("archive")
+                .long("archive")
+                .hide_short_help(true)
+                .display_order(124)
+                .help("record every request and the response it got in this compressed, indexed archive"),
+        )
+        .arg(
+            Arg::new("checkpoint")
+                .long("checkpoint")
+                .hide_short_help(true)
+                .display_order(125)
+                .help("write how far the scan got and the stats so far to this file as json, so a crash loses at most the work since"),
+        )
+        .arg(
+            Arg::new("checkpoint-interval")
+                .long("checkpoint-interval")
+                .hide_short_help(true)
+                .requires("checkpoint")
+                .display_order(126)
+                .help("how often the checkpoint is written, such as 10s or 5m, 30s by default"),
+        )
+        .arg(
+            Arg::new("checkpoint-results")
+                .long("checkpoint-results")
+                .hide_short_help(true)
+                .requires("checkpoint")
+                .display_order(127)
+                .help("write the checkpoint every this many results as well"),
+        )
+        .arg(
+            Arg::new("discover-limit")
+                .long("discover-limit")
+                .hide_short_help(true)
+                .requires("discover")
+                .display_order(121)
+                .help("the most words of the --discover wordlist tried, 50 by default"),
+        )
+}
+
+/**
+ * Build the command line interface
+ */
+fn build_cli() -> Command {
+    probe_args(
+        Command::new("hrekt")
+            .version("0.1.6")
+            .author("Blake Jacobs <krypt0mux@gmail.com>")
+            .about("really fast http prober, run without a subcommand it probes like hrekt probe"),
+    )
+    .subcommand(probe_args(
+        Command::new("probe").about("probe the targets, the same as hrekt without a subcommand"),
+    ))
+    .subcommand(
+        Command::new("analyze")
+            .about("run the title, regex and filter options over the responses saved with --store-response-dir")
+            .arg(
+                Arg::new("dir")
+                    .required(true)
+                    .help("the directory the responses were saved in"),
+            ),
+    )
+    .subcommand(
+        Command::new("report")
+            .about("summarize --json results: the status codes, errors, and the most common tech, servers and titles")
+            .arg(
+                Arg::new("input")
+                    .required(true)
+                    .help("the file of --json results, gzip or zstd compressed too, or - for stdin"),
+            )
+            .arg(
+                Arg::new("top")
+                    .long("top")
+                    .default_value("10")
+                    .help("how many of the most common tech, servers and titles to list"),
+            ),
+    )
+    .subcommand(
+        Command::new("diff")
+            .about("compare the --json results of two runs and list the urls that are new, gone or changed")
+            .arg(
+                Arg::new("old")
+                    .required(true)
+                    .help("the earlier results"),
+            )
+            .arg(
+                Arg::new("new")
+                    .required(true)
+                    .help("the later results"),
+            )
+            .arg(
+                Arg::new("json")
+                    .long("json")
+                    .action(ArgAction::SetTrue)
+                    .help("print the changes as json lines"),
+            ),
+    )
+    .subcommand(
+        Command::new("serve")
+            .about("serve --json results as an html report, csv and json over http")
+            .arg(
+                Arg::new("input")
+                    .required(true)
+                    .help("the file of --json results, read again on every request"),
+            )
+            .arg(
+                Arg::new("listen")
+                    .long("listen")
+                    .default_value("127.0.0.1:8080")
+                    .help("the address to listen on"),
+            ),
+    )
+    .subcommand(Command::new("schema").about("print the json schema of the --json results"))
+    .subcommand(
+        Command::new("convert")
+            .about("convert the --json results to csv, html, xml or txt without scanning again")
+            .arg(
+                Arg::new("input")
+                    .required(true)
+                    .help("the file of --json results, gzip or zstd compressed too, or - for stdin"),
+            )
+            .arg(
+                Arg::new("to")
+                    .long("to")
+                    .required(true)
+                    .help("the format to convert to: csv, html, xml (laid out like nmap's) or txt"),
+            )
+            .arg(
+                Arg::new("output")
+                    .short('o')
+                    .long("output")
+                    .help("the file to write, stdout by default"),
+            ),
+    )
+    .subcommand(
+        Command::new("archive")
+            .about("read an --archive back as json lines, one exchange a line")
+            .arg(
+                Arg::new("input")
+                    .required(true)
+                    .help("the archive to read, cut short by an interrupted scan too"),
+            )
+            .arg(
+                Arg::new("output")
+                    .short('o')
+                    .long("output")
+                    .help("the file to write, stdout by default"),
+            ),
+    )
+    .subcommand(
+        Command::new("merge")
+            .about("merge the --json results of several runs, keeping the most recent result of each url")
+            .arg(
+                Arg::new("inputs")
+                    .required(true)
+                    .num_args(1..)
+                    .help("the files of --json results, gzip or zstd compressed too, or - for stdin"),
+            )
+            .arg(
+                Arg::new("output")
+                    .short('o')
+                    .long("output")
+                    .help("the file to write, compressed by a .gz or .zst extension, stdout by default"),
+            ),
+    )
+    .subcommand(
+        Command::new("completions")
+            .about("generate shell completions")
+            .arg(
+                Arg::new("shell")
+                    .required(true)
+                    .value_parser(value_parser!(Shell))
+                    .help("the shell to generate completions for"),
+            ),
+    )
+}
+
+/**
+ * The command line, as the hrekt binary runs it
+ */
+pub fn main() -> Result<(), Box<dyn Error + Send + Sync + 'static>> {
+    // parse the cli arguments
+    let matches = build_cli().get_matches();
+
+    // print the shell completions and exit
+    if let Some(("completions", sub_matches)) = matches.subcommand() {
+        if let Some(shell) = sub_matches.get_one::<Shell>("shell") {
+            generate(*shell, &mut build_cli(), "hrekt", &mut std::io::stdout());
+        }
+        return Ok(());
+    }
+
+    // print the json schema and exit
+    if let Some(("schema", _)) = matches.subcommand() {
+        print!("{}", output::SCHEMA);
+        return Ok(());
+    }
+
+    // summarize the results of an earlier scan and exit
+    if let Some(("report", sub_matches)) = matches.subcommand() {
+        let input = sub_matches.get_one::<String>("input").unwrap();
+        let top = match sub_matches
+            .get_one::<String>("top")
+            .unwrap()
+            .parse::<usize>()
+        {
+            Ok(top) => top,
+            Err(_) => {
+                eprintln!("{}", "invalid --top, it has to be a number".red());
+                return Ok(());
+            }
+        };
+        match convert::read_records(input) {
+            Ok(records) => summary::Summary::from_records(&records).print(top),
+            Err(err) => eprintln!("{}", format!("could not read {}: {}", input, err).red()),
+        }
+        return Ok(());
+    }
+
+    // compare the results of two scans and exit
+    if let Some(("diff", sub_matches)) = matches.subcommand() {
+        let old = sub_matches.get_one::<String>("old").unwrap();
+        let new = sub_matches.get_one::<String>("new").unwrap();
+        let records = convert::read_records(old)
+            .map_err(|err| format!("could not read {}: {}", old, err))
+            .and_then(|old| {
+                convert::read_records(new)
+                    .map(|new| (old, new))
+                    .map_err(|err| format!("could not read {}: {}", new, err))
+            });
+        match records {
+            Ok((old, new)) => {
+                for change in diff::diff(old, new) {
+                    if sub_matches.get_flag("json") {
+                        println!("{}", serde_json::to_string(&change).unwrap_or_default());
+                    } else {
+                        println!("{}", diff::format_change(&change));
+                    }
+                }
+            }
+            Err(err) => eprintln!("{}", err.red()),
+        }
+        return Ok(());
+    }
+
+    // serve the results of a scan until stopped
+    if let Some(("serve", sub_matches)) = matches.subcommand() {
+        let input = sub_matches.get_one::<String>("input").unwrap().clone();
+        let listen = sub_matches.get_one::<String>("listen").unwrap().clone();
+        if input == "-" {
+            eprintln!(
+                "{}",
+                "serve reads its input again on every request, give it a file".red()
+            );
+            return Ok(());
+        }
+        let rt = Builder::new_multi_thread().enable_all().build()?;
+        if let Err(err) = rt.block_on(serve::serve(input, listen.clone())) {
+            eprintln!(
+                "{}",
+                format!("could not serve on {}: {}", listen, err).red()
+            );
+        }
+        return Ok(());
+    }
+
+    // merge the results of earlier scans and exit
+    if let Some(("merge", sub_matches)) = matches.subcommand() {
+        let inputs = sub_matches
+            .get_many::<String>("inputs")
+            .unwrap()
+            .cloned()
+            .collect::<Vec<String>>();
+        let output = sub_matches.get_one::<String>("output").map(|s| s.as_str());
+        match merge::merge(&inputs, output) {
+            Ok(merged) => eprintln!(
+                "{}",
+                format!(
+                    "[merge] {} results read, {} unique urls written",
+                    merged.read, merged.written
+                )
+                .white()
+            ),
+            Err(err) => eprintln!("{}", format!("could not merge: {}", err).red()),
+        }
+        return Ok(());
+    }
+
+    // convert the results of an earlier scan and exit
+    if let Some(("convert", sub_matches)) = matches.subcommand() {
+        let format = match convert::Format::parse(sub_matches.get_one::<String>("to").unwrap()) {
+            Ok(format) => format,
+            Err(err) => {
+                eprintln!("{}", format!("could not parse --to: {}", err).red());
+                return Ok(());
+            }
+        };
+        let input = sub_matches.get_one::<String>("input").unwrap();
+        let converted = convert::read_records(input).and_then(|records| {
+            convert::convert(
+                &records,
+                format,
+                sub_matches.get_one::<String>("output").map(|s| s.as_str()),
+            )
+        });
+        if let Err(err) = converted {
+            eprintln!("{}", format!("could not convert {}: {}", input, err).red());
+        }
+        return Ok(());
+    }
+
+    // read an archive back and exit
+    if let Some(("archive", sub_matches)) = matches.subcommand() {
+        let input = sub_matches.get_one::<String>("input").unwrap();
+        let output = sub_matches.get_one::<String>("output").map(|s| s.as_str());
+        if let Err(err) = archive::dump_archive(input, output) {
+            eprintln!("{}", format!("could not read {}: {}", input, err).red());
+        }
+        return Ok(());
+    }
+
+    // hrekt probe takes the options hrekt alone does
+    let probe_matches = matches.subcommand_matches("probe").cloned();
+    let matches = probe_matches.unwrap_or(matches);
+
+    let w: usize = match matches.get_one::<String>("workers").map(|s| s.to_string()) {
+        Some(w) => match w.parse::<usize>() {
+            Ok(w) => w,
+            Err(_) => 1,
+        },
+        None => {
+            println!("{}", "could not parse workers, using default of 1");
+            1
+        }
+    };
+
+    // Set up the runtime with the number of threads specified from the arguments
+    let rt = Builder::new_multi_thread()
+        .enable_all()
+        .worker_threads(w.max(1))
+        .build()?;
+    rt.block_on(run(matches))
+}
+
+/**
+ * Parse the scan settings and run the scans
+ */
+async fn run(matches: ArgMatches) -> Result<(), Box<dyn Error + Send + Sync + 'static>> {
+    // keep the colors out of files and pipes unless CLICOLOR_FORCE asks for them
+    if (!io::stdout().is_terminal() || matches.contains_id("output"))
+        && env::var_os("CLICOLOR_FORCE").is_none()
+    {
+        colored::control::set_override(false);
+    }
+
+    let silent = matches.get_flag("silent");
+    if !silent && io::stderr().is_terminal() {
+        print_banner();
+    }
+
+    let scan = match configure(&matches, None).await? {
+        Some(scan) => scan,
+        None => return Ok(()),
+    };
+    let Scan {
+        targets,
+        job,
+        settings,
+        analyze_dir,
+        schedule,
+        schedule_jitter,
+        conditional,
+    } = scan;
+
+    if let Some(analyze_dir) = analyze_dir {
+        store::analyze(&analyze_dir, &job, &settings).await?;
+        return Ok(());
+    }
+
+    // only show what the scan would do
+    if matches.get_flag("dry-run") {
+        dryrun::dry_run(&targets, &job, &settings);
+        return Ok(());
+    }
+
+    // rerun the scan on the schedule, otherwise scan once
+    if let Some(schedule) = schedule {
+        // later scans only report the pages that changed since the previous one
+        let validators = if conditional {
+            Some(Arc::new(Validators::default()))
+        } else {
+            None
+        };
+
+        loop {
+            let next = match schedule.next_after(Local::now()) {
+                Some(next) => next,
+                None => {
+                    eprintln!("{}", "the schedule never matches a date".red());
+                    break;
+                }
+            };
+            let mut wait = (next - Local::now()).to_std().unwrap_or_default();
+            if schedule_jitter > 0 {
+                wait += Duration::from_secs(rand::thread_rng().gen_range(0..=schedule_jitter));
+            }
+            if !silent {
+                eprintln!(
+                    "{}",
+                    format!("[schedule] next scan at {}", next.format("%Y-%m-%d %H:%M")).white()
+                );
+            }
+            // a signal while waiting ends the schedule as it would a scan
+            tokio::select! {
+                _ = tokio::time::sleep(wait) => {}
+                _ = stop_signal() => break,
+            }
+            let stopped = run_scan(
+                targets.clone(),
+                job.clone(),
+                settings.clone(),
+                validators.clone(),
+                Embedding::cli(),
+            )
+            .await;
+            if stopped {
+                break;
+            }
+        }
+    } else {
+        run_scan(targets, job, settings, None, Embedding::cli()).await;
+    }
+
+    Ok(())
+}
+
+/**
+ * Where a scan run from code hands its results, what it sends its requests
+ * through and what stops it, all left unset on the command line
+ */
+#[derive(Default)]
+struct Embedding {
+    // the binary owns the signals and stderr, a scan run from code leaves
+    // them to the program it runs in
+    cli: bool,
+    results: Option<async_channel::Sender<ProbeResult>>,
+    callbacks: Option<Arc<dyn Callbacks>>,
+    http: Option<Arc<dyn HttpClient>>,
+    stop: Option<watch::Receiver<bool>>,
+}
+
+impl Embedding {
+    fn cli() -> Embedding {
+        Embedding {
+            cli: true,
+            ..Default::default()
+        }
+    }
+}
+
+/**
+ * A scan as the options describe it, ready to run
+ */
+struct Scan {
+    targets: Vec<Target>,
+    job: Job,
+    settings: Arc<Settings>,
+    analyze_dir: Option<String>,
+    schedule: Option<Schedule>,
+    schedule_jitter: u64,
+    // the scheduled scans after the first only report the pages that changed
+    conditional: bool,
+}
+
+/**
+ * Read the options and the targets into a scan, None once a wrong option
+ * was reported or when there's nothing to scan, such as after --bench. The
+ * targets are read from the input unless they're given
+ */
+async fn configure(
+    matches: &ArgMatches,
+    given_targets: Option<Vec<Target>>,
+) -> Result<Option<Scan>, Box<dyn Error + Send + Sync + 'static>> {
+    let silent = matches.get_flag("silent");
+    let status_codes = matches.get_flag("status-code");
+
+    // the stealth preset only stands in for the options left unset
+    let stealth = matches.get_flag("stealth");
+    let stealth_default =
+        |id: &str| stealth && matches.value_source(id) != Some(ValueSource::CommandLine);
+
+    let rate = match matches.get_one::<String>("rate").unwrap().parse::<u32>() {
+        _ if stealth_default("rate") => STEALTH_RATE,
+        Ok(rate) if rate > 0 => rate,
+        _ => {
+            eprintln!(
+                "{}",
+                "the rate must be a number of requests a second above 0".red()
+            );
+            return Ok(None);
+        }
+    };
+
+    let mut body_regex = Vec::new();
+    for value in matches
+        .get_many::<String>("body-regex")
+        .into_iter()
+        .flatten()
+        .filter(|value| !value.is_empty())
+    {
+        match Pattern::parse(value) {
+            Ok(pattern) => body_regex.push(pattern),
+            Err(err) => {
+                eprintln!("{}", format!("invalid body regex: {}", err).red());
+                return Ok(None);
+            }
+        }
+    }
+
+    let mut hunt_patterns = match matches.get_one::<String>("regex-file") {
+        Some(path) => match patterns::read_patterns_file(path) {
+            Ok(file_patterns) => file_patterns,
+            Err(err) => {
+                eprintln!("{}", format!("could not read the regex file {}", err).red());
+                return Ok(None);
+            }
+        },
+        None => Vec::new(),
+    };
+    if let Some(packs) = matches.get_one::<String>("detect-secrets") {
+        match patterns::secret_patterns(packs) {
+            Ok(secret_patterns) => hunt_patterns.extend(secret_patterns),
+            Err(err) => {
+                eprintln!("{}", err.red());
+                return Ok(None);
+            }
+        }
+    }
+
+    let header_regex = match matches
+        .get_one::<String>("header-regex")
+        .unwrap()
+        .parse::<String>()
+    {
+        Ok(header_regex) => header_regex,
+        Err(_) => "".to_string(),
+    };
+
+    let ports = match matches
+        .get_one::<String>("ports")
+        .unwrap()
+        .parse::<String>()
+    {
+        Ok(ports) => ports,
+        Err(_) => "".to_string(),
+    };
+    if !input::valid_ports(&ports) {
+        eprintln!("{}", format!("invalid ports: {}", ports).red());
+        return Ok(None);
+    }
+
+    let path = match matches.get_one::<String>("path").unwrap().parse::<String>() {
+        Ok(path) => path,
+        Err(_) => "".to_string(),
+    };
+    if let Err(err) = input::expand_braces(&path) {
+        eprintln!("{}", err.red());
+        return Ok(None);
+    }
+
+    let display_title = matches.get_flag("title");
+    let display_tech = matches.get_flag("tech-detect");
+    let follow_redirects = matches.get_flag("follow-redirects");
+    let sni = matches.get_one::<String>("sni").cloned();
+    let source_ip = match matches.get_one::<String>("source-ip") {
+        Some(source_ip) => match source_ip.parse::<IpAddr>() {
+            Ok(source_ip) => Some(source_ip),
+            Err(_) => {
+                eprintln!("{}", format!("invalid source ip: {}", source_ip).red());
+                return Ok(None);
+            }
+        },
+        None => None,
+    };
+    let interface = matches.get_one::<String>("interface").cloned();
+    let port_schemes = match matches.get_one::<String>("port-scheme") {
+        Some(mappings) => match PortSchemes::parse(mappings) {
+            Ok(port_schemes) => port_schemes,
+            Err(err) => {
+                eprintln!("{}", format!("invalid port scheme: {}", err).red());
+                return Ok(None);
+            }
+        },
+        None => PortSchemes::default(),
+    };
+    let digest_auth = match matches.get_one::<String>("digest-auth") {
+        Some(credentials) => match DigestAuth::parse(credentials) {
+            Some(digest_auth) => Some(digest_auth),
+            None => {
+                eprintln!(
+                    "{}",
+                    "the digest credentials must be given as user:pass".red()
+                );
+                return Ok(None);
+            }
+        },
+        None => None,
+    };
+    let ntlm_auth = match matches.get_one::<String>("ntlm") {
+        Some(credentials) => match NtlmAuth::parse(credentials) {
+            Some(ntlm_auth) => Some(ntlm_auth),
+            None => {
+                eprintln!(
+                    "{}",
+                    "the ntlm credentials must be given as DOMAIN/user:pass".red()
+                );
+                return Ok(None);
+            }
+        },
+        None => None,
+    };
+    let tech_filter = match matches.get_one::<String>("tech-filter") {
+        Some(tech_filter) => tech_filter
+            .split(',')
+            .map(|category| category.trim().to_lowercase())
+            .filter(|category| !category.is_empty())
+            .collect(),
+        None => vec![],
+    };
+    let tech_min_confidence = match matches.get_one::<String>("tech-min-confidence") {
+        Some(confidence) => match confidence.parse::<u8>() {
+            Ok(confidence) if confidence <= 100 => confidence,
+            _ => {
+                eprintln!("{}", format!("invalid confidence: {}", confidence).red());
+                return Ok(None);
+            }
+        },
+        None => 0,
+    };
+    let tags = matches
+        .get_many::<String>("tag")
+        .map(|tags| tags.cloned().collect::<Vec<String>>())
+        .unwrap_or_default();
+    let delay = match Delay::parse(
+        matches.get_one::<String>("delay").map(|s| s.as_str()),
+        match matches.get_one::<String>("jitter") {
+            Some(jitter) => Some(jitter.as_str()),
+            None if stealth_default("jitter") && stealth_default("delay") => Some(STEALTH_JITTER),
+            None => None,
+        },
+    ) {
+        Ok(delay) => delay,
+        Err(err) => {
+            eprintln!("{}", err.red());
+            return Ok(None);
+        }
+    };
+    let encoding = if matches.get_flag("json") {
+        Encoding::Json
+    } else {
+        match matches.get_one::<String>("output-encoding") {
+            Some(encoding) => match Encoding::parse(encoding) {
+                Ok(encoding) => encoding,
+                Err(err) => {
+                    eprintln!("{}", err.red());
+                    return Ok(None);
+                }
+            },
+            None => Encoding::Text,
+        }
+    };
+    let output = matches.get_one::<String>("output").cloned();
+    let host_rate = match matches.get_one::<String>("host-rate") {
+        Some(host_rate) => match host_rate.parse::<u32>() {
+            Ok(host_rate) => host_rate,
+            Err(_) => {
+                eprintln!("{}", format!("invalid host rate: {}", host_rate).red());
+                return Ok(None);
+            }
+        },
+        None if stealth => STEALTH_HOST_RATE,
+        None => 0,
+    };
+    let output_compress = match matches.get_one::<String>("output-compress") {
+        Some(compression) => match Compression::parse(compression) {
+            Ok(compression) => Some(compression),
+            Err(err) => {
+                eprintln!("{}", err.red());
+                return Ok(None);
+            }
+        },
+        None => output.as_deref().and_then(Compression::from_path),
+    };
+    let sort = match matches.get_one::<String>("sort") {
+        Some(sort) => match Sort::parse(sort) {
+            Ok(sort) => Some(sort),
+            Err(err) => {
+                eprintln!("{}", err.red());
+                return Ok(None);
+            }
+        },
+        None => None,
+    };
+    let group_by = match matches.get_one::<String>("group-by") {
+        Some(group_by) => match GroupBy::parse(group_by) {
+            Ok(group_by) => Some(group_by),
+            Err(err) => {
+                eprintln!("{}", err.red());
+                return Ok(None);
+            }
+        },
+        None => None,
+    };
+    if group_by.is_some() && !matches!(encoding, Encoding::Text | Encoding::Json) {
+        eprintln!(
+            "{}",
+            "--group-by only works with the text and json output".red()
+        );
+        return Ok(None);
+    }
+    let ip_preference = if matches.get_flag("dual-stack") {
+        Some(IpPreference::Both)
+    } else if matches.get_flag("prefer-ipv6") {
+        Some(IpPreference::Ipv6)
+    } else if matches.get_flag("prefer-ipv4") {
+        Some(IpPreference::Ipv4)
+    } else {
+        None
+    };
+    if interface.is_some()
+        && !cfg!(any(
+            target_os = "android",
+            target_os = "fuchsia",
+            target_os = "linux"
+        ))
+    {
+        eprintln!("{}", "--interface is only supported on linux".red());
+        return Ok(None);
+    }
+    let output_xml = matches.get_one::<String>("output-xml").cloned();
+    let seen_db = matches.get_one::<String>("seen-db").cloned();
+    let new_only = matches.get_flag("new-only");
+    let plugins = matches
+        .get_many::<String>("plugin")
+        .into_iter()
+        .flatten()
+        .cloned()
+        .collect::<Vec<String>>();
+    let script = matches.get_one::<String>("script").cloned();
+    let live_stats = matches.get_flag("stats");
+    let ca_certs = match matches.get_one::<String>("ca-file") {
+        Some(ca_file) => match read_ca_file(ca_file) {
+            Ok(ca_certs) => ca_certs,
+            Err(err) => {
+                eprintln!("{}", format!("could not read the ca file: {}", err).red());
+                return Ok(None);
+            }
+        },
+        None => vec![],
+    };
+    let proxy_rotate = match matches
+        .get_one::<String>("proxy-rotate")
+        .unwrap()
+        .parse::<usize>()
+    {
+        Ok(proxy_rotate) if proxy_rotate > 0 => proxy_rotate,
+        _ => {
+            eprintln!("{}", "the proxy rotation must be a number above 0".red());
+            return Ok(None);
+        }
+    };
+    let tor = if matches.get_flag("tor") {
+        matches.get_one::<String>("tor-proxy").cloned()
+    } else {
+        None
+    };
+    let tor_newnym = match matches.get_one::<String>("tor-newnym") {
+        Some(tor_newnym) => match tor_newnym.parse::<usize>() {
+            Ok(tor_newnym) => tor_newnym,
+            Err(_) => {
+                eprintln!("{}", format!("invalid tor newnym: {}", tor_newnym).red());
+                return Ok(None);
+            }
+        },
+        None => 0,
+    };
+    let mut static_hosts = StaticHosts::default();
+    if let Some(hosts_file) = matches.get_one::<String>("hosts-file") {
+        if let Err(err) = static_hosts.load_file(hosts_file) {
+            eprintln!(
+                "{}",
+                format!("could not read the hosts file: {}", err).red()
+            );
+            return Ok(None);
+        }
+    }
+    for mapping in matches.get_many::<String>("resolve").into_iter().flatten() {
+        if let Err(err) = static_hosts.add_mapping(mapping) {
+            eprintln!("{}", err.red());
+            return Ok(None);
+        }
+    }
+    let chrome_path =
+        match chrome::chrome_path(matches.get_one::<String>("chrome-path").map(|s| s.as_str())) {
+            Ok(path) => path,
+            Err(err) => {
+                eprintln!("{}", err.red());
+                return Ok(None);
+            }
+        };
+    let chrome_args = match matches.get_one::<String>("chrome-args") {
+        Some(args) => args
+            .split(',')
+            .map(|arg| arg.trim().to_string())
+            .filter(|arg| !arg.is_empty())
+            .collect(),
+        None => vec![],
+    };
+    let browser_max_pages = match matches.get_one::<String>("browser-max-pages") {
+        Some(max_pages) => match max_pages.parse::<usize>() {
+            Ok(max_pages) if max_pages > 0 => Some(max_pages),
+            _ => {
+                eprintln!(
+                    "{}",
+                    format!("invalid browser max pages: {}", max_pages).red()
+                );
+                return Ok(None);
+            }
+        },
+        None => None,
+    };
+    let browser_max_mem = match matches.get_one::<String>("browser-max-mem") {
+        Some(size) => match memory::parse_size(size) {
+            Some(max_mem) => Some(max_mem),
+            None => {
+                eprintln!("{}", format!("invalid memory size: {}", size).red());
+                return Ok(None);
+            }
+        },
+        None => None,
+    };
+    let cert_expiry_warn = match matches.get_one::<String>("cert-expiry-warn") {
+        Some(days) => match cert::parse_days(days) {
+            Some(days) => Some(days),
+            None => {
+                eprintln!("{}", format!("invalid certificate expiry: {}", days).red());
+                return Ok(None);
+            }
+        },
+        None => None,
+    };
+    let san_feedback = matches.get_flag("san-feedback");
+    let san_scope = match matches.get_one::<String>("san-scope") {
+        Some(san_scope) => san_scope
+            .split(',')
+            .map(|suffix| suffix.trim().trim_start_matches('.').to_lowercase())
+            .filter(|suffix| !suffix.is_empty())
+            .collect(),
+        None => vec![],
+    };
+    // trusting a ca only makes sense when the certificates are validated
+    let verify_tls = matches.get_flag("verify-tls") || !ca_certs.is_empty();
+    let stats_interval = match matches.get_one::<String>("stats-interval") {
+        Some(interval) => match stats::parse_interval(interval) {
+            Some(interval) if !interval.is_zero() => Some(interval),
+            _ => {
+                eprintln!("{}", format!("invalid stats interval: {}", interval).red());
+                return Ok(None);
+            }
+        },
+        // the live line refreshes every second
+        None if live_stats => Some(Duration::from_secs(1)),
+        None => None,
+    };
+    let checkpoint_interval = match matches.get_one::<String>("checkpoint-interval") {
+        Some(interval) => match stats::parse_interval(interval) {
+            Some(interval) if !interval.is_zero() => interval,
+            _ => {
+                eprintln!(
+                    "{}",
+                    format!("invalid checkpoint interval: {}", interval).red()
+                );
+                return Ok(None);
+            }
+        },
+        None => Duration::from_secs(30),
+    };
+    let checkpoint_results = match matches.get_one::<String>("checkpoint-results") {
+        Some(results) => match results.parse::<u64>() {
+            Ok(results) if results > 0 => Some(results),
+            _ => {
+                eprintln!(
+                    "{}",
+                    format!("invalid checkpoint results: {}", results).red()
+                );
+                return Ok(None);
+            }
+        },
+        None => None,
+    };
+    let on_match = matches.get_one::<String>("on-match").cloned();
+    // cmd expands %variables% inside any quotes, the values can't be passed safely
+    if cfg!(windows) && on_match.as_deref().map_or(false, hook::has_placeholders) {
+        eprintln!(
+            "{}",
+            "the placeholders of --on-match can't be filled in safely on windows".red()
+        );
+        return Ok(None);
+    }
+    let on_match_codes = match matches.get_one::<String>("on-match-codes") {
+        Some(codes) => codes
+            .split(',')
+            .filter_map(|code| code.trim().parse::<u16>().ok())
+            .collect::<Vec<u16>>(),
+        None => vec![],
+    };
+    let dedupe = matches.get_one::<String>("dedupe").cloned();
+    let resolve_only = matches.get_flag("resolve-only");
+    let connect_only = matches.get_flag("connect-only");
+    let content_length = matches.get_flag("content-length");
+    let content_type = matches.get_flag("content-type");
+    let server = matches.get_flag("server");
+    let word_count = matches.get_flag("wc");
+    let display_hash = matches.get_flag("hash");
+    let unique = matches.get_flag("unique");
+    let hsts = matches.get_flag("hsts");
+    let methods_discovery = matches.get_flag("methods-discovery");
+    let etag = matches.get_flag("etag");
+    let last_modified = matches.get_flag("last-modified");
+    let cache_info = matches.get_flag("cache-info");
+    let extract_links = matches.get_flag("extract-links");
+    let extract_emails = matches.get_flag("extract-emails");
+    let extract_all_domains = matches.get_flag("extract-all-domains");
+    let js_scrape = matches.get_flag("js-scrape");
+    let filter_hashes: Vec<String> = match matches.get_one::<String>("filter-hash") {
+        Some(hashes) => hashes
+            .split(',')
+            .map(|hash| hash.trim().to_lowercase())
+            .filter(|hash| !hash.is_empty())
+            .collect(),
+        None => vec![],
+    };
+
+    let auto_rate = matches.get_flag("auto-rate");
+
+    let schedule = match matches.get_one::<String>("schedule") {
+        Some(expression) => match Schedule::parse(expression) {
+            Ok(schedule) => Some(schedule),
+            Err(err) => {
+                eprintln!("{}", format!("invalid schedule: {}", err).red());
+                return Ok(None);
+            }
+        },
+        None => None,
+    };
+
+    let schedule_jitter = match matches
+        .get_one::<String>("schedule-jitter")
+        .map(|s| s.to_string())
+    {
+        Some(n) => match n.parse::<u64>() {
+            Ok(n) => n,
+            Err(_) => 0,
+        },
+        None => 0,
+    };
+
+    let rate_min = match matches.get_one::<String>("rate-min").map(|s| s.to_string()) {
+        Some(n) => match n.parse::<u32>() {
+            Ok(n) => n,
+            Err(_) => 10,
+        },
+        None => 10,
+    };
+
+    // the rate given with -r is the ceiling unless --rate-max raises it
+    let rate_max = match matches.get_one::<String>("rate-max").map(|s| s.to_string()) {
+        Some(_)
+            if matches.value_source("rate-max") != Some(ValueSource::CommandLine)
+                && matches.value_source("rate") == Some(ValueSource::CommandLine) =>
+        {
+            rate
+        }
+        Some(n) => match n.parse::<u32>() {
+            Ok(n) => n,
+            Err(_) => 5000,
+        },
+        None => 5000,
+    };
+
+    let concurrency = match matches
+        .get_one::<String>("concurrency")
+        .map(|s| s.to_string())
+    {
+        Some(_) if stealth_default("concurrency") => STEALTH_CONCURRENCY,
+        Some(n) => match n.parse::<i32>() {
+            Ok(n) if n > 0 => n,
+            _ => {
+                eprintln!("{}", "the concurrency must be a number above 0".red());
+                return Ok(None);
+            }
+        },
+        None => {
+            println!("{}", "could not parse concurrency, using default of 100");
+            100
+        }
+    };
+
+    let queue_size = match matches
+        .get_one::<String>("queue-size")
+        .map(|s| s.to_string())
+    {
+        Some(n) => match n.parse::<usize>() {
+            Ok(n) if n > 0 => n,
+            _ => 1000,
+        },
+        None => 1000,
+    };
+
+    let timeout = match matches.get_one::<String>("timeout").map(|s| s.to_string()) {
+        Some(timeout) => match timeout.parse::<usize>() {
+            Ok(timeout) if timeout > 0 => timeout,
+            _ => {
+                eprintln!(
+                    "{}",
+                    "the timeout must be a number of seconds above 0".red()
+                );
+                return Ok(None);
+            }
+        },
+        None => 3,
+    };
+
+    // keep every response for analysing it again later
+    let store_dir = matches.get_one::<String>("store-response-dir").cloned();
+    if let Some(store_dir) = &store_dir {
+        std::fs::create_dir_all(store_dir)?;
+    }
+
+    // analyse the stored responses rather than probing the targets
+    let analyze_dir = matches
+        .subcommand_matches("analyze")
+        .and_then(|sub_matches| sub_matches.get_one::<String>("dir").cloned());
+
+    // measure the throughput against a local server instead of scanning
+    if matches.get_flag("bench") {
+        bench::run_bench(concurrency.max(1) as usize, timeout).await;
+        return Ok(None);
+    }
+
+    // collect the targets from the targets file or from stdin
+    let mut targets = vec![];
+    if let Some(given_targets) = given_targets {
+        targets = given_targets;
+    } else if let Some(targets_file) = matches.get_one::<String>("targets-file") {
+        targets = input::read_targets_file(targets_file)?;
+    } else if let Some(nmap_input) = matches.get_one::<String>("nmap-input") {
+        targets = match input::read_nmap_xml(nmap_input) {
+            Ok(targets) => targets,
+            Err(err) => {
+                eprintln!("{}", format!("could not read the nmap scan {}", err).red());
+                return Ok(None);
+            }
+        };
+    } else if let Some(masscan_input) = matches.get_one::<String>("masscan-input") {
+        targets = input::read_masscan(masscan_input)?;
+    } else if analyze_dir.is_none() {
+        // read bytes so a line that isn't utf-8 or fails to read doesn't end
+        // the input, only eof does
+        let mut stdin = BufReader::new(tokio::io::stdin());
+        let mut buf = vec![];
+        loop {
+            buf.clear();
+            match stdin.read_until(b'\n', &mut buf).await {
+                Ok(0) => break,
+                Ok(_) => {}
+                Err(_) => continue,
+            }
+            let line = String::from_utf8_lossy(&buf);
+            let line = line.trim_end_matches(&['\r', '\n'][..]);
+            match input::parse_input_line(line) {
+                Some(target) => targets.push(target),
+                None if !silent && !input::is_comment(line.trim()) => {
+                    eprintln!(
+                        "{}",
+                        format!("could not make out a host in {}, skipping it", line.trim())
+                            .yellow()
+                    );
+                }
+                None => {}
+            }
+        }
+        targets = input::merge_ports(targets);
+    }
+
+    let max_memory = match matches.get_one::<String>("max-memory") {
+        Some(size) => match memory::parse_size(size) {
+            Some(max_memory) => match memory::too_low(max_memory) {
+                Some(resident) => {
+                    eprintln!(
+                        "{}",
+                        format!(
+                            "--max-memory {} leaves no room above the {}MB already resident",
+                            size,
+                            resident >> 20
+                        )
+                        .red()
+                    );
+                    return Ok(None);
+                }
+                None => Some(max_memory),
+            },
+            None => {
+                eprintln!("{}", format!("invalid memory size: {}", size).red());
+                return Ok(None);
+            }
+        },
+        None => None,
+    };
+
+    let dedupe_bits = match matches
+        .get_one::<String>("dedupe-bits")
+        .map(|s| s.to_string())
+    {
+        Some(dedupe_bits) => match dedupe_bits.parse::<u64>() {
+            Ok(dedupe_bits) => dedupe_bits,
+            Err(_) => 134217728,
+        },
+        None => 134217728,
+    };
+
+    // drop the repeated input lines
+    if let Some(input_dedupe) = dedupe
+        .as_deref()
+        .and_then(|mode| Dedupe::new(mode, dedupe_bits))
+    {
+        targets.retain(|target| {
+            input_dedupe.first_seen(&format!(
+                "{},{},{}",
+                target.host,
+                target.ports.as_deref().unwrap_or_default(),
+                target.path.as_deref().unwrap_or_default()
+            ))
+        });
+    }
+
+    // spread the hosts of the same origin over the whole scan
+    if matches.get_flag("shuffle") || stealth {
+        targets.shuffle(&mut rand::thread_rng());
+    }
+
+    let dns_timeout = match matches
+        .get_one::<String>("dns-timeout")
+        .map(|s| s.to_string())
+    {
+        Some(dns_timeout) => match dns_timeout.parse::<u64>() {
+            Ok(dns_timeout) => dns_timeout,
+            Err(_) => 3,
+        },
+        None => 3,
+    };
+
+    let dns_retries = match matches
+        .get_one::<String>("dns-retries")
+        .map(|s| s.to_string())
+    {
+        Some(dns_retries) => match dns_retries.parse::<usize>() {
+            Ok(dns_retries) => dns_retries,
+            Err(_) => 2,
+        },
+        None => 2,
+    };
+
+    let crawl_depth = match matches
+        .get_one::<String>("crawl-depth")
+        .map(|s| s.to_string())
+    {
+        Some(crawl_depth) => match crawl_depth.parse::<usize>() {
+            Ok(crawl_depth) => crawl_depth,
+            Err(_) => 0,
+        },
+        None => 0,
+    };
+
+    let crawl_max_pages = match matches
+        .get_one::<String>("crawl-max-pages")
+        .map(|s| s.to_string())
+    {
+        Some(crawl_max_pages) => match crawl_max_pages.parse::<usize>() {
+            Ok(crawl_max_pages) => crawl_max_pages,
+            Err(_) => 50,
+        },
+        None => 50,
+    };
+
+    let discover_limit = match matches.get_one::<String>("discover-limit") {
+        Some(discover_limit) => match discover_limit.parse::<usize>() {
+            Ok(discover_limit) => discover_limit,
+            Err(_) => {
+                eprintln!(
+                    "{}",
+                    format!("invalid discover limit: {}", discover_limit).red()
+                );
+                return Ok(None);
+            }
+        },
+        None => 50,
+    };
+
+    let js_max_files = match matches
+        .get_one::<String>("js-max-files")
+        .map(|s| s.to_string())
+    {
+        Some(js_max_files) => match js_max_files.parse::<usize>() {
+            Ok(js_max_files) => js_max_files,
+            Err(_) => 10,
+        },
+        None => 10,
+    };
+
+    let js_max_size = match matches
+        .get_one::<String>("js-max-size")
+        .map(|s| s.to_string())
+    {
+        Some(js_max_size) => match js_max_size.parse::<usize>() {
+            Ok(js_max_size) => js_max_size,
+            Err(_) => 1024,
+        },
+        None => 1024,
+    };
+
+    // the settings shared by every job, targets may override some of them
+    let job = Job {
+        host: None,
+        body_regex,
+        header_regex,
+        ports,
+        display_title,
+        display_tech,
+        status_codes,
+        content_length,
+        content_type,
+        server,
+        path,
+        timeout: None,
+        word_count,
+        display_hash,
+        filter_hashes,
+        hsts,
+        methods_discovery,
+        etag,
+        last_modified,
+        cache_info,
+        hunt_patterns,
+        extract_links,
+        extract_emails,
+        extract_all_domains,
+        url: None,
+        depth: 0,
+        target_index: None,
+        js_scrape,
+        js_max_files,
+        js_max_size,
+        scheme: None,
+        store_dir,
+        cert_expiry_warn,
+        digest_auth,
+        ntlm_auth,
+        tags,
+        render: matches.get_flag("render"),
+        tech_filter,
+        tech_filter_drop: matches.get_flag("tech-filter-drop"),
+        tech_min_confidence,
+        timing: matches.get_flag("timing"),
+        bypass_403: matches.get_flag("bypass-403"),
+        method_diff: matches.get_flag("method-diff"),
+    };
+
+    let settings = Arc::new(Settings {
+        rate,
+        auto_rate,
+        rate_min,
+        rate_max,
+        concurrency,
+        queue_size,
+        timeout,
+        dns_timeout,
+        dns_retries,
+        follow_redirects,
+        crawl_depth,
+        crawl_max_pages,
+        sni,
+        output_xml,
+        seen_db,
+        unresolved: matches.get_one::<String>("unresolved").cloned(),
+        archive: matches.get_one::<String>("archive").cloned(),
+        discover: matches.get_one::<String>("discover").cloned(),
+        discover_limit,
+        new_only,
+        dedupe,
+        dedupe_bits,
+        max_memory,
+        plugins,
+        script,
+        on_match,
+        on_match_codes,
+        stats_interval,
+        live_stats,
+        stats_json: matches.get_one::<String>("stats-json").cloned(),
+        checkpoint: matches.get_one::<String>("checkpoint").cloned(),
+        checkpoint_interval,
+        checkpoint_results,
+        include_errors: matches.get_flag("include-errors"),
+        include_failed: matches.get_flag("include-failed"),
+        verify_tls,
+        ca_certs,
+        tls_info: cert_expiry_warn.is_some() || san_feedback,
+        san_feedback,
+        san_scope,
+        source_ip,
+        interface,
+        ip_preference,
+        port_schemes,
+        // detecting the scheme connects straight to the target, around the proxies
+        scheme_detect: !matches.get_flag("no-scheme-detect")
+            && !matches.contains_id("proxy-list")
+            && tor.is_none(),
+        one_per_ip: matches.get_flag("one-per-ip"),
+        ip_group: matches.get_flag("ip-group"),
+        delay,
+        encoding,
+        output,
+        output_compress,
+        sort,
+        group_by,
+        host_rate,
+        browser_headers: stealth || matches.get_flag("browser-headers"),
+        proxy_list: matches.get_one::<String>("proxy-list").cloned(),
+        proxy_rotate,
+        tor,
+        tor_newnym,
+        tor_control: matches
+            .get_one::<String>("tor-control")
+            .unwrap()
+            .to_string(),
+        tor_password: matches.get_one::<String>("tor-password").cloned(),
+        static_hosts,
+        same_host_only: matches.get_flag("same-host-only"),
+        chrome_path,
+        chrome_args,
+        browser_max_pages,
+        browser_max_mem,
+        fingerprints: matches.get_one::<String>("fingerprints").cloned(),
+        banner: matches.get_flag("banner"),
+        resolve_only,
+        connect_only,
+        unique,
+        silent,
+    });
+
+    Ok(Some(Scan {
+        targets,
+        job,
+        settings,
+        analyze_dir,
+        schedule,
+        schedule_jitter,
+        conditional: etag || last_modified,
+    }))
+}
+
+/**
+ * Run a single scan over all of the targets, returns whether it was stopped
+ * before its end
+ */
+async fn run_scan(
+    targets: Vec<Target>,
+    job: Job,
+    settings: Arc<Settings>,
+    validators: Option<Arc<Validators>>,
+    embedding: Embedding,
+) -> bool {
+    let silent = settings.silent;
+    let Embedding {
+        cli,
+        results,
+        callbacks,
+        http,
+        mut stop,
+    } = embedding;
+
+    // only resolve the hosts without probing them
+    if settings.resolve_only {
+        resolve::resolve_only(targets, &settings).await;
+        return false;
+    }
+
+    // only check which ports accept connections
+    if settings.connect_only {
+        connect::connect_only(targets, &job, &settings).await;
+        return false;
+    }
+
+    // the nmap style xml report
+    let xml = match &settings.output_xml {
+        Some(path) => match XmlReport::create(path) {
+            Ok(xml) => Some(xml),
+            Err(err) => {
+                eprintln!(
+                    "{}",
+                    format!("could not create the xml report {}: {}", path, err).red()
+                );
+                return false;
+            }
+        },
+        None => None,
+    };
+
+    // the proxies the probes are spread over
+    let proxies = match &settings.proxy_list {
+        Some(path) => match ProxyPool::load(path, settings.proxy_rotate, &settings) {
+            Ok(proxies) => Some(proxies),
+            Err(err) => {
+                eprintln!(
+                    "{}",
+                    format!("could not load the proxy list: {}", err).red()
+                );
+                return false;
+            }
+        },
+        None => None,
+    };
+
+    // the socks port of tor, resolving the hosts on the far side
+    let proxies = match &settings.tor {
+        Some(tor) => match ProxyPool::single(&format!("socks5h://{}", tor), &settings) {
+            Ok(tor_proxy) if tor_proxy.reachable(&settings).await => Some(tor_proxy),
+            Ok(_) => {
+                eprintln!("{}", format!("tor isn't listening on {}", tor).red());
+                return false;
+            }
+            Err(err) => {
+                eprintln!("{}", format!("could not use tor: {}", err).red());
+                return false;
+            }
+        },
+        None => proxies,
+    };
+
+    // the file or stdout the results are written to, nowhere when they're
+    // streamed without a file asked for
+    let output = if (results.is_some() || callbacks.is_some()) && settings.output.is_none() {
+        Ok(Output::discard(settings.encoding))
+    } else {
+        Output::create(
+            settings.encoding,
+            settings.output.as_deref(),
+            settings.output_compress,
+            settings.sort,
+            settings.group_by,
+        )
+    };
+    let output = match output {
+        Ok(output) => output,
+        Err(err) => {
+            eprintln!(
+                "{}",
+                format!(
+                    "could not create the output {}: {}",
+                    settings.output.as_deref().unwrap_or("stdout"),
+                    err
+                )
+                .red()
+            );
+            return false;
+        }
+    };
+
+    // the wasm plugins run over every response
+    let plugins = if settings.plugins.is_empty() {
+        None
+    } else {
+        match Plugins::load(&settings.plugins) {
+            Ok(plugins) => Some(plugins),
+            Err(err) => {
+                eprintln!("{}", format!("could not load the plugins: {}", err).red());
+                return false;
+            }
+        }
+    };
+
+    // the rhai script run over every result
+    let script = match &settings.script {
+        Some(path) => match Script::load(path) {
+            Ok(script) => Some(script),
+            Err(err) => {
+                eprintln!("{}", format!("could not load the script: {}", err).red());
+                return false;
+            }
+        },
+        None => None,
+    };
+
+    // the user's technologies detected next to the built-in ones
+    let fingerprints = match &settings.fingerprints {
+        Some(path) => match Fingerprints::load(path) {
+            Ok(fingerprints) => {
+                if fingerprints.skipped > 0 && !silent {
+                    eprintln!(
+                        "{}",
+                        format!(
+                            "[fingerprints] skipped {} patterns the regex engine can't run",
+                            fingerprints.skipped
+                        )
+                        .yellow()
+                    );
+                }
+                Some(fingerprints)
+            }
+            Err(err) => {
+                eprintln!(
+                    "{}",
+                    format!("could not load the fingerprints: {}", err).red()
+                );
+                return false;
+            }
+        },
+        None => None,
+    };
+
+    // the urls reported by the earlier runs
+    let seen_db = match &settings.seen_db {
+        Some(path) => match SeenDb::open(path) {
+            Ok(seen_db) => Some(seen_db),
+            Err(err) => {
+                eprintln!(
+                    "{}",
+                    format!("could not open the seen db {}: {}", path, err).red()
+                );
+                return false;
+            }
+        },
+        None => None,
+    };
+
+    // the hosts without an address, for the next run to leave out
+    let unresolved = match &settings.unresolved {
+        Some(path) => match Unresolved::create(path) {
+            Ok(unresolved) => Some(unresolved),
+            Err(err) => {
+                eprintln!("{}", format!("could not create {}: {}", path, err).red());
+                return false;
+            }
+        },
+        None => None,
+    };
+
+    // the raw exchanges, for replaying or converting them later
+    let archive = match &settings.archive {
+        Some(path) => {
+            match Archive::create(path, default_headers(&settings), decodes_bodies(&settings)) {
+                Ok(archive) => Some(archive),
+                Err(err) => {
+                    eprintln!("{}", format!("could not create {}: {}", path, err).red());
+                    return false;
+                }
+            }
+        }
+        None => None,
+    };
+
+    // the paths tried on every origin that answers
+    let discover = match &settings.discover {
+        Some(path) => match Discover::load(path, settings.discover_limit) {
+            Ok(discover) => Some(discover),
+            Err(err) => {
+                eprintln!(
+                    "{}",
+                    format!("could not read the wordlist {}: {}", path, err).red()
+                );
+                return false;
+            }
+        },
+        None => None,
+    };
+
+    // the background tasks only live as long as the scan
+    let mut background = vec![];
+
+    // pause and resume the job dispatching with SIGUSR1 and SIGUSR2
+    let paused = Arc::new(AtomicBool::new(false));
+    #[cfg(unix)]
+    if cli {
+        let paused = paused.clone();
+        background.push(task::spawn(async move {
+            watch_pause_signals(paused, silent).await
+        }));
+    }
+
+    // adjust the rate from the outcome of the requests
+    let stats = Arc::new(Stats::default());
+    let adaptive_rate = if settings.auto_rate {
+        let adaptive_rate = Arc::new(AdaptiveRate::new(
+            settings.rate,
+            settings.rate_min,
+            settings.rate_max,
+        ));
+        background.push(task::spawn(
+            adaptive_rate.clone().run(stats.clone(), silent),
+        ));
+        Some(adaptive_rate)
+    } else {
+        None
+    };
+
+    // report the progress on stderr
+    if let Some(interval) = settings.stats_interval {
+        background.push(task::spawn(stats.clone().report_progress(
+            targets.len(),
+            interval,
+            settings.live_stats,
+        )));
+    }
+
+    // hold back new work when the memory use gets close to the limit
+    let memory = match settings.max_memory {
+        Some(max_memory) => {
+            let memory = Arc::new(MemoryGuard::new(max_memory));
+            background.push(task::spawn(memory.clone().run(silent)));
+            Some(memory)
+        }
+        None => None,
+    };
+
+    // job channels
+    let (job_tx, job_rx) = async_channel::bounded::<Job>(settings.queue_size);
+
+    // feed the links found in the pages and the certificate names back into the queue
+    let crawl = if settings.crawl_depth > 0 || settings.san_feedback {
+        let crawl = Crawl::new(
+            job_tx.clone(),
+            settings.crawl_depth,
+            settings.crawl_max_pages,
+        );
+        Some(Arc::new(if settings.san_feedback {
+            crawl.with_san_feedback(settings.san_scope.clone(), &targets)
+        } else {
+            crawl
+        }))
+    } else {
+        None
+    };
+
+    let shared = Arc::new(Shared {
+        stats,
+        // collapse the duplicate bodies
+        unique: if settings.unique {
+            Some(UniqueFilter::default())
+        } else {
+            None
+        },
+        validators,
+        crawl,
+        xml,
+        output,
+        host_throttle: HostThrottle::new(settings.host_rate),
+        host_delay: match settings.host_rate {
+            0 => None,
+            _ => settings.delay.clone().map(HostDelay::new),
+        },
+        proxies,
+        tor_control: match settings.tor {
+            Some(_) => TorControl::new(
+                &settings.tor_control,
+                settings.tor_password.clone(),
+                settings.tor_newnym,
+                silent,
+            ),
+            None => None,
+        },
+        seen_db,
+        unresolved,
+        archive,
+        checkpoint: settings.checkpoint.as_deref().map(|path| {
+            let hosts = targets.iter().map(|target| target.host.clone()).collect();
+            Arc::new(Checkpoint::new(path, hosts, settings.checkpoint_results))
+        }),
+        results,
+        callbacks,
+        http,
+        total: targets.len(),
+        discover,
+        dedupe: settings
+            .dedupe
+            .as_deref()
+            .and_then(|mode| Dedupe::new(mode, settings.dedupe_bits)),
+        delay: settings.delay.clone().filter(|_| settings.host_rate == 0),
+        one_per_ip: if settings.one_per_ip {
+            Dedupe::new(
+                settings.dedupe.as_deref().unwrap_or("exact"),
+                settings.dedupe_bits,
+            )
+        } else {
+            None
+        },
+        memory,
+        hook: settings.on_match.as_deref().map(Hook::new),
+        plugins,
+        script,
+        fingerprints,
+        // read what the ports that don't speak http say
+        banners: if settings.banner {
+            Some(Banners::new(settings.clone()))
+        } else {
+            None
+        },
+        non_http: NonHttp::new(silent),
+        stopping: AtomicBool::new(false),
+    });
+
+    // write how far the scan got every interval
+    if let Some(checkpoint) = &shared.checkpoint {
+        background.push(task::spawn(
+            checkpoint
+                .clone()
+                .run(shared.clone(), settings.checkpoint_interval),
+        ));
+    }
+
+    // only the pages loaded in chrome need it kept alive
+    let uses_chrome = job.display_tech || job.render;
+
+    let rate = settings.rate;
+    let sender_shared = shared.clone();
+    let sender = task::spawn(async move {
+        send_url(
+            job_tx,
+            paused,
+            adaptive_rate,
+            sender_shared,
+            targets,
+            job,
+            rate,
+        )
+        .await
+    });
+
+    // process the jobs
+    let workers = FuturesUnordered::new();
+
+    // process the jobs for scanning.
+    for _ in 0..settings.concurrency {
+        let jrx = job_rx.clone();
+        // initialize the new chromium browser instance, only for the pages
+        // loaded in it
+        let chrome = if uses_chrome {
+            match Chrome::launch(settings.clone()) {
+                Ok(chrome) => {
+                    background.push(task::spawn(chrome.clone().watch()));
+                    Some(chrome)
+                }
+                Err(err) => {
+                    eprintln!("{}", format!("could not launch chrome: {}", err).red());
+                    break;
+                }
+            }
+        } else {
+            None
+        };
+        let settings = settings.clone();
+        let shared = shared.clone();
+        workers.push(task::spawn(async move {
+            //  run the detector
+            supervise_detector(jrx, chrome, settings, shared).await
+        }));
+    }
+    // nothing would take the jobs off the queue
+    if workers.is_empty() {
+        sender.abort();
+    }
+
+    // on ctrl-c or SIGTERM, or the stop of a scan run from code, stop
+    // dispatching, give the requests in flight a moment and fall through to
+    // writing out everything collected so far
+    let worker_aborts: Vec<_> = workers.iter().map(|worker| worker.abort_handle()).collect();
+    let mut workers_done = workers.collect::<Vec<_>>();
+    tokio::select! {
+        _ = &mut workers_done => {
+            // the workers also leave when nobody reads the results, the
+            // sender would wait on the full queue forever
+            sender.abort();
+            job_rx.close();
+        }
+        _ = stop_requested(cli, &mut stop) => {
+            shared.stopping.store(true, Ordering::SeqCst);
+            sender.abort();
+            job_rx.close();
+            if cli && !silent {
+                eprintln!(
+                    "{}",
+                    "[stopping] finishing the requests in flight, ctrl-c again to stop now".yellow()
+                );
+            }
+            tokio::select! {
+                _ = &mut workers_done => {}
+                _ = tokio::time::sleep(STOP_GRACE) => {}
+                _ = stop_signal(), if cli => {}
+            }
+            for worker in worker_aborts {
+                worker.abort();
+            }
+        }
+    }
+    let _ = sender.await;
+    for task in background {
+        task.abort();
+    }
+    // move past the live stats line
+    if settings.live_stats {
+        eprintln!();
+    }
+
+    // the first result of each body, now that its duplicates are counted
+    if let Some(unique) = &shared.unique {
+        for (result, status_codes) in unique.release() {
+            report(&settings, &shared, &result, status_codes).await;
+        }
+    }
+
+    // the last checkpoint, written while the output is still open to flush
+    if let Some(checkpoint) = &shared.checkpoint {
+        if let Err(err) = checkpoint.write(&shared) {
+            eprintln!(
+                "{}",
+                format!("could not write the checkpoint: {}", err).red()
+            );
+        }
+    }
+    if let Some(xml) = &shared.xml {
+        if let Err(err) = xml.finish() {
+            eprintln!(
+                "{}",
+                format!("could not write the xml report: {}", err).red()
+            );
+        }
+    }
+    if let Err(err) = shared.output.finish() {
+        eprintln!("{}", format!("could not write the results: {}", err).red());
+    }
+    if let Some(archive) = &shared.archive {
+        if let Err(err) = archive.finish() {
+            eprintln!("{}", format!("could not write the archive: {}", err).red());
+        }
+    }
+
+    // the tally of status codes everyone works out from the results anyway
+    if cli && !silent {
+        shared.stats.print_status_table();
+        if shared.stopping.load(Ordering::SeqCst) {
+            eprintln!(
+                "{}",
+                "[stopping] interrupted, the targets that weren't probed are left out".yellow()
+            );
+        }
+    }
+    if let Some(stats_json) = &settings.stats_json {
+        if let Err(err) = shared.stats.write_json(stats_json) {
+            eprintln!(
+                "{}",
+                format!("could not write the stats to {}: {}", stats_json, err).red()
+            );
+        }
+    }
+    shared.stopping.load(Ordering::SeqCst)
+}
+
+/**
+ * Wait for the scan to be stopped, by its stop handle when it was run from
+ * code, by a signal when it was run from the command line, and never
+ * otherwise
+ */
+async fn stop_requested(cli: bool, stop: &mut Option<watch::Receiver<bool>>) {
+    match stop {
+        Some(stop) => {
+            while !*stop.borrow_and_update() {
+                // every handle is gone, nothing can stop the scan anymore
+                if stop.changed().await.is_err() {
+                    std::future::pending::<()>().await;
+                }
+            }
+        }
+        None if cli => stop_signal().await,
+        None => std::future::pending::<()>().await,
+    }
+}
+
+/**
+ * Wait for ctrl-c, or SIGTERM where there is one
+ */
+async fn stop_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        if let Ok(mut terminate) = signal(SignalKind::terminate()) {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {}
+                _ = terminate.recv() => {}
+            }
+            return;
+        }
+    }
+    // never stop the scan when ctrl-c can't be listened for
+    if tokio::signal::ctrl_c().await.is_err() {
+        std::future::pending::<()>().await;
+    }
+}
+
+/**
+ * Pause the job dispatching on SIGUSR1 and resume it on SIGUSR2
+ */
+#[cfg(unix)]
+async fn watch_pause_signals(paused: Arc<AtomicBool>, silent: bool) {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut pause = match signal(SignalKind::user_defined1()) {
+        Ok(pause) => pause,
+        Err(_) => return,
+    };
+    let mut resume = match signal(SignalKind::user_defined2()) {
+        Ok(resume) => resume,
+        Err(_) => return,
+    };
+    loop {
+        tokio::select! {
+            _ = pause.recv() => {
+                paused.store(true, Ordering::SeqCst);
+                if !silent {
+                    eprintln!("{}", "[paused] send SIGUSR2 to resume".yellow());
+                }
+            }
+            _ = resume.recv() => {
+                paused.store(false, Ordering::SeqCst);
+                if !silent {
+                    eprintln!("{}", "[resumed]".green());
+                }
+            }
+        }
+    }
+}
+
+/**
+ * Send the urls to be processed by the workers
+ */
+async fn send_url(
+    tx: async_channel::Sender<Job>,
+    paused: Arc<AtomicBool>,
+    adaptive_rate: Option<Arc<AdaptiveRate>>,
+    shared: Arc<Shared>,
+    targets: Vec<Target>,
+    job: Job,
+    rate: u32,
+) -> Result<(), Box<dyn Error + Send + Sync + 'static>> {
+    //set rate limit
+    let mut current_rate = rate;
+    let mut lim = RateLimiter::direct(rate::per_second(rate));
+
+    for (index, target) in targets.iter().enumerate() {
+        // hold off while the scan is paused
+        while paused.load(Ordering::SeqCst) {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        if let Some(memory) = &shared.memory {
+            memory.wait_for_room().await;
+        }
+
+        // follow the adaptive rate when it has changed
+        if let Some(adaptive_rate) = &adaptive_rate {
+            let rate = adaptive_rate.current();
+            if rate != current_rate {
+                current_rate = rate;
+                lim = RateLimiter::direct(rate::per_second(rate));
+            }
+        }
+
+        // send the jobs
+        lim.until_ready().await;
+        let mut msg = job.clone();
+        msg.host = Some(target.host.clone());
+        msg.target_index = Some(index);
+        if let Some(ports) = &target.ports {
+            msg.ports = ports.clone();
+        }
+        if let Some(path) = &target.path {
+            msg.path = path.clone();
+        }
+        if let Some(timeout) = target.timeout {
+            msg.timeout = Some(timeout);
+        }
+        if let Some(scheme) = &target.scheme {
+            msg.scheme = Some(scheme.clone());
+        }
+        if let Some(crawl) = &shared.crawl {
+            crawl.add_pending();
+        }
+        if let Err(err) = tx.send(msg).await {
+            eprintln!("{}", err.to_string());
+        }
+    }
+
+    // the crawl closes the queue once the crawled pages are probed too
+    if let Some(crawl) = &shared.crawl {
+        crawl.seeding_done();
+    }
+    Ok(())
+}
+
+/**
+ * Run the detector and restart it whenever it panics so one bad
+ * response can't take the whole scan down with it
+ */
+async fn supervise_detector(
+    rx: async_channel::Receiver<Job>,
+    chrome: Option<Arc<Chrome>>,
+    settings: Arc<Settings>,
+    shared: Arc<Shared>,
+) {
+    for restarts in 0.. {
+        let detector = task::spawn(run_detector(
+            rx.clone(),
+            chrome.clone(),
+            settings.clone(),
+            shared.clone(),
+        ));
+        let err = match detector.await {
+            Ok(_) => return,
+            Err(err) => err,
+        };
+        if !err.is_panic() {
+            return;
+        }
+        if restarts >= MAX_WORKER_RESTARTS {
+            eprintln!("{}", "a worker kept panicking and has been stopped".red());
+            return;
+        }
+        if !settings.silent {
+            eprintln!(
+                "{}",
+                format!("[worker] panicked: {}, restarting", panic_message(err)).yellow()
+            );
+        }
+    }
+}
+
+/**
+ * Get the message a task panicked with
+ */
+fn panic_message(err: task::JoinError) -> String {
+    let panic = err.into_panic();
+    if let Some(message) = panic.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = panic.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+/**
+ * Perform the HTTP probing operation.
+ */
+async fn run_detector(
+    rx: async_channel::Receiver<Job>,
+    chrome: Option<Arc<Chrome>>,
+    settings: Arc<Settings>,
+    shared: Arc<Shared>,
+) {
+    let client = build_client(&settings, None);
+    let insecure_client = build_insecure_client(&settings, None);
+
+    // a client kept to each address family when there's a preference
+    let family_clients: Vec<(IpFamily, reqwest::Client, Option<reqwest::Client>)> =
+        match settings.ip_preference {
+            Some(_) => [IpFamily::V4, IpFamily::V6]
+                .into_iter()
+                .map(|family| {
+                    (
+                        family,
+                        build_client(&settings, Some(family)),
+                        build_insecure_client(&settings, Some(family)),
+                    )
+                })
+                .collect(),
+            None => vec![],
+        };
+
+    while let Ok(job) = rx.recv().await {
+        // leave the queued jobs once the scan is stopping
+        if shared.stopping.load(Ordering::SeqCst) {
+            break;
+        }
+        let _pending = shared.crawl.as_ref().map(|crawl| crawl.pending_guard());
+        let job_status_codes = job.status_codes;
+
+        // crawled pages are probed as they are
+        if let Some(url) = &job.url {
+            let clients = (&client, insecure_client.as_ref());
+            let result = match probe_proxied(
+                clients,
+                chrome.as_deref(),
+                &job,
+                url.clone(),
+                &settings,
+                &shared,
+            )
+            .await
+            {
+                Ok(result) => result,
+                Err(error) => failed(&job, &settings, url, error),
+            };
+            if let Some(result) = result {
+                report(&settings, &shared, &result, job_status_codes).await;
+            }
+            continue;
+        }
+        let _completed = CompletedGuard(&shared, job.target_index);
+
+        let job_host: String = job.host.clone().unwrap();
+        let job_path = job.path.clone();
+        let job_paths = input::expand_braces(&job_path).unwrap_or_else(|_| vec![job_path]);
+        let job_ports = job.ports.clone();
+        let mut resolved_domains: Vec<Resolved> = vec![];
+        let mut dns_error = None;
+
+        // probe for open ports and perform dns resolution
+        for port in job_ports.split(",") {
+            // the target's own scheme wins over the port table
+            let schemes = match job.scheme.as_deref() {
+                Some(scheme) => vec![scheme],
+                None => match settings.port_schemes.schemes(port) {
+                    Some(schemes) => schemes,
+                    // say hello in tls to find out what the unknown ports speak
+                    None if settings.scheme_detect => {
+                        match schemes::detect_scheme(&job_host, port, &settings).await {
+                            Some(scheme) => vec![scheme],
+                            None => continue,
+                        }
+                    }
+                    None => schemes::BOTH.to_vec(),
+                },
+            };
+            for scheme in schemes {
+                let resolved = http_resolver(
+                    job_host.clone(),
+                    format!("{}://", scheme),
+                    port.to_string(),
+                    &settings,
+                )
+                .await;
+                match resolved {
+                    Ok(resolved) => resolved_domains.push(resolved),
+                    Err(err) => dns_error = Some(err),
+                }
+            }
+        }
+
+        // a host that didn't resolve is reported once, not for every port
+        if let (true, Some(err)) = (resolved_domains.is_empty(), dns_error) {
+            // only the names that don't exist, a timeout may resolve next time
+            if let (ErrorKind::DnsNxdomain, Some(unresolved)) = (err.kind, &shared.unresolved) {
+                if let Err(err) = unresolved.record(&job_host) {
+                    eprintln!(
+                        "{}",
+                        format!("could not write the unresolved hosts: {}", err).red()
+                    );
+                }
+            }
+            if let Some(result) = failed(&job, &settings, &job_host, err) {
+                report(&settings, &shared, &result, job_status_codes).await;
+            }
+        }
+
+        // whether anything of the host answered, for --include-failed
+        let mut probed = false;
+        let mut answered = false;
+        let mut last_error = None;
+
+        // Iterate over the resolved IP addresses and send HTTP requests
+        for Resolved {
+            domain,
+            families,
+            ip,
+        } in &resolved_domains
+        {
+            // probe each address once, however many hosts point at it
+            if let (Some(one_per_ip), Some(ip)) = (&shared.one_per_ip, ip) {
+                let scheme = domain.split("://").next().unwrap_or_default();
+                let port = domain.rsplit(':').next().unwrap_or_default();
+                if !one_per_ip.first_seen(&format!("{}://{}:{}", scheme, ip, port)) {
+                    continue;
+                }
+            }
+
+            for job_path in &job_paths {
+                let url = format!("{}{}", domain, job_path);
+                if let Some(crawl) = &shared.crawl {
+                    crawl.mark_seen(&url);
+                }
+
+                for family in families {
+                    let (mut request_client, mut request_insecure_client) = match family {
+                        Some(family) => match family_clients.iter().find(|(f, _, _)| f == family) {
+                            Some((_, client, insecure_client)) => {
+                                (client.clone(), insecure_client.clone())
+                            }
+                            None => continue,
+                        },
+                        None => (client.clone(), insecure_client.clone()),
+                    };
+
+                    // connect to the target but present the sni name in the handshake
+                    let mut request_url = url.clone();
+                    if let Some(sni) = &settings.sni {
+                        match sni_request(&settings, &url, sni, *family).await {
+                            Ok((sni_client, sni_insecure_client, sni_url)) => {
+                                request_client = sni_client;
+                                request_insecure_client = sni_insecure_client;
+                                request_url = sni_url;
+                            }
+                            // the target couldn't be reached, like a failed probe
+                            Err(error) => {
+                                probed = true;
+                                last_error = Some(error.clone());
+                                if let Some(result) = failed(&job, &settings, &url, error) {
+                                    report(&settings, &shared, &result, job_status_codes).await;
+                                }
+                                continue;
+                            }
+                        }
+                    }
+
+                    probed = true;
+                    let mut result = match probe_proxied(
+                        (&request_client, request_insecure_client.as_ref()),
+                        chrome.as_deref(),
+                        &job,
+                        request_url.clone(),
+                        &settings,
+                        &shared,
+                    )
+                    .await
+                    {
+                        Ok(Some(result)) => result,
+                        // filtered out, but it answered
+                        Ok(None) => {
+                            answered = true;
+                            continue;
+                        }
+                        // never sent, so it's no more answered than a failure
+                        Err(error) => {
+                            last_error = Some(error.clone());
+                            match failed(&job, &settings, &url, error) {
+                                Some(result) => result,
+                                None => continue,
+                            }
+                        }
+                    };
+                    match &result.error {
+                        Some(error) => last_error = Some(error.clone()),
+                        None => answered = true,
+                    }
+                    result.url = url.clone();
+                    result.family = *family;
+                    if settings.ip_group {
+                        result.ip_group = ip.map(|ip| ip.to_string());
+                    }
+
+                    // print the final results
+                    report(&settings, &shared, &result, job_status_codes).await;
+
+                    // brute-force a few paths on the origins that answered, through
+                    // the clients that reached it, so the sni name goes to the same
+                    // address the result came from
+                    if result.error.is_none() && result.banner.is_none() {
+                        discover_origin(
+                            (&request_client, request_insecure_client.as_ref()),
+                            chrome.as_deref(),
+                            &job,
+                            &result,
+                            &request_url,
+                            &settings,
+                            &shared,
+                        )
+                        .await;
+                    }
+                }
+            }
+        }
+
+        // the host resolved but nothing of it answered
+        if settings.include_failed && probed && !answered {
+            if let Some(error) = last_error {
+                let result = ProbeResult {
+                    url: job_host.clone(),
+                    tags: job.tags.clone(),
+                    error: Some(error),
+                    failed: true,
+                    ..Default::default()
+                };
+                report(&settings, &shared, &result, job_status_codes).await;
+            }
+        }
+    }
+}
+
+/**
+ * Probe the url through the next proxy of the list when there is one, seeing
+ * whether the proxy is still up when the probe fails. The error is for a url
+ * that couldn't be sent at all, every proxy being down
+ */
+async fn probe_proxied(
+    clients: (&reqwest::Client, Option<&reqwest::Client>),
+    chrome: Option<&Chrome>,
+    job: &Job,
+    url: String,
+    settings: &Settings,
+    shared: &Shared,
+) -> Result<Option<ProbeResult>, ProbeError> {
+    let proxies = match &shared.proxies {
+        Some(proxies) => proxies,
+        None => return Ok(probe_url(clients, chrome, job, url, settings, shared).await),
+    };
+    let (index, client, insecure_client) = proxies
+        .next()
+        .ok_or_else(|| ProbeError::new(ErrorKind::Other, "no live proxy left"))?;
+    let result = probe_url(
+        (client, insecure_client),
+        chrome,
+        job,
+        url,
+        settings,
+        shared,
+    )
+    .await;
+    if result
+        .as_ref()
+        .map_or(true, |result| result.error.is_some())
+    {
+        proxies.check(index, settings).await;
+    }
+    Ok(result)
+}
+
+/**
+ * The record of a probe that failed, None unless --include-errors asks for
+ * them or --include-failed needs the reason
+ */
+fn failed(job: &Job, settings: &Settings, url: &str, error: ProbeError) -> Option<ProbeResult> {
+    if !settings.include_errors && !settings.include_failed {
+        return None;
+    }
+    Some(ProbeResult {
+        url: url.to_string(),
+        tags: job.tags.clone(),
+        error: Some(error),
+        ..Default::default()
+    })
+}
+
+/**
+ * Try the wordlist of --discover on the origin of a reported result, once,
+ * and report the paths it has
+ */
+async fn discover_origin(
+    (client, insecure_client): (&reqwest::Client, Option<&reqwest::Client>),
+    chrome: Option<&Chrome>,
+    job: &Job,
+    root: &ProbeResult,
+    request_url: &str,
+    settings: &Settings,
+    shared: &Shared,
+) {
+    let discover = match &shared.discover {
+        Some(discover) => discover,
+        None => return,
+    };
+    let (origin, request_origin) = match (url_origin(&root.url), url_origin(request_url)) {
+        (Some(origin), Some(request_origin)) => (origin, request_origin),
+        _ => return,
+    };
+    if !discover.first_visit(&origin) {
+        return;
+    }
+
+    // the certificate has no say in which paths are there
+    let fetch_client = insecure_client.unwrap_or(client);
+    let found = discover
+        .found_paths(fetch_client, &request_origin, job.timeout, shared)
+        .await;
+    for path in found {
+        let url = format!("{}{}", request_origin, path);
+        let mut result = match probe_proxied(
+            (client, insecure_client),
+            chrome,
+            job,
+            url,
+            settings,
+            shared,
+        )
+        .await
+        {
+            Ok(Some(result)) if result.error.is_none() => result,
+            _ => continue,
+        };
+        result.url = format!("{}{}", origin, path);
+        result.family = root.family;
+        result.ip_group = root.ip_group.clone();
+        report(settings, shared, &result, job.status_codes).await;
+    }
+}
+
+/**
+ * The scheme, host and port of a url, laid out like the urls probed
+ */
+fn url_origin(url: &str) -> Option<String> {
+    let url = reqwest::Url::parse(url).ok()?;
+    Some(format!(
+        "{}://{}:{}",
+        url.scheme(),
+        url.host_str()?,
+        url.port_or_known_default()?
+    ))
+}
+
+/**
+ * Counts a target as completed once the worker is done with it, the
+ * checkpoint leaves out the ones cut off by a stop
+ */
+struct CompletedGuard<'a>(&'a Shared, Option<usize>);
+
+impl Drop for CompletedGuard<'_> {
+    fn drop(&mut self) {
+        self.0.stats.record_completed();
+        if let Some(callbacks) = &self.0.callbacks {
+            callbacks.on_progress(&Progress {
+                completed: self.0.stats.completed(),
+                total: self.0.total,
+                requests: self.0.stats.requests(),
+                errors: self.0.stats.errors(),
+            });
+        }
+        if let (Some(checkpoint), Some(index)) = (&self.0.checkpoint, self.1) {
+            if !self.0.stopping.load(Ordering::SeqCst) {
+                checkpoint.complete(index);
+            }
+        }
+    }
+}
+
+/**
+ * Print a result and write it to the reports
+ */
+async fn report(settings: &Settings, shared: &Shared, result: &ProbeResult, status_codes: bool) {
+    // the callbacks hear of every failed probe
+    if let (Some(callbacks), Some(_)) = (&shared.callbacks, &result.error) {
+        callbacks.on_error(result);
+    }
+
+    // the failed probes kept only for --include-failed to tell why
+    if result.error.is_some() && !result.failed && !settings.include_errors {
+        return;
+    }
+
+    // the first result of each body waits for its duplicates to be counted
+    if let Some(unique) = &shared.unique {
+        if unique.hold(result, status_codes) {
+            return;
+        }
+    }
+
+    // drop the urls already reported in this scan
+    if let Some(dedupe) = &shared.dedupe {
+        if !dedupe.first_seen(&result.url) {
+            return;
+        }
+    }
+
+    // remember the url for the next runs, skipping it if an earlier one reported it
+    if let Some(seen_db) = &shared.seen_db {
+        match seen_db.record(&result.url) {
+            Ok(new) => {
+                if settings.new_only && !new {
+                    return;
+                }
+            }
+            Err(err) => eprintln!("{}", format!("could not write the seen db: {}", err).red()),
+        }
+    }
+
+    shared.stats.record_status(result.status_code);
+    if let Err(err) = shared.output.write_result(result, status_codes) {
+        eprintln!("{}", format!("could not write the results: {}", err).red());
+    }
+    if let Some(checkpoint) = &shared.checkpoint {
+        if let Err(err) = checkpoint.record_result(shared) {
+            eprintln!(
+                "{}",
+                format!("could not write the checkpoint: {}", err).red()
+            );
+        }
+    }
+
+    // hand the result to the stream, stopping the scan once nobody reads it
+    if let Some(results) = &shared.results {
+        if results.send(result.clone()).await.is_err() {
+            shared.stopping.store(true, Ordering::SeqCst);
+        }
+    }
+    if let (Some(callbacks), None) = (&shared.callbacks, &result.error) {
+        callbacks.on_result(result);
+    }
+
+    // the failed probes only go to the results
+    if result.error.is_some() {
+        return;
+    }
+
+    // chain the result into another tool
+    if let Some(hook) = &shared.hook {
+        if settings.on_match_codes.is_empty()
+            || settings.on_match_codes.contains(&result.status_code)
+        {
+            hook.run(result);
+        }
+    }
+    if let Some(xml) = &shared.xml {
+        if let Err(err) = xml.record(&Record::from_result(result)) {
+            eprintln!(
+                "{}",
+                format!("could not write the xml report: {}", err).red()
+            );
+        }
+    }
+}
+
+/**
+ * Build the http client used by a worker, kept to the address family when
+ * one is given
+ */
+fn build_client(settings: &Settings, family: Option<IpFamily>) -> reqwest::Client {
+    family_builder(settings, family).build().unwrap()
+}
+
+/**
+ * Build the client that retries the requests failing certificate validation,
+ * only needed with --verify-tls
+ */
+fn build_insecure_client(settings: &Settings, family: Option<IpFamily>) -> Option<reqwest::Client> {
+    if !settings.verify_tls {
+        return None;
+    }
+    accept_invalid_certs(family_builder(settings, family))
+        .build()
+        .ok()
+}
+
+/**
+ * The client settings, bound to the unspecified address of the family so
+ * only the addresses of that family are connected to
+ */
+fn family_builder(settings: &Settings, family: Option<IpFamily>) -> reqwest::ClientBuilder {
+    match family {
+        Some(family) => client_builder(settings).local_address(family.local_address()),
+        None => client_builder(settings),
+    }
+}
+
+/**
+ * Read every certificate in a pem bundle
+ */
+fn read_ca_file(path: &str) -> Result<Vec<reqwest::Certificate>, String> {
+    let contents = std::fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err))?;
+    const END: &str = "-----END CERTIFICATE-----";
+    let mut certs = vec![];
+    for block in contents
+        .split_inclusive(END)
+        .filter(|block| block.contains(END))
+    {
+        let cert = reqwest::Certificate::from_pem(block.trim().as_bytes())
+            .map_err(|err| format!("{}: {}", path, err))?;
+        certs.push(cert);
+    }
+    if certs.is_empty() {
+        return Err(format!("{}: no certificates found", path));
+    }
+    Ok(certs)
+}
+
+/**
+ * Skip the certificate validation
+ */
+fn accept_invalid_certs(builder: reqwest::ClientBuilder) -> reqwest::ClientBuilder {
+    builder
+        .danger_accept_invalid_hostnames(true)
+        .danger_accept_invalid_certs(true)
+}
+
+/**
+ * Build a client that sends the sni name in the handshake while connecting
+ * to the address of the original target, along with the url to request, the
+ * error when the target doesn't resolve
+ */
+async fn sni_request(
+    settings: &Settings,
+    url: &str,
+    sni: &str,
+    family: Option<IpFamily>,
+) -> Result<(reqwest::Client, Option<reqwest::Client>, String), ProbeError> {
+    let invalid = |message: String| ProbeError::new(ErrorKind::Other, message);
+    let mut request_url =
+        reqwest::Url::parse(url).map_err(|err| invalid(format!("{}: {}", url, err)))?;
+    if request_url.scheme() != "https" {
+        return Ok((
+            build_client(settings, family),
+            build_insecure_client(settings, family),
+            url.to_string(),
+        ));
+    }
+    let host = request_url
+        .host_str()
+        .ok_or_else(|| invalid(format!("{} has no host", url)))?
+        .to_string();
+    let port = request_url.port_or_known_default().unwrap_or(443);
+    let addrs = resolve_host(&format!("{}:{}", host, port), settings).await?;
+    let addr = *addrs
+        .iter()
+        .find(|addr| match family {
+            Some(IpFamily::V4) => addr.is_ipv4(),
+            Some(IpFamily::V6) => addr.is_ipv6(),
+            None => true,
+        })
+        .ok_or_else(|| {
+            ProbeError::new(
+                ErrorKind::DnsNoAddress,
+                format!("{} has no address of the family probed", host),
+            )
+        })?;
+
+    request_url
+        .set_host(Some(sni))
+        .map_err(|err| invalid(format!("{}: {}", sni, err)))?;
+    let client = family_builder(settings, family)
+        .resolve(sni, addr)
+        .build()
+        .map_err(|err| ProbeError::from_reqwest(&err))?;
+    let insecure_client = if settings.verify_tls {
+        accept_invalid_certs(family_builder(settings, family).resolve(sni, addr))
+            .build()
+            .ok()
+    } else {
+        None
+    };
+    Ok((client, insecure_client, request_url.to_string()))
+}
+
+/**
+ * The headers every client sends, unless a request overrides them
+ */
+fn default_headers(settings: &Settings) -> HeaderMap {
+    let mut headers = reqwest::header::HeaderMap::new();
+    headers.insert(
+        reqwest::header::USER_AGENT,
+        reqwest::header::HeaderValue::from_static(
+            "Mozilla/5.0 (Macintosh; Intel Mac OS X 10.15; rv:95.0) Gecko/20100101 Firefox/95.0",
+        ),
+    );
+
+    if settings.browser_headers {
+        headers.extend(headers::browser_headers());
+    }
+    headers
+}
+
+/**
+ * Whether the client asks for compressed bodies and decodes them, only when
+ * the browser headers ask for them compressed
+ */
+fn decodes_bodies(settings: &Settings) -> bool {
+    settings.browser_headers
+}
+
+/**
+ * The client settings shared by every request
+ */
+fn client_builder(settings: &Settings) -> reqwest::ClientBuilder {
+    let headers = default_headers(settings);
+    let decode = decodes_bodies(settings);
+
+    let redirect_policy = if settings.follow_redirects && settings.same_host_only {
+        // fail the request rather than send it to a host out of scope
+        redirect::Policy::custom(|attempt| {
+            if attempt.previous().len() >= 10 {
+                attempt.error("too many redirects")
+            } else if attempt.url().host_str() != attempt.previous()[0].host_str() {
+                attempt.error("the redirect left the host")
+            } else {
+                attempt.follow()
+            }
+        })
+    } else if settings.follow_redirects {
+        redirect::Policy::limited(10)
+    } else {
+        redirect::Policy::none()
+    };
+
+    let mut builder = reqwest::Client::builder()
+        .default_headers(headers)
+        .redirect(redirect_policy)
+        .timeout(Duration::from_secs(settings.timeout as u64))
+        .tls_info(settings.tls_info)
+        .gzip(decode)
+        .brotli(decode)
+        .deflate(decode)
+        .local_address(settings.source_ip);
+
+    // connect the pinned hosts to their addresses, on the port of the url
+    for (host, ips) in settings.static_hosts.iter() {
+        let addrs: Vec<SocketAddr> = ips.iter().map(|ip| SocketAddr::new(*ip, 0)).collect();
+        builder = builder.resolve_to_addrs(host, &addrs);
+    }
+
+    // leave through the given interface
+    #[cfg(any(target_os = "android", target_os = "fuchsia", target_os = "linux"))]
+    if let Some(interface) = &settings.interface {
+        builder = builder.interface(interface);
+    }
+
+    //no certs, unless they are being verified
+    if settings.verify_tls {
+        settings.ca_certs.iter().fold(builder, |builder, cert| {
+            builder.add_root_certificate(cert.clone())
+        })
+    } else {
+        accept_invalid_certs(builder)
+    }
+}
+
+/**
+ * Probe a single url and collect the details requested by the job, the
+ * clients are the one used first and the one to retry with when the
+ * certificate fails validation
+ */
+async fn probe_url(
+    (client, insecure_client): (&reqwest::Client, Option<&reqwest::Client>),
+    chrome: Option<&Chrome>,
+    job: &Job,
+    url: String,
+    settings: &Settings,
+    shared: &Shared,
+) -> Option<ProbeResult> {
+    if shared.non_http.is_known(&url) {
+        return None;
+    }
+
+    // ask whether the page changed since the previous scheduled scan
+    let conditional_headers = match &shared.validators {
+        Some(validators) => validators.conditional_headers(&url),
+        None => HeaderMap::new(),
+    };
+    let (client, (resp, sent), tls_error) = match try_fetch_timed(
+        client,
+        Method::GET,
+        &url,
+        conditional_headers.clone(),
+        job.timeout,
+        shared,
+    )
+    .await
+    {
+        Ok(resp) => (client, resp, None),
+        Err(err) => {
+            // an invalid certificate is a finding, fetch the page anyway to report it
+            let tls_error = match certificate_error(&err) {
+                Some(tls_error) => tls_error,
+                // whatever is on the port didn't answer in http, see what it says
+                None => {
+                    let banner = match &shared.banners {
+                        Some(banners) => banners.grab(&url).await,
+                        None => None,
+                    };
+                    return match banner {
+                        Some(banner) => Some(ProbeResult {
+                            url,
+                            banner: Some(banner),
+                            tags: job.tags.clone(),
+                            ..Default::default()
+                        }),
+                        None => failed(job, settings, &url, ProbeError::from_reqwest(&err)),
+                    };
+                }
+            };
+            let insecure_client = match insecure_client {
+                Some(insecure_client) => insecure_client,
+                None => {
+                    let error = ProbeError::new(ErrorKind::TlsError, tls_error);
+                    return failed(job, settings, &url, error);
+                }
+            };
+            let resp = match try_fetch_timed(
+                insecure_client,
+                Method::GET,
+                &url,
+                conditional_headers.clone(),
+                job.timeout,
+                shared,
+            )
+            .await
+            {
+                Ok(resp) => resp,
+                Err(err) => return failed(job, settings, &url, ProbeError::from_reqwest(&err)),
+            };
+            (insecure_client, resp, Some(tls_error))
+        }
+    };
+
+    let ttfb = sent.elapsed();
+
+    // answer the digest challenge and ask again
+    let resp = match &job.digest_auth {
+        Some(digest_auth) if resp.status().as_u16() == 401 => {
+            match digest_auth.authorization(resp.headers(), "GET", &url) {
+                Some(authorization) => {
+                    archive_exchange(shared, Exchange::of(&resp), None);
+                    let mut headers = conditional_headers.clone();
+                    headers.insert(reqwest::header::AUTHORIZATION, authorization);
+                    match try_fetch_method(client, Method::GET, &url, headers, job.timeout, shared)
+                        .await
+                    {
+                        Ok(resp) => resp,
+                        Err(err) => {
+                            return failed(job, settings, &url, ProbeError::from_reqwest(&err))
+                        }
+                    }
+                }
+                None => resp,
+            }
+        }
+        _ => resp,
+    };
+
+    // log in with windows integrated auth
+    let resp = match &job.ntlm_auth {
+        Some(ntlm_auth) if resp.status().as_u16() == 401 => {
+            let challenged = resp.headers().clone();
+            let authenticated = ntlm_auth
+                .handshake(
+                    client,
+                    &url,
+                    &conditional_headers,
+                    &challenged,
+                    job.timeout,
+                    shared,
+                )
+                .await;
+            match authenticated {
+                Some(authenticated) => {
+                    archive_exchange(shared, Exchange::of(&resp), None);
+                    authenticated
+                }
+                None => resp,
+            }
+        }
+        _ => resp,
+    };
+    let status_code = resp.status().as_u16();
+    let exchange = Exchange::of(&resp);
+    if status_code == 304 && shared.validators.is_some() {
+        archive_exchange(shared, exchange, None);
+        return None;
+    }
+
+    if let Some(validators) = &shared.validators {
+        validators.remember(
+            &url,
+            header_value(resp.headers(), "ETag"),
+            header_value(resp.headers(), "Last-Modified"),
+        );
+    }
+
+    let ip = resp.remote_addr().map(|addr| addr.ip().to_string());
+    let peer_cert = PeerCertificate::from_response(&resp);
+    let cert_expiry = job
+        .cert_expiry_warn
+        .zip(peer_cert.as_ref())
+        .and_then(|(warn_days, peer_cert)| peer_cert.expiry(warn_days));
+
+    // the certificate names are new targets, whatever becomes of this page
+    if let (Some(crawl), Some(peer_cert)) = (&shared.crawl, &peer_cert) {
+        crawl.enqueue_hosts(job, peer_cert.names());
+    }
+    let final_url = resp.url().clone();
+    let headers = resp.headers().clone();
+
+    // skip the download when the status or headers already rule the page out
+    if !path_found(job, status_code) || !headers_match(job, &headers) {
+        archive_exchange(shared, exchange, None);
+        return None;
+    }
+
+    // over the memory limit the body is cut short instead of held whole
+    let body = match shared.memory.as_ref().filter(|memory| memory.is_over()) {
+        Some(_) => read_bounded(resp, memory::HELD_BODY).await,
+        None => resp.text().await,
+    };
+    let body = match body {
+        Ok(body) => body,
+        Err(err) => {
+            archive_exchange(shared, exchange, None);
+            return failed(job, settings, &url, ProbeError::from_reqwest(&err));
+        }
+    };
+    let total = sent.elapsed();
+
+    // keep the response for analysing it again offline
+    if let Some(store_dir) = &job.store_dir {
+        if let Err(err) = store::store_response(store_dir, &url, status_code, &headers, &body) {
+            eprintln!("{}", format!("could not store the response: {}", err).red());
+        }
+    }
+    archive_exchange(shared, exchange, Some(&body));
+
+    // let the scripts of single page apps fill in the page
+    let body = if let (true, Some(chrome)) = (job.render, chrome) {
+        let rendered =
+            render::render_page(&chrome.browser(), final_url.as_str(), job.timeout).await;
+        chrome.page_done().await;
+        rendered.unwrap_or(body)
+    } else {
+        body
+    };
+
+    // queue the same-host links for the crawl
+    if let Some(crawl) = &shared.crawl {
+        crawl.enqueue_links(
+            job,
+            &final_url,
+            extract::extract_links(&body, &final_url, true),
+        );
+    }
+
+    let mut result =
+        analyze_response(job, shared, &url, &final_url, status_code, &headers, &body).await?;
+    result.ip = ip;
+    if tls_error.is_some() || cert_expiry.is_some() {
+        result.tls = Some(TlsInfo {
+            error: tls_error,
+            cert_expiry,
+        });
+    }
+    result.latency = Some(ttfb);
+
+    // the client keeps the phases of the connection to itself, time them apart
+    if job.timing {
+        let handshake = match reqwest::Url::parse(&url) {
+            Ok(url) => timing::handshake(&url, settings).await,
+            Err(_) => None,
+        };
+        result.timing = Some(Timing {
+            dns: handshake.as_ref().and_then(|handshake| handshake.dns),
+            connect: handshake.as_ref().map(|handshake| handshake.connect),
+            tls: handshake.as_ref().and_then(|handshake| handshake.tls),
+            ttfb,
+            total,
+        });
+    }
+
+    // search the scripts the page loads for endpoints and secrets
+    if job.js_scrape {
+        let secret_patterns = if job.hunt_patterns.is_empty() {
+            patterns::secret_patterns("all").unwrap_or_default()
+        } else {
+            job.hunt_patterns.clone()
+        };
+        let findings =
+            scripts::scrape_scripts(client, job, &body, &final_url, &secret_patterns, shared).await;
+        result.js_endpoints = findings.endpoints;
+        result.js_secrets = findings.secrets;
+    }
+
+    // find out which methods are accepted
+    if job.methods_discovery {
+        result.methods = Some(methods::discover_methods(client, &url, job.timeout, shared).await);
+    }
+
+    // see whether the other methods are let in where GET isn't, or the other way around
+    if job.method_diff {
+        result.method_diff =
+            methods::method_diff(client, &url, status_code, job.timeout, shared).await;
+    }
+
+    // look for a way around the forbidden page
+    if job.bypass_403 && status_code == 403 {
+        result.bypasses = bypass::try_bypasses(client, &url, job.timeout, shared).await;
+    }
+
+    // extract the technologies
+    if let (true, Some(chrome)) = (job.display_tech, chrome) {
+        let url = reqwest::Url::parse(&url).ok()?;
+        let tech_result = match wappalyzer::scan(url.clone(), &chrome.browser())
+            .await
+            .result
+        {
+            Ok(tech_result) => tech_result,
+            // a hung tab or a dead websocket, try once more in a new chrome
+            Err(_) => {
+                if !chrome.relaunch().await {
+                    return None;
+                }
+                wappalyzer::scan(url, &chrome.browser()).await.result.ok()?
+            }
+        };
+        chrome.page_done().await;
+        // the built-in set only reports what it is sure of
+        let mut found = tech_result
+            .iter()
+            .map(|tech| Detection {
+                name: tech.name.clone(),
+                categories: vec![tech.category.clone()],
+                confidence: 100,
+            })
+            .collect::<Vec<Detection>>();
+        if let Some(fingerprints) = &shared.fingerprints {
+            found.extend(fingerprints.detect(&final_url, &headers, &body));
+        }
+        let min_confidence = job.tech_min_confidence;
+        for detection in found {
+            if detection.confidence < min_confidence
+                || !tech_wanted(job, &detection.name, &detection.categories)
+            {
+                continue;
+            }
+            match result
+                .tech
+                .iter_mut()
+                .find(|tech| tech.name == detection.name)
+            {
+                Some(tech) => tech.confidence = tech.confidence.max(detection.confidence),
+                None => result.tech.push(TechMatch {
+                    name: detection.name,
+                    confidence: detection.confidence,
+                }),
+            }
+        }
+        if result.tech.is_empty() && job.tech_filter_drop {
+            return None;
+        }
+    }
+
+    Some(result)
+}
+
+/**
+ * Check if a valid path has been found, when the job probes one
+ */
+fn path_found(job: &Job, status_code: u16) -> bool {
+    job.path.is_empty() || (status_code != 404 && status_code != 400)
+}
+
+/**
+ * Whether a technology is in one of the categories of --tech-filter or
+ * named by it, when there is one
+ */
+fn tech_wanted(job: &Job, name: &str, categories: &[String]) -> bool {
+    let tech_filter = &job.tech_filter;
+    if tech_filter.is_empty() {
+        return true;
+    }
+    let name = name.to_lowercase();
+    tech_filter.iter().any(|filter| {
+        *filter == name
+            || categories
+                .iter()
+                .any(|category| category.to_lowercase().contains(filter.as_str()))
+    })
+}
+
+/**
+ * Whether the headers match the header regex, when there is one
+ */
+fn headers_match(job: &Job, headers: &HeaderMap) -> bool {
+    if job.header_regex.is_empty() {
+        return true;
+    }
+    let re = match Regex::new(&job.header_regex) {
+        Ok(re) => re,
+        Err(_) => return false,
+    };
+    headers.iter().any(|(k, v)| {
+        let header_str = format!("{}:{}", k.as_str(), v.to_str().unwrap_or(""));
+        re.is_match(&header_str)
+    })
+}
+
+/**
+ * Run the filters and collect the details that only need the response
+ * itself, shared by the live probes and the analysis of stored responses
+ */
+async fn analyze_response(
+    job: &Job,
+    shared: &Shared,
+    url: &str,
+    final_url: &reqwest::Url,
+    status_code: u16,
+    headers: &HeaderMap,
+    body: &str,
+) -> Option<ProbeResult> {
+    if !path_found(job, status_code) || !headers_match(job, headers) {
+        return None;
+    }
+
+    let mut result = ProbeResult {
+        url: url.to_string(),
+        status_code,
+        tags: job.tags.clone(),
+        length: Some(body.len() as u64),
+        ..Default::default()
+    };
+    if job.content_type {
+        result.content_type = header_value(headers, "Content-Type");
+    }
+    if job.server {
+        result.server = header_value(headers, "Server");
+    }
+    if job.etag {
+        result.etag = header_value(headers, "ETag");
+    }
+    if job.last_modified {
+        result.last_modified = header_value(headers, "Last-Modified");
+    }
+    if job.cache_info {
+        result.cache_info = Some(CacheInfo::from_headers(headers));
+    }
+    // flag the redirects that ended up on another host
+    if let Ok(url) = reqwest::Url::parse(url) {
+        if final_url.host_str() != url.host_str() {
+            result.redirect_host = final_url.host_str().map(|host| host.to_string());
+        }
+    }
+
+    // browsers ignore the policy over plain http
+    if job.hsts && final_url.scheme() == "https" {
+        result.hsts = Some(Hsts::from_headers(headers));
+    }
+
+    // perform the regexes on the response body
+    if !job.body_regex.is_empty() {
+        let (matched, labels) = patterns::match_patterns(&job.body_regex, body);
+        if !matched {
+            return None;
+        }
+        result.matches = labels;
+    }
+
+    // hunt for the patterns from the regex file and the secret packs, in the headers too
+    let hunt_patterns = &job.hunt_patterns;
+    if !hunt_patterns.is_empty() {
+        let header_text = headers
+            .iter()
+            .map(|(k, v)| format!("{}: {}\n", k.as_str(), v.to_str().unwrap_or("")))
+            .collect::<String>();
+        for pattern in hunt_patterns {
+            let name = pattern.name();
+            if (pattern.regex.is_match(&header_text) || pattern.regex.is_match(body))
+                && !result.matches.contains(&name)
+            {
+                result.matches.push(name);
+            }
+        }
+    }
+
+    // only hash the body for the options that look at the hash
+    let hash = (job.display_hash || !job.filter_hashes.is_empty() || shared.unique.is_some())
+        .then(|| body_hash(body));
+
+    // drop the known boring pages
+    if let Some(hash) = &hash {
+        if job.filter_hashes.contains(hash) {
+            return None;
+        }
+        if job.display_hash {
+            result.body_hash = Some(hash.clone());
+        }
+    }
+
+    if job.content_length {
+        let content_length = header_value(headers, "Content-Length")
+            .and_then(|content_length| content_length.parse::<u64>().ok());
+        result.content_length = Some(content_length.unwrap_or(body.len() as u64));
+    }
+    if job.word_count {
+        result.words = Some(body.split_whitespace().count());
+        result.lines = Some(body.lines().count());
+    }
+
+    // only keep the first result for each body
+    if let (Some(unique), Some(hash)) = (&shared.unique, hash) {
+        if !unique.first_seen(&hash) {
+            return None;
+        }
+        result.unique_hash = Some(hash);
+    }
+
+    // extract the page title
+    if job.display_title {
+        result.title = extract_title(body);
+    }
+
+    // extract the links and emails, only the target's own by default
+    let scoped = !job.extract_all_domains;
+    if job.extract_links {
+        result.links = extract::extract_links(body, final_url, scoped);
+    }
+    if job.extract_emails {
+        let host = final_url.host_str().unwrap_or_default();
+        result.emails = extract::extract_emails(body, host, scoped);
+    }
+
+    // let the plugins add their own fields or drop the result
+    if let Some(plugins) = &shared.plugins {
+        match plugins.run(url, status_code, headers, body).await {
+            Verdict::Keep(fields) => result.plugin_fields = fields,
+            Verdict::Drop => return None,
+        }
+    }
+
+    // let the script filter, tag or rewrite the result
+    if let Some(script) = &shared.script {
+        if !script.run(&mut result, headers, body) {
+            return None;
+        }
+    }
+
+    Some(result)
+}
+
+/**
+ * Send a request with any method, after the delay, and record its outcome
+ * in the stats. Its body isn't archived, the callers only look at the status
+ * and headers
+ */
+async fn fetch_method(
+    client: &reqwest::Client,
+    method: Method,
+    url: &str,
+    headers: HeaderMap,
+    timeout: Option<usize>,
+    shared: &Shared,
+) -> Option<reqwest::Response> {
+    let resp = try_fetch_method(client, method, url, headers, timeout, shared)
+        .await
+        .ok()?;
+    archive_exchange(shared, Exchange::of(&resp), None);
+    Some(resp)
+}
+
+/**
+ * Read the body up to the limit, leaving the rest of it unread
+ */
+async fn read_bounded(mut resp: reqwest::Response, limit: usize) -> reqwest::Result<String> {
+    let mut body = vec![];
+    while let Some(chunk) = resp.chunk().await? {
+        let room = limit - body.len();
+        body.extend_from_slice(&chunk[..chunk.len().min(room)]);
+        if body.len() >= limit {
+            break;
+        }
+    }
+    Ok(String::from_utf8_lossy(&body).into_owned())
+}
+
+/**
+ * Record an exchange in the archive, with the body when it was read
+ */
+fn archive_exchange(shared: &Shared, exchange: Option<Exchange>, body: Option<&str>) {
+    if let (Some(archive), Some(exchange)) = (&shared.archive, exchange) {
+        if let Err(err) = archive.record(&exchange, body) {
+            eprintln!(
+                "{}",
+                format!("could not archive the response: {}", err).red()
+            );
+        }
+    }
+}
+
+/**
+ * Send a request, keeping the error for the callers that look into it
+ */
+async fn try_fetch_method(
+    client: &reqwest::Client,
+    method: Method,
+    url: &str,
+    headers: HeaderMap,
+    timeout: Option<usize>,
+    shared: &Shared,
+) -> reqwest::Result<reqwest::Response> {
+    try_fetch_timed(client, method, url, headers, timeout, shared)
+        .await
+        .map(|(resp, _)| resp)
+}
+
+/**
+ * Send a request, along with the moment it went out once the delays were
+ * waited for
+ */
+async fn try_fetch_timed(
+    client: &reqwest::Client,
+    method: Method,
+    url: &str,
+    headers: HeaderMap,
+    timeout: Option<usize>,
+    shared: &Shared,
+) -> reqwest::Result<(reqwest::Response, Instant)> {
+    let mut request = client.request(method, url).headers(headers);
+    if let Some(timeout) = timeout {
+        request = request.timeout(Duration::from_secs(timeout as u64));
+    }
+    let req = request.build()?;
+    if let Some(delay) = &shared.delay {
+        delay.wait().await;
+    }
+    if let (Some(host_delay), Some(host)) = (&shared.host_delay, req.url().host_str()) {
+        host_delay.wait(host).await;
+    }
+    if let (Some(host_throttle), Some(host)) = (&shared.host_throttle, req.url().host_str()) {
+        host_throttle.wait(host).await;
+    }
+    let stats = &shared.stats;
+    stats.record_request();
+    if let Some(tor_control) = &shared.tor_control {
+        tor_control.record_request();
+    }
+    let http: &dyn HttpClient = match &shared.http {
+        Some(http) => http.as_ref(),
+        None => client,
+    };
+    // the request as it goes out, kept on the response for the archive
+    let archived = shared.archive.as_ref().map(|archive| archive.sent(&req));
+    let sent = Instant::now();
+    match http.execute(req).await {
+        Ok(mut resp) => {
+            let status_code = resp.status().as_u16();
+            if status_code == 429 || status_code == 503 {
+                stats.record_throttled();
+            }
+            if let Some(archived) = archived {
+                resp.extensions_mut().insert(archived);
+            }
+            Ok((resp, sent))
+        }
+        Err(err) => {
+            stats.record_error(err.is_timeout());
+            // the rest of the origin's requests would only fail the same way
+            if schemes::is_non_http(&err) {
+                stats.record_non_http();
+                shared.non_http.record(url);
+            }
+            Err(err)
+        }
+    }
+}
+
+/**
+ * The reason a request failed certificate validation, None when it failed
+ * for anything else
+ */
+fn certificate_error(err: &reqwest::Error) -> Option<String> {
+    let mut source = err.source();
+    while let Some(inner) = source {
+        let message = inner.to_string();
+        if message.to_lowercase().contains("certificate") {
+            // openssl puts the reason at the end, e.g. (self-signed certificate)
+            let reason = message
+                .rsplit_once(" (")
+                .and_then(|(_, reason)| reason.strip_suffix(')'))
+                .unwrap_or(&message);
+            return Some(reason.to_string());
+        }
+        source = inner.source();
+    }
+    None
+}
+
+/**
+ * Get a non-empty header value as a string
+ */
+fn header_value(headers: &reqwest::header::HeaderMap, name: &str) -> Option<String> {
+    let value = headers.get(name)?.to_str().ok()?;
+    if value.is_empty() {
+        return None;
+    }
+    Some(value.to_string())
+}
+
+/**
+ * Hash the body so identical pages can be recognised
+ */
+fn body_hash(body: &str) -> String {
+    format!("{:x}", Sha256::digest(body.as_bytes()))
+}
+
+/**
+ * Extract the page title from the body, the first that isn't empty, in tags
+ * of any case and with its whitespace collapsed
+ */
+fn extract_title(body: &str) -> Option<String> {
+    let re = Regex::new(r"(?is)<title(?:\s[^>]*)?>(.*?)</title\s*>").ok()?;
+    re.captures_iter(body)
+        .map(|cap| cap[1].split_whitespace().collect::<Vec<&str>>().join(" "))
+        .find(|title| !title.is_empty())
+}
+
+/**
+ * Format the result as a line of colored output
+ */
+fn format_result(result: &ProbeResult, status_codes: bool) -> String {
+    let mut line = result.url.clone();
+    if let Some(title) = &result.title {
+        line.push_str(&format!(" {}", format!("[{}]", title).cyan()));
+    }
+    // there's no status code without an http response
+    if let Some(error) = &result.error {
+        let field = if result.failed { "failed" } else { "error" };
+        line.push_str(&format!(
+            " {}",
+            format!("[{}:{}]", field, error.kind.as_str()).red()
+        ));
+    } else if let Some(banner) = &result.banner {
+        line.push_str(&format!(" {}", format!("[banner:{}]", banner).yellow()));
+    } else if status_codes {
+        let sc = format!("[{}]", result.status_code);
+        let sc = match result.status_code {
+            100..=199 => sc.white(),
+            200..=299 => sc.green(),
+            300..=399 => sc.blue(),
+            400..=499 => sc.magenta(),
+            _ => sc.red(),
+        };
+        line.push_str(&format!(" {}", sc));
+    }
+    if let Some(redirect_host) = &result.redirect_host {
+        line.push_str(&format!(
+            " {}",
+            format!("[redirect-host:{}]", redirect_host).yellow()
+        ));
+    }
+    if !result.tech.is_empty() {
+        line.push_str(&format!(
+            " {}",
+            format!(
+                "[{}]",
+                result
+                    .tech
+                    .iter()
+                    .map(|tech| tech.name.as_str())
+                    .collect::<Vec<&str>>()
+                    .join(",")
+            )
+            .white()
+            .bold()
+        ));
+    }
+    if let Some(content_type) = &result.content_type {
+        line.push_str(&format!(" [{}]", content_type));
+    }
+    if let Some(content_length) = result.content_length {
+        line.push_str(&format!(" [{}]", content_length));
+    }
+    if let (Some(words), Some(lines)) = (result.words, result.lines) {
+        line.push_str(&format!(" [words:{},lines:{}]", words, lines));
+    }
+    if let Some(body_hash) = &result.body_hash {
+        line.push_str(&format!(" [{}]", body_hash));
+    }
+    if let Some(duplicates) = result.duplicates {
+        line.push_str(&format!(" [duplicates:{}]", duplicates));
+    }
+    if let Some(server) = &result.server {
+        line.push_str(&format!(" [{}]", server));
+    }
+    if let Some(hsts) = &result.hsts {
+        line.push_str(&format!(" [{}]", hsts.to_field()));
+    }
+    if let Some(ip_group) = &result.ip_group {
+        line.push_str(&format!(" [ip-group:{}]", ip_group));
+    }
+    if let Some(family) = &result.family {
+        line.push_str(&format!(
+            " [{}:{}]",
+            family,
+            result.ip.as_deref().unwrap_or_default()
+        ));
+    }
+    let tls = result.tls.clone().unwrap_or_default();
+    if let Some(tls_error) = &tls.error {
+        line.push_str(&format!(" {}", format!("[tls-error:{}]", tls_error).red()));
+    }
+    if let Some(cert_expiry) = &tls.cert_expiry {
+        let field = format!("[{}]", cert_expiry.to_field());
+        let field = match cert_expiry {
+            CertExpiry::Expired => field.red(),
+            CertExpiry::Expiring(_) => field.yellow(),
+        };
+        line.push_str(&format!(" {}", field));
+    }
+    if let Some(etag) = &result.etag {
+        line.push_str(&format!(" [etag:{}]", etag));
+    }
+    if let Some(last_modified) = &result.last_modified {
+        line.push_str(&format!(" [last-modified:{}]", last_modified));
+    }
+    if let Some(cache_info) = &result.cache_info {
+        for field in cache_info.to_fields() {
+            line.push_str(&format!(" [{}]", field));
+        }
+        if cache_info.cacheable_authenticated() {
+            line.push_str(&format!(" {}", "[cacheable-with-cookie]".red()));
+        }
+    }
+    if !result.matches.is_empty() {
+        line.push_str(&format!(
+            " {}",
+            format!("[matches:{}]", result.matches.join(",")).yellow()
+        ));
+    }
+    if !result.links.is_empty() {
+        line.push_str(&format!(" [links:{}]", result.links.join(",")));
+    }
+    if !result.emails.is_empty() {
+        line.push_str(&format!(" [emails:{}]", result.emails.join(",")));
+    }
+    if !result.js_endpoints.is_empty() {
+        line.push_str(&format!(
+            " [js-endpoints:{}]",
+            result.js_endpoints.join(",")
+        ));
+    }
+    if !result.js_secrets.is_empty() {
+        line.push_str(&format!(
+            " {}",
+            format!("[js-secrets:{}]", result.js_secrets.join(",")).red()
+        ));
+    }
+    for field in result.plugin_fields.iter().chain(&result.script_tags) {
+        line.push_str(&format!(" [{}]", field));
+    }
+    if let Some(methods) = &result.methods {
+        if !methods.is_empty() {
+            let field = format!("[methods:{}]", methods.join(","));
+            if methods::has_risky_method(methods) {
+                line.push_str(&format!(" {}", field.red()));
+            } else {
+                line.push_str(&format!(" {}", field));
+            }
+        }
+    }
+    if !result.method_diff.is_empty() {
+        let statuses = result
+            .method_diff
+            .iter()
+            .map(|(method, status)| format!("{}={}", method, status))
+            .collect::<Vec<String>>();
+        line.push_str(&format!(
+            " {}",
+            format!("[method-diff:{}]", statuses.join(",")).yellow()
+        ));
+    }
+    if !result.bypasses.is_empty() {
+        let field = format!("[bypass:{}]", result.bypasses.join(","));
+        if bypass::has_success(&result.bypasses) {
+            line.push_str(&format!(" {}", field.red()));
+        } else {
+            line.push_str(&format!(" {}", field));
+        }
+    }
+    if let Some(timing) = &result.timing {
+        line.push_str(&format!(" [{}]", timing.to_field()));
+    }
+    for tag in &result.tags {
+        line.push_str(&format!(" [tag:{}]", tag));
+    }
+    line
+}
+
+/**
+ * A host resolved for probing
+ */
+struct Resolved {
+    domain: String,
+    families: Vec<Option<IpFamily>>,
+    ip: Option<IpAddr>,
+}
+
+/**
+ * Resolve the subdomains and return the host, along with the address
+ * families to probe it over and the address it will be reached at
+ */
+async fn http_resolver(
+    host: String,
+    schema: String,
+    port: String,
+    settings: &Settings,
+) -> Result<Resolved, ProbeError> {
+    let domain = format!("{}{}:{}", schema, host, port);
+
+    // tor resolves the host itself, looking it up here would leak it
+    if settings.tor.is_some() {
+        return Ok(Resolved {
+            domain,
+            families: vec![None],
+            ip: None,
+        });
+    }
+
+    let lookup = resolve_host(&format!("{}:{}", host, port), settings).await?;
+    let no_address = || {
+        let message = "no address of the family probed";
+        ProbeError::new(ErrorKind::DnsNoAddress, message)
+    };
+
+    // pick the families the host resolved to by the preference
+    if let Some(ip_preference) = settings.ip_preference {
+        let families = ip_preference.families(&lookup);
+        let first = *families.first().ok_or_else(no_address)?;
+        let ip = lookup
+            .iter()
+            .find(|addr| addr.is_ipv4() == (first == IpFamily::V4))
+            .map(|addr| addr.ip());
+        return Ok(Resolved {
+            domain,
+            families: families.into_iter().map(Some).collect(),
+            ip,
+        });
+    }
+
+    // only the hosts with an ipv4 address are probed
+    let ip = lookup
+        .iter()
+        .find(|addr| addr.is_ipv4())
+        .ok_or_else(no_address)?
+        .ip();
+    Ok(Resolved {
+        domain,
+        families: vec![None],
+        ip: Some(ip),
+    })
+}
+
+/**
+ * Look up the address, pinned ones first, retrying when the resolver times
+ * out or fails temporarily
+ */
+async fn lookup_host(domain: &str, settings: &Settings) -> Option<Vec<SocketAddr>> {
+    resolve_host(domain, settings).await.ok()
+}
+
+/**
+ * Look up the address like lookup_host, telling a name that doesn't exist
+ * from a resolver that never answered
+ */
+async fn resolve_host(domain: &str, settings: &Settings) -> Result<Vec<SocketAddr>, ProbeError> {
+    if let Some(addrs) = settings.static_hosts.lookup(domain) {
+        return Ok(addrs);
+    }
+    let mut last_error = ProbeError::new(ErrorKind::DnsTimeout, "the lookup timed out");
+    for _ in 0..=settings.dns_retries {
+        let lookup = tokio::time::timeout(
+            Duration::from_secs(settings.dns_timeout),
+            net::lookup_host(domain),
+        )
+        .await;
+        match lookup {
+            Ok(Ok(addrs)) => return Ok(addrs.collect()),
+            Ok(Err(err)) => {
+                // the name doesn't exist, asking again won't change that
+                let message = err.to_string().to_lowercase();
+                if !message.contains("temporary") && !message.contains("again") {
+                    return Err(ProbeError::new(ErrorKind::DnsNxdomain, err.to_string()));
+                }
+                last_error = ProbeError::new(ErrorKind::DnsTimeout, err.to_string());
+            }
+            Err(_) => continue,
+        }
+    }
+    Err(last_error)
+}
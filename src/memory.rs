@@ -0,0 +1,246 @@
+use colored::Colorize;
+use std::{
+    fs,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+/**
+ * Parse a size such as 512M or 2G into bytes, a bare number is taken as bytes
+ */
+pub fn parse_size(size: &str) -> Option<u64> {
+    let size = size.trim().to_uppercase();
+    let size = size.trim_end_matches('B');
+    let (number, unit) = match size.char_indices().find(|(_, c)| c.is_ascii_alphabetic()) {
+        Some((i, _)) => size.split_at(i),
+        None => (size, ""),
+    };
+    let multiplier: u64 = match unit.trim() {
+        "" => 1,
+        "K" => 1 << 10,
+        "M" => 1 << 20,
+        "G" => 1 << 30,
+        "T" => 1 << 40,
+        _ => return None,
+    };
+    let number = number.trim().parse::<f64>().ok()?;
+    // a limit of nothing would hold everything back
+    match (number * multiplier as f64) as u64 {
+        0 => None,
+        size => Some(size),
+    }
+}
+
+/**
+ * Whether the guard holds back at this resident memory, from 90% of the
+ * limit
+ */
+fn holds_back(limit: u64, resident: u64) -> bool {
+    resident >= limit / 10 * 9
+}
+
+/**
+ * The resident memory when it's already too close to the limit for the scan
+ * to start, the guard would hold back from the first job and never let go
+ */
+pub fn too_low(limit: u64) -> Option<u64> {
+    resident_bytes().filter(|resident| holds_back(limit, *resident))
+}
+
+/**
+ * The resident memory of the process, only known on linux
+ */
+fn resident_bytes() -> Option<u64> {
+    status_resident_bytes("self")
+}
+
+/**
+ * The resident memory from the status file of a process
+ */
+fn status_resident_bytes(pid: &str) -> Option<u64> {
+    let status = fs::read_to_string(format!("/proc/{}/status", pid)).ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    let kb = line.split_whitespace().nth(1)?.parse::<u64>().ok()?;
+    Some(kb * 1024)
+}
+
+/**
+ * The resident memory of a process and all of its children, such as chrome
+ * and its renderers, only known on linux
+ */
+pub fn tree_resident_bytes(pid: u32) -> Option<u64> {
+    // the parent of every process, from the field after the name in its stat
+    let mut parents = vec![];
+    for entry in fs::read_dir("/proc").ok()?.flatten() {
+        let child = match entry
+            .file_name()
+            .to_str()
+            .and_then(|name| name.parse::<u32>().ok())
+        {
+            Some(child) => child,
+            None => continue,
+        };
+        let stat = match fs::read_to_string(entry.path().join("stat")) {
+            Ok(stat) => stat,
+            Err(_) => continue,
+        };
+        let parent = stat
+            .rsplit_once(')')
+            .and_then(|(_, fields)| fields.split_whitespace().nth(1))
+            .and_then(|parent| parent.parse::<u32>().ok());
+        if let Some(parent) = parent {
+            parents.push((child, parent));
+        }
+    }
+
+    let mut tree = vec![pid];
+    let mut i = 0;
+    while i < tree.len() {
+        let parent = tree[i];
+        tree.extend(
+            parents
+                .iter()
+                .filter(|(_, p)| *p == parent)
+                .map(|(child, _)| *child),
+        );
+        i += 1;
+    }
+    let mut total = status_resident_bytes(&pid.to_string())?;
+    for child in &tree[1..] {
+        total += status_resident_bytes(&child.to_string()).unwrap_or(0);
+    }
+    Some(total)
+}
+
+// the most of a body read while the memory is over the limit
+pub const HELD_BODY: usize = 1024 * 1024;
+
+/**
+ * Holds back new jobs while the resident memory is close to the limit, the
+ * requests already sent are still read, their bodies cut at HELD_BODY
+ */
+pub struct MemoryGuard {
+    limit: u64,
+    over: AtomicBool,
+}
+
+impl MemoryGuard {
+    pub fn new(limit: u64) -> Self {
+        MemoryGuard {
+            limit,
+            over: AtomicBool::new(false),
+        }
+    }
+
+    /**
+     * Sample the resident memory, holding back once it passes 90% of the
+     * limit and letting go again below 75%
+     */
+    pub async fn run(self: Arc<Self>, silent: bool) {
+        if resident_bytes().is_none() {
+            if !silent {
+                eprintln!(
+                    "{}",
+                    "[memory] resident memory is unknown on this system, --max-memory is ignored"
+                        .yellow()
+                );
+            }
+            return;
+        }
+        loop {
+            tokio::time::sleep(Duration::from_millis(250)).await;
+            let resident = match resident_bytes() {
+                Some(resident) => resident,
+                None => continue,
+            };
+            let over = self.over.load(Ordering::SeqCst);
+            if !over && holds_back(self.limit, resident) {
+                self.over.store(true, Ordering::SeqCst);
+                if !silent {
+                    eprintln!(
+                        "{}",
+                        format!(
+                            "[memory] {}MB resident, holding back new work",
+                            resident >> 20
+                        )
+                        .yellow()
+                    );
+                }
+            } else if over && resident < self.limit / 4 * 3 {
+                self.over.store(false, Ordering::SeqCst);
+                if !silent {
+                    eprintln!(
+                        "{}",
+                        format!("[memory] {}MB resident, resuming", resident >> 20).white()
+                    );
+                }
+            }
+        }
+    }
+
+    /**
+     * Whether the memory is over the limit right now
+     */
+    pub fn is_over(&self) -> bool {
+        self.over.load(Ordering::SeqCst)
+    }
+
+    /**
+     * Wait until the memory use has dropped back down
+     */
+    pub async fn wait_for_room(&self) {
+        while self.over.load(Ordering::SeqCst) {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_sizes() {
+        assert_eq!(parse_size("512"), Some(512));
+        assert_eq!(parse_size("2k"), Some(2048));
+        assert_eq!(parse_size("512M"), Some(512 << 20));
+        assert_eq!(parse_size("1.5GB"), Some(3 << 29));
+        assert_eq!(parse_size(" 1 T "), Some(1 << 40));
+    }
+
+    #[test]
+    fn rejects_a_size_of_nothing() {
+        assert_eq!(parse_size("0"), None);
+        assert_eq!(parse_size("0G"), None);
+        assert_eq!(parse_size("-1G"), None);
+        assert_eq!(parse_size("0.0000001"), None);
+    }
+
+    #[test]
+    fn rejects_what_isnt_a_size() {
+        assert_eq!(parse_size(""), None);
+        assert_eq!(parse_size("G"), None);
+        assert_eq!(parse_size("2X"), None);
+        assert_eq!(parse_size("lots"), None);
+    }
+
+    #[test]
+    fn holds_back_from_90_percent() {
+        assert!(!holds_back(1000, 0));
+        assert!(!holds_back(1000, 899));
+        assert!(holds_back(1000, 900));
+        assert!(holds_back(1000, 5000));
+    }
+
+    #[test]
+    fn refuses_a_limit_below_the_resident_memory() {
+        if resident_bytes().is_none() {
+            return;
+        }
+        assert!(too_low(1).is_some());
+        assert!(too_low(u64::MAX).is_none());
+    }
+}
@@ -0,0 +1,77 @@
+use crate::{
+    convert::read_records,
+    output::{Compression, Encoding, Output, Record},
+};
+use chrono::{DateTime, FixedOffset};
+use std::{collections::HashMap, io};
+
+/**
+ * What a merge kept out of what it read
+ */
+pub struct Merged {
+    pub read: usize,
+    pub written: usize,
+}
+
+/**
+ * When a record was probed, the records without a readable timestamp lose
+ * to every other
+ */
+fn probed_at(record: &Record) -> Option<DateTime<FixedOffset>> {
+    DateTime::parse_from_rfc3339(&record.timestamp).ok()
+}
+
+/**
+ * Keep one record of each url, the most recently probed one, in the order
+ * the urls were first seen. On the same time the later file wins
+ */
+pub fn merge_records(records: Vec<Record>) -> Vec<Record> {
+    let mut merged: Vec<Record> = vec![];
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    for record in records {
+        match positions.get(&record.url) {
+            Some(&position) => {
+                if probed_at(&record) >= probed_at(&merged[position]) {
+                    merged[position] = record;
+                }
+            }
+            None => {
+                positions.insert(record.url.clone(), merged.len());
+                merged.push(record);
+            }
+        }
+    }
+    merged
+}
+
+/**
+ * Merge the --json results of the inputs into one file, or stdout,
+ * compressed when its extension asks for it
+ */
+pub fn merge(inputs: &[String], output: Option<&str>) -> io::Result<Merged> {
+    let mut records = vec![];
+    for input in inputs {
+        records.extend(
+            read_records(input)
+                .map_err(|err| io::Error::new(err.kind(), format!("{}: {}", input, err)))?,
+        );
+    }
+    let read = records.len();
+    let merged = merge_records(records);
+
+    let out = Output::create(
+        Encoding::Json,
+        output,
+        output.and_then(Compression::from_path),
+        None,
+        None,
+    )?;
+    for record in &merged {
+        out.write_record(record)?;
+    }
+    out.finish()?;
+    Ok(Merged {
+        read,
+        written: merged.len(),
+    })
+}
@@ -0,0 +1,119 @@
+use crate::{fetch_method, header_value, Shared};
+use rand::{distributions::Alphanumeric, Rng};
+use reqwest::{
+    header::{HeaderMap, HeaderValue, CONTENT_LENGTH},
+    Method,
+};
+use std::collections::BTreeMap;
+
+// the methods compared with GET by --method-diff
+const DIFF_METHODS: [Method; 2] = [Method::POST, Method::PUT];
+
+// the methods worth flagging when a host accepts them
+pub const RISKY_METHODS: [&str; 3] = ["PUT", "DELETE", "TRACE"];
+
+/**
+ * Find out which methods the url accepts, asking with OPTIONS first and
+ * falling back to trying the risky methods one by one
+ */
+pub async fn discover_methods(
+    client: &reqwest::Client,
+    url: &str,
+    timeout: Option<usize>,
+    shared: &Shared,
+) -> Vec<String> {
+    if let Some(resp) = fetch_method(
+        client,
+        Method::OPTIONS,
+        url,
+        HeaderMap::new(),
+        timeout,
+        shared,
+    )
+    .await
+    {
+        if let Some(allow) = header_value(resp.headers(), "Allow") {
+            return allow
+                .split(',')
+                .map(|method| method.trim().to_uppercase())
+                .filter(|method| !method.is_empty())
+                .collect();
+        }
+    }
+
+    // PUT and DELETE go to a throwaway path so nothing real gets touched
+    let throwaway: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(12)
+        .map(char::from)
+        .collect();
+    let throwaway_url = format!("{}/hrekt-{}.txt", url.trim_end_matches('/'), throwaway);
+
+    let mut methods = vec![];
+    for method in RISKY_METHODS {
+        let method_url = if method == "TRACE" {
+            url
+        } else {
+            throwaway_url.as_str()
+        };
+        let method = match Method::from_bytes(method.as_bytes()) {
+            Ok(method) => method,
+            Err(_) => continue,
+        };
+        if let Some(resp) = fetch_method(
+            client,
+            method.clone(),
+            method_url,
+            HeaderMap::new(),
+            timeout,
+            shared,
+        )
+        .await
+        {
+            if resp.status().is_success() {
+                methods.push(method.to_string());
+            }
+        }
+    }
+    methods
+}
+
+/**
+ * Ask for the url with POST and PUT too, empty, and return the status of
+ * each method when one falls in another class than GET, such as a page only
+ * forbidden to GET, empty when they all agree
+ */
+pub async fn method_diff(
+    client: &reqwest::Client,
+    url: &str,
+    get_status: u16,
+    timeout: Option<usize>,
+    shared: &Shared,
+) -> BTreeMap<String, u16> {
+    let mut statuses = BTreeMap::from([("GET".to_string(), get_status)]);
+    for method in DIFF_METHODS {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_LENGTH, HeaderValue::from_static("0"));
+        if let Some(resp) =
+            fetch_method(client, method.clone(), url, headers, timeout, shared).await
+        {
+            statuses.insert(method.to_string(), resp.status().as_u16());
+        }
+    }
+    if statuses
+        .values()
+        .all(|status| status / 100 == get_status / 100)
+    {
+        return BTreeMap::new();
+    }
+    statuses
+}
+
+/**
+ * Check whether any of the methods should be flagged
+ */
+pub fn has_risky_method(methods: &[String]) -> bool {
+    methods
+        .iter()
+        .any(|method| RISKY_METHODS.contains(&method.as_str()))
+}
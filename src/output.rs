@@ -0,0 +1,591 @@
+use crate::{cert::CertExpiry, format_result, proto, timing::millis, ProbeResult};
+use chrono::Utc;
+use colored::Colorize;
+use flate2::write::GzEncoder;
+use prost::Message;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{self, BufWriter, Write},
+    sync::Mutex,
+};
+
+/**
+ * The version of the structured output, bumped whenever a field is removed,
+ * renamed or changes type. Adding a field doesn't bump it, so parsers should
+ * ignore the fields they don't know.
+ */
+pub const SCHEMA_VERSION: u32 = 1;
+
+// the json schema of a record, printed by `hrekt schema`
+pub const SCHEMA: &str = include_str!("../schema/result.schema.json");
+
+/**
+ * How the results are written
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    Text,
+    Json,
+    Proto,
+    Msgpack,
+}
+
+impl Encoding {
+    pub fn parse(encoding: &str) -> Result<Encoding, String> {
+        match encoding.trim().to_lowercase().as_str() {
+            "text" => Ok(Encoding::Text),
+            "json" => Ok(Encoding::Json),
+            "proto" => Ok(Encoding::Proto),
+            "msgpack" => Ok(Encoding::Msgpack),
+            encoding => Err(format!("{} isn't text, json, proto or msgpack", encoding)),
+        }
+    }
+}
+
+/**
+ * How the output is compressed as it's written
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    pub fn parse(compression: &str) -> Result<Compression, String> {
+        match compression.trim().to_lowercase().as_str() {
+            "gzip" | "gz" => Ok(Compression::Gzip),
+            "zstd" | "zst" => Ok(Compression::Zstd),
+            compression => Err(format!("{} isn't gzip or zstd", compression)),
+        }
+    }
+
+    /**
+     * The compression a file name asks for with its extension
+     */
+    pub fn from_path(path: &str) -> Option<Compression> {
+        if path.ends_with(".gz") {
+            Some(Compression::Gzip)
+        } else if path.ends_with(".zst") {
+            Some(Compression::Zstd)
+        } else {
+            None
+        }
+    }
+}
+
+/**
+ * The order the results are written in when --sort holds them back
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Sort {
+    Status,
+    Length,
+    Url,
+    Latency,
+}
+
+impl Sort {
+    pub fn parse(sort: &str) -> Result<Sort, String> {
+        match sort.trim().to_lowercase().as_str() {
+            "status" => Ok(Sort::Status),
+            "length" => Ok(Sort::Length),
+            "url" => Ok(Sort::Url),
+            "latency" => Ok(Sort::Latency),
+            sort => Err(format!("{} isn't status, length, url or latency", sort)),
+        }
+    }
+
+    /**
+     * What a result is ordered by, the results without a length or latency
+     * go last and the ties are broken by url
+     */
+    fn key(&self, result: &ProbeResult) -> (u128, String) {
+        let key = match self {
+            Sort::Status => result.status_code as u128,
+            Sort::Length => result
+                .content_length
+                .or(result.length)
+                .map_or(u128::MAX, |length| length as u128),
+            Sort::Url => 0,
+            Sort::Latency => result
+                .latency
+                .map_or(u128::MAX, |latency| latency.as_nanos()),
+        };
+        (key, result.url.clone())
+    }
+}
+
+/**
+ * What the results are nested under when --group-by holds them back
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GroupBy {
+    Ip,
+}
+
+impl GroupBy {
+    pub fn parse(group_by: &str) -> Result<GroupBy, String> {
+        match group_by.trim().to_lowercase().as_str() {
+            "ip" => Ok(GroupBy::Ip),
+            group_by => Err(format!("{} isn't ip", group_by)),
+        }
+    }
+
+    /**
+     * The group of a result, the address the host resolved to or else the
+     * one that answered
+     */
+    fn key(&self, result: &ProbeResult) -> String {
+        match self {
+            GroupBy::Ip => result
+                .ip_group
+                .clone()
+                .or_else(|| result.ip.clone())
+                .unwrap_or_else(|| "unresolved".to_string()),
+        }
+    }
+}
+
+/**
+ * A result held back until the end, already encoded
+ */
+struct Held {
+    group: String,
+    key: (u128, String),
+    buf: Vec<u8>,
+}
+
+/**
+ * The Strict-Transport-Security policy of a record
+ */
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HstsRecord {
+    pub present: bool,
+    pub max_age: Option<u64>,
+    pub include_subdomains: bool,
+    pub preload: bool,
+}
+
+/**
+ * The caching headers of a record
+ */
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CacheRecord {
+    pub cache_control: Option<String>,
+    pub expires: Option<String>,
+    pub vary: Option<String>,
+    pub sets_cookie: bool,
+    pub cacheable: bool,
+}
+
+/**
+ * The phases of the request of a record, in milliseconds
+ */
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TimingRecord {
+    pub dns_ms: Option<f64>,
+    pub connect_ms: Option<f64>,
+    pub tls_ms: Option<f64>,
+    pub ttfb_ms: f64,
+    pub total_ms: f64,
+}
+
+/**
+ * Why the probe of a record failed
+ */
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ErrorRecord {
+    pub kind: String,
+    pub message: String,
+}
+
+/**
+ * A result as written in the structured output, every field is always
+ * present, null or empty when it wasn't asked for. Read back, the fields
+ * missing from older files are left empty
+ */
+#[derive(Clone, Debug, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct Record {
+    pub schema_version: u32,
+    pub timestamp: String,
+    pub url: String,
+    pub ip: Option<String>,
+    pub ip_family: Option<String>,
+    pub ip_group: Option<String>,
+    pub status_code: u16,
+    pub title: Option<String>,
+    pub tech: Vec<String>,
+    pub tech_confidence: BTreeMap<String, u8>,
+    pub content_type: Option<String>,
+    pub content_length: Option<u64>,
+    pub words: Option<u64>,
+    pub lines: Option<u64>,
+    pub body_hash: Option<String>,
+    pub server: Option<String>,
+    pub hsts: Option<HstsRecord>,
+    pub tls_error: Option<String>,
+    pub cert_expired: bool,
+    pub cert_expires_in_days: Option<i32>,
+    pub methods: Option<Vec<String>>,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub cache: Option<CacheRecord>,
+    pub matches: Vec<String>,
+    pub links: Vec<String>,
+    pub emails: Vec<String>,
+    pub js_endpoints: Vec<String>,
+    pub js_secrets: Vec<String>,
+    pub plugin_fields: Vec<String>,
+    pub script_tags: Vec<String>,
+    pub tags: Vec<String>,
+    pub redirect_host: Option<String>,
+    pub banner: Option<String>,
+    pub timing: Option<TimingRecord>,
+    pub bypasses: Vec<String>,
+    pub method_diff: BTreeMap<String, u16>,
+    pub duplicates: Option<u64>,
+    pub error: Option<ErrorRecord>,
+    pub failed: bool,
+}
+
+impl Record {
+    /**
+     * Build the record of a result, stamped with the current time
+     */
+    pub fn from_result(result: &ProbeResult) -> Record {
+        Record {
+            schema_version: SCHEMA_VERSION,
+            timestamp: Utc::now().to_rfc3339(),
+            url: result.url.clone(),
+            ip: result.ip.clone(),
+            ip_family: result.family.map(|family| family.to_string()),
+            ip_group: result.ip_group.clone(),
+            status_code: result.status_code,
+            title: result.title.clone(),
+            tech: result.tech.iter().map(|tech| tech.name.clone()).collect(),
+            tech_confidence: result
+                .tech
+                .iter()
+                .map(|tech| (tech.name.clone(), tech.confidence))
+                .collect(),
+            content_type: result.content_type.clone(),
+            content_length: result.content_length,
+            words: result.words.map(|words| words as u64),
+            lines: result.lines.map(|lines| lines as u64),
+            body_hash: result.body_hash.clone(),
+            server: result.server.clone(),
+            hsts: result.hsts.as_ref().map(|hsts| HstsRecord {
+                present: hsts.present,
+                max_age: hsts.max_age,
+                include_subdomains: hsts.include_subdomains,
+                preload: hsts.preload,
+            }),
+            tls_error: result.tls.as_ref().and_then(|tls| tls.error.clone()),
+            cert_expired: matches!(
+                result.tls.as_ref().and_then(|tls| tls.cert_expiry.as_ref()),
+                Some(CertExpiry::Expired)
+            ),
+            cert_expires_in_days: match result.tls.as_ref().and_then(|tls| tls.cert_expiry.as_ref())
+            {
+                Some(CertExpiry::Expiring(days)) => Some(*days),
+                _ => None,
+            },
+            methods: result.methods.clone(),
+            etag: result.etag.clone(),
+            last_modified: result.last_modified.clone(),
+            cache: result.cache_info.as_ref().map(|cache_info| CacheRecord {
+                cache_control: cache_info.cache_control.clone(),
+                expires: cache_info.expires.clone(),
+                vary: cache_info.vary.clone(),
+                sets_cookie: cache_info.sets_cookie,
+                cacheable: cache_info.cacheable(),
+            }),
+            matches: result.matches.clone(),
+            links: result.links.clone(),
+            emails: result.emails.clone(),
+            js_endpoints: result.js_endpoints.clone(),
+            js_secrets: result.js_secrets.clone(),
+            plugin_fields: result.plugin_fields.clone(),
+            script_tags: result.script_tags.clone(),
+            tags: result.tags.clone(),
+            redirect_host: result.redirect_host.clone(),
+            banner: result.banner.clone(),
+            timing: result.timing.as_ref().map(|timing| TimingRecord {
+                dns_ms: timing.dns.map(millis),
+                connect_ms: timing.connect.map(millis),
+                tls_ms: timing.tls.map(millis),
+                ttfb_ms: millis(timing.ttfb),
+                total_ms: millis(timing.total),
+            }),
+            bypasses: result.bypasses.clone(),
+            method_diff: result.method_diff.clone(),
+            duplicates: result.duplicates.map(|duplicates| duplicates as u64),
+            error: result.error.as_ref().map(|error| ErrorRecord {
+                kind: error.kind.as_str().to_string(),
+                message: error.message.clone(),
+            }),
+            failed: result.failed,
+        }
+    }
+}
+
+/**
+ * Format a result as a json line
+ */
+pub fn to_json(result: &ProbeResult) -> String {
+    serde_json::to_string(&Record::from_result(result)).unwrap_or_default()
+}
+
+/**
+ * Encode a result as a protobuf message prefixed with its length
+ */
+pub fn to_proto(result: &ProbeResult) -> Vec<u8> {
+    proto::ProbeResult::from(Record::from_result(result)).encode_length_delimited_to_vec()
+}
+
+/**
+ * Encode a result as a messagepack map keyed by the json field names
+ */
+pub fn to_msgpack(result: &ProbeResult) -> Vec<u8> {
+    rmp_serde::to_vec_named(&Record::from_result(result)).unwrap_or_default()
+}
+
+/**
+ * Where the results are written, stdout as it comes unless a file or a
+ * compression was asked for
+ */
+enum Sink {
+    Plain(BufWriter<Box<dyn Write + Send>>),
+    Gzip(GzEncoder<BufWriter<Box<dyn Write + Send>>>),
+    Zstd(zstd::Encoder<'static, BufWriter<Box<dyn Write + Send>>>),
+}
+
+impl Sink {
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        match self {
+            Sink::Plain(out) => out.write_all(buf),
+            Sink::Gzip(out) => out.write_all(buf),
+            Sink::Zstd(out) => out.write_all(buf),
+        }
+    }
+
+    /**
+     * Push what's written so far to the file, the compressed streams stay
+     * open and readable up to here
+     */
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Sink::Plain(out) => out.flush(),
+            Sink::Gzip(out) => out.flush(),
+            Sink::Zstd(out) => out.flush(),
+        }
+    }
+
+    /**
+     * Write out the end of the compressed stream and flush it
+     */
+    fn finish(&mut self) -> io::Result<()> {
+        match self {
+            Sink::Plain(out) => out.flush(),
+            Sink::Gzip(out) => {
+                out.try_finish()?;
+                out.get_mut().flush()
+            }
+            Sink::Zstd(out) => {
+                out.do_finish()?;
+                out.get_mut().flush()
+            }
+        }
+    }
+}
+
+/**
+ * The results written in the chosen encoding to stdout or a file
+ */
+pub struct Output {
+    encoding: Encoding,
+    sink: Option<Mutex<Sink>>,
+    sort: Option<Sort>,
+    group_by: Option<GroupBy>,
+    held: Mutex<Vec<Held>>,
+}
+
+impl Output {
+    /**
+     * Create the output file, or stdout when there's no path, compressed when
+     * a compression is given, the results are held back and written in order
+     * or in groups by finish when a sort or a grouping is given
+     */
+    pub fn create(
+        encoding: Encoding,
+        path: Option<&str>,
+        compression: Option<Compression>,
+        sort: Option<Sort>,
+        group_by: Option<GroupBy>,
+    ) -> io::Result<Output> {
+        if path.is_none() && compression.is_none() {
+            return Ok(Output {
+                encoding,
+                sink: None,
+                sort,
+                group_by,
+                held: Mutex::new(vec![]),
+            });
+        }
+        let out: Box<dyn Write + Send> = match path {
+            Some(path) => Box::new(File::create(path)?),
+            None => Box::new(io::stdout()),
+        };
+        let out = BufWriter::new(out);
+        let sink = match compression {
+            Some(Compression::Gzip) => {
+                Sink::Gzip(GzEncoder::new(out, flate2::Compression::default()))
+            }
+            Some(Compression::Zstd) => Sink::Zstd(zstd::Encoder::new(out, 0)?),
+            None => Sink::Plain(out),
+        };
+        Ok(Output {
+            encoding,
+            sink: Some(Mutex::new(sink)),
+            sort,
+            group_by,
+            held: Mutex::new(vec![]),
+        })
+    }
+
+    /**
+     * An output that drops the results, for the scans whose results are
+     * taken from the stream instead
+     */
+    pub fn discard(encoding: Encoding) -> Output {
+        Output {
+            encoding,
+            sink: Some(Mutex::new(Sink::Plain(BufWriter::new(
+                Box::new(io::sink()),
+            )))),
+            sort: None,
+            group_by: None,
+            held: Mutex::new(vec![]),
+        }
+    }
+
+    /**
+     * Write a result in the chosen encoding, in one write so the results of
+     * concurrent workers don't interleave
+     */
+    pub fn write_result(&self, result: &ProbeResult, status_codes: bool) -> io::Result<()> {
+        let buf = match self.encoding {
+            Encoding::Text => format!("{}\n", format_result(result, status_codes)).into_bytes(),
+            Encoding::Json => format!("{}\n", to_json(result)).into_bytes(),
+            Encoding::Proto => to_proto(result),
+            Encoding::Msgpack => to_msgpack(result),
+        };
+        // encoded now so the timestamps are when the results came in
+        if self.sort.is_some() || self.group_by.is_some() {
+            self.held.lock().unwrap().push(Held {
+                group: self
+                    .group_by
+                    .map(|group_by| group_by.key(result))
+                    .unwrap_or_default(),
+                key: self.sort.map(|sort| sort.key(result)).unwrap_or_default(),
+                buf,
+            });
+            return Ok(());
+        }
+        self.write(&buf)
+    }
+
+    /**
+     * Flush the results written so far, the held back ones are only written
+     * by finish
+     */
+    pub fn flush(&self) -> io::Result<()> {
+        match &self.sink {
+            Some(sink) => sink.lock().unwrap().flush(),
+            None => io::stdout().lock().flush(),
+        }
+    }
+
+    /**
+     * Write a record read back from earlier results as a json line
+     */
+    pub fn write_record(&self, record: &Record) -> io::Result<()> {
+        let json = serde_json::to_string(record)?;
+        self.write(format!("{}\n", json).as_bytes())
+    }
+
+    fn write(&self, buf: &[u8]) -> io::Result<()> {
+        match &self.sink {
+            Some(sink) => sink.lock().unwrap().write_all(buf),
+            None => io::stdout().lock().write_all(buf),
+        }
+    }
+
+    /**
+     * Write a group of results under its address, indented in the text output
+     * and as one record with the results in an array in json
+     */
+    fn write_group(&self, group: &str, held: &[Held]) -> io::Result<()> {
+        match self.encoding {
+            Encoding::Text => {
+                let mut buf = format!("{} ({})\n", group.bold(), held.len()).into_bytes();
+                for held in held {
+                    buf.extend_from_slice(b"  ");
+                    buf.extend_from_slice(&held.buf);
+                }
+                self.write(&buf)
+            }
+            Encoding::Json => {
+                let results = held
+                    .iter()
+                    .map(|held| String::from_utf8_lossy(&held.buf).trim_end().to_string())
+                    .collect::<Vec<String>>();
+                let buf = format!(
+                    "{{\"ip\":{},\"results\":[{}]}}\n",
+                    serde_json::to_string(group).unwrap_or_default(),
+                    results.join(",")
+                );
+                self.write(buf.as_bytes())
+            }
+            // refused along with --group-by
+            Encoding::Proto | Encoding::Msgpack => {
+                for held in held {
+                    self.write(&held.buf)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /**
+     * Write the held back results in order, the biggest groups first, then
+     * close the compressed stream and flush what's left to the file
+     */
+    pub fn finish(&self) -> io::Result<()> {
+        let mut held = std::mem::take(&mut *self.held.lock().unwrap());
+        held.sort_by(|a, b| a.key.cmp(&b.key));
+        if self.group_by.is_none() {
+            for held in held {
+                self.write(&held.buf)?;
+            }
+        } else {
+            let mut groups: BTreeMap<String, Vec<Held>> = BTreeMap::new();
+            for held in held {
+                groups.entry(held.group.clone()).or_default().push(held);
+            }
+            let mut groups = groups.into_iter().collect::<Vec<(String, Vec<Held>)>>();
+            groups.sort_by_key(|(_, held)| std::cmp::Reverse(held.len()));
+            for (group, held) in groups {
+                self.write_group(&group, &held)?;
+            }
+        }
+        match &self.sink {
+            Some(sink) => sink.lock().unwrap().finish(),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use regex::Regex;
+use std::fs;
+
+/**
+ * A regex searched for in the responses, optionally named by a label
+ */
+#[derive(Clone, Debug)]
+pub struct Pattern {
+    pub label: Option<String>,
+    pub regex: Regex,
+}
+
+impl Pattern {
+    /**
+     * Parse a `label:pattern` or bare `pattern`, the label may only hold
+     * letters, digits, '-' and '_' so a ':' inside the regex isn't mistaken for one
+     */
+    pub fn parse(value: &str) -> Result<Pattern, regex::Error> {
+        if let Some((label, pattern)) = value.split_once(':') {
+            let is_label = !label.is_empty()
+                && label
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+            if is_label {
+                return Ok(Pattern {
+                    label: Some(label.to_string()),
+                    regex: Regex::new(pattern)?,
+                });
+            }
+        }
+        Ok(Pattern {
+            label: None,
+            regex: Regex::new(value)?,
+        })
+    }
+
+    /**
+     * The label, or the regex itself when there isn't one
+     */
+    pub fn name(&self) -> String {
+        match &self.label {
+            Some(label) => label.clone(),
+            None => self.regex.as_str().to_string(),
+        }
+    }
+}
+
+// the built-in secret patterns grouped into packs, as (pack, label, regex)
+const SECRET_PATTERNS: [(&str, &str, &str); 10] = [
+    (
+        "aws",
+        "aws-access-key",
+        r"\b(?:AKIA|ASIA|AGPA|AIDA|AROA)[0-9A-Z]{16}\b",
+    ),
+    (
+        "aws",
+        "aws-secret-key",
+        r#"(?i)aws_?secret_?access_?key["'\s]*[:=]\s*["']?[0-9A-Za-z/+]{40}\b"#,
+    ),
+    ("gcp", "gcp-api-key", r"\bAIza[0-9A-Za-z_-]{35}\b"),
+    (
+        "gcp",
+        "gcp-service-account",
+        r#""type"\s*:\s*"service_account""#,
+    ),
+    (
+        "gcp",
+        "gcp-oauth-client",
+        r"\b[0-9]+-[0-9a-z_]{32}\.apps\.googleusercontent\.com\b",
+    ),
+    (
+        "jwt",
+        "jwt",
+        r"\beyJ[0-9A-Za-z_-]{8,}\.eyJ[0-9A-Za-z_-]{8,}\.[0-9A-Za-z_-]{16,}",
+    ),
+    ("slack", "slack-token", r"\bxox[abposr]-[0-9A-Za-z-]{10,}"),
+    (
+        "slack",
+        "slack-webhook",
+        r"https://hooks\.slack\.com/services/T[0-9A-Z]+/B[0-9A-Z]+/[0-9A-Za-z]+",
+    ),
+    (
+        "private-key",
+        "private-key",
+        r"-----BEGIN (?:RSA |EC |DSA |OPENSSH |PGP |ENCRYPTED )?PRIVATE KEY( BLOCK)?-----",
+    ),
+    (
+        "private-key",
+        "putty-private-key",
+        r"PuTTY-User-Key-File-[0-9]+:",
+    ),
+];
+
+/**
+ * The built-in secret patterns of the comma separated packs, "all" selects every pack
+ */
+pub fn secret_patterns(packs: &str) -> Result<Vec<Pattern>, String> {
+    let packs = packs
+        .split(',')
+        .map(|pack| pack.trim().to_lowercase())
+        .filter(|pack| !pack.is_empty())
+        .collect::<Vec<String>>();
+    for pack in &packs {
+        if pack != "all" && !SECRET_PATTERNS.iter().any(|(name, _, _)| name == pack) {
+            return Err(format!("unknown secret pack {}", pack));
+        }
+    }
+
+    let mut patterns = Vec::new();
+    for (pack, label, regex) in SECRET_PATTERNS {
+        if packs.iter().any(|p| p == "all" || p == pack) {
+            patterns.push(Pattern {
+                label: Some(label.to_string()),
+                regex: Regex::new(regex).map_err(|err| err.to_string())?,
+            });
+        }
+    }
+    Ok(patterns)
+}
+
+/**
+ * Read the patterns from a file, one per line, skipping the blank lines and '#' comments
+ */
+pub fn read_patterns_file(path: &str) -> Result<Vec<Pattern>, String> {
+    let contents = fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err))?;
+    let mut patterns = Vec::new();
+    for (number, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match Pattern::parse(line) {
+            Ok(pattern) => patterns.push(pattern),
+            Err(err) => return Err(format!("{}:{}: {}", path, number + 1, err)),
+        }
+    }
+    Ok(patterns)
+}
+
+/**
+ * Search the text with every pattern, returning whether any matched along
+ * with the labels of the labelled ones that did
+ */
+pub fn match_patterns(patterns: &[Pattern], text: &str) -> (bool, Vec<String>) {
+    let mut matched = false;
+    let mut labels = Vec::new();
+    for pattern in patterns {
+        if pattern.regex.is_match(text) {
+            matched = true;
+            if let Some(label) = &pattern.label {
+                labels.push(label.clone());
+            }
+        }
+    }
+    (matched, labels)
+}
@@ -0,0 +1,245 @@
+use reqwest::header::HeaderMap;
+#[cfg(feature = "plugins")]
+use std::sync::Arc;
+
+/**
+ * The wasm plugins run over every response, each one is handed the response
+ * as text in the same layout --store-response-dir uses:
+ *
+ *   url
+ *   HTTP status
+ *   name: value
+ *
+ *   body
+ *
+ * A plugin exports its memory, an `alloc(len: i32) -> i32` function and an
+ * `analyze(ptr: i32, len: i32) -> i64` function returning the pointer of its
+ * output in the high 32 bits and the length in the low ones. Every line of
+ * the output becomes an extra field, a line reading `drop` drops the result.
+ * A plugin running out of fuel on a response is stopped and skipped for it.
+ */
+pub struct Plugins {
+    #[cfg(feature = "plugins")]
+    engine: wasmtime::Engine,
+    #[cfg(feature = "plugins")]
+    modules: Arc<Vec<(String, wasmtime::Module)>>,
+}
+
+// the wasm instructions a plugin may run on one response before it's stopped,
+// about a second of work
+#[cfg(feature = "plugins")]
+const PLUGIN_FUEL: u64 = 1_000_000_000;
+
+/**
+ * What the plugins made of a response
+ */
+#[cfg_attr(not(feature = "plugins"), allow(dead_code))]
+pub enum Verdict {
+    Keep(Vec<String>),
+    Drop,
+}
+
+/**
+ * Lay the response out as the plugins' input
+ */
+#[cfg(feature = "plugins")]
+fn plugin_input(url: &str, status_code: u16, headers: &HeaderMap, body: &str) -> String {
+    let mut input = format!("{}\nHTTP {}\n", url, status_code);
+    for (name, value) in headers {
+        input.push_str(&format!(
+            "{}: {}\n",
+            name.as_str(),
+            value.to_str().unwrap_or_default()
+        ));
+    }
+    input.push('\n');
+    input.push_str(body);
+    input
+}
+
+#[cfg(feature = "plugins")]
+impl Plugins {
+    /**
+     * Compile the plugins up front so a broken one fails before the scan starts
+     */
+    pub fn load(paths: &[String]) -> Result<Plugins, String> {
+        let mut config = wasmtime::Config::new();
+        config.consume_fuel(true);
+        let engine = wasmtime::Engine::new(&config).map_err(|err| err.to_string())?;
+        let mut modules = vec![];
+        for path in paths {
+            let module = wasmtime::Module::from_file(&engine, path)
+                .map_err(|err| format!("{}: {}", path, err))?;
+            modules.push((path.clone(), module));
+        }
+        Ok(Plugins {
+            engine,
+            modules: Arc::new(modules),
+        })
+    }
+
+    /**
+     * Run a plugin in a fresh instance so no state leaks between responses,
+     * on a budget of fuel so a plugin that never returns can't hold a worker
+     */
+    fn call(
+        engine: &wasmtime::Engine,
+        module: &wasmtime::Module,
+        input: &[u8],
+    ) -> wasmtime::Result<String> {
+        let mut store = wasmtime::Store::new(engine, ());
+        store.add_fuel(PLUGIN_FUEL)?;
+        let instance = wasmtime::Instance::new(&mut store, module, &[])?;
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .ok_or_else(|| wasmtime::Error::msg("the plugin doesn't export its memory"))?;
+        let alloc = instance.get_typed_func::<i32, i32>(&mut store, "alloc")?;
+        let analyze = instance.get_typed_func::<(i32, i32), i64>(&mut store, "analyze")?;
+
+        let ptr = alloc.call(&mut store, input.len() as i32)?;
+        memory.write(&mut store, ptr as usize, input)?;
+        let packed = analyze.call(&mut store, (ptr, input.len() as i32))? as u64;
+        let mut output = vec![0u8; (packed & 0xffff_ffff) as usize];
+        memory.read(&store, (packed >> 32) as usize, &mut output)?;
+        Ok(String::from_utf8_lossy(&output).into_owned())
+    }
+
+    /**
+     * Hand the response to every plugin and gather their fields, on the
+     * blocking threads so the plugins don't stall the requests in flight
+     */
+    pub async fn run(
+        &self,
+        url: &str,
+        status_code: u16,
+        headers: &HeaderMap,
+        body: &str,
+    ) -> Verdict {
+        let input = plugin_input(url, status_code, headers, body);
+        let engine = self.engine.clone();
+        let modules = self.modules.clone();
+        let url = url.to_string();
+        let verdict = tokio::task::spawn_blocking(move || {
+            let mut fields = vec![];
+            for (path, module) in modules.iter() {
+                let output = match Plugins::call(&engine, module, input.as_bytes()) {
+                    Ok(output) => output,
+                    Err(err) => {
+                        eprintln!("plugin {} failed on {}: {}", path, url, err);
+                        continue;
+                    }
+                };
+                for line in output.lines().map(|line| line.trim()) {
+                    if line == "drop" {
+                        return Verdict::Drop;
+                    }
+                    if !line.is_empty() {
+                        fields.push(line.to_string());
+                    }
+                }
+            }
+            Verdict::Keep(fields)
+        })
+        .await;
+        verdict.unwrap_or(Verdict::Keep(vec![]))
+    }
+}
+
+#[cfg(not(feature = "plugins"))]
+impl Plugins {
+    pub fn load(_paths: &[String]) -> Result<Plugins, String> {
+        Err(
+            "hrekt was built without plugin support, rebuild it with --features plugins"
+                .to_string(),
+        )
+    }
+
+    pub async fn run(
+        &self,
+        _url: &str,
+        _status_code: u16,
+        _headers: &HeaderMap,
+        _body: &str,
+    ) -> Verdict {
+        Verdict::Keep(vec![])
+    }
+}
+
+#[cfg(all(test, feature = "plugins"))]
+mod tests {
+    use super::*;
+    use std::{fs, path::PathBuf};
+
+    /**
+     * Write the plugin in the wasm text format to a file of its own
+     */
+    fn plugin(name: &str, wat: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("hrekt-{}-{}.wat", name, std::process::id()));
+        fs::write(&path, wat).unwrap();
+        path
+    }
+
+    async fn run(name: &str, wat: &str) -> Verdict {
+        let path = plugin(name, wat);
+        let plugins = Plugins::load(&[path.to_string_lossy().into_owned()]).unwrap();
+        let verdict = plugins
+            .run(
+                "http://example.com",
+                200,
+                &HeaderMap::new(),
+                "<title>x</title>",
+            )
+            .await;
+        fs::remove_file(path).unwrap();
+        verdict
+    }
+
+    #[tokio::test]
+    async fn gathers_the_fields() {
+        let verdict = run(
+            "fields",
+            r#"(module
+                (memory (export "memory") 1)
+                (data (i32.const 1024) "cms:xyz\nversion:2")
+                (func (export "alloc") (param i32) (result i32) i32.const 0)
+                (func (export "analyze") (param i32 i32) (result i64)
+                    i64.const 4398046511121))"#,
+        )
+        .await;
+        assert!(matches!(verdict, Verdict::Keep(fields) if fields == ["cms:xyz", "version:2"]));
+    }
+
+    #[tokio::test]
+    async fn drops_the_result() {
+        let verdict = run(
+            "drop",
+            r#"(module
+                (memory (export "memory") 1)
+                (data (i32.const 1024) "drop")
+                (func (export "alloc") (param i32) (result i32) i32.const 0)
+                (func (export "analyze") (param i32 i32) (result i64)
+                    i64.const 4398046511108))"#,
+        )
+        .await;
+        assert!(matches!(verdict, Verdict::Drop));
+    }
+
+    #[tokio::test]
+    async fn stops_a_plugin_that_never_returns() {
+        let verdict = tokio::time::timeout(
+            std::time::Duration::from_secs(30),
+            run(
+                "spin",
+                r#"(module
+                    (memory (export "memory") 1)
+                    (func (export "alloc") (param i32) (result i32) i32.const 0)
+                    (func (export "analyze") (param i32 i32) (result i64)
+                        (loop $spin (br $spin))
+                        i64.const 0))"#,
+            ),
+        )
+        .await
+        .expect("the plugin ran out of fuel");
+        assert!(matches!(verdict, Verdict::Keep(fields) if fields.is_empty()));
+    }
+}
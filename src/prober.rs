@@ -0,0 +1,172 @@
+use crate::{
+    build_cli, configure, input, run_scan, Callbacks, Embedding, HttpClient, ProbeResult,
+    ProberConfig, Scan,
+};
+use futures::Stream;
+use std::{error::Error, ffi::OsString, iter, sync::Arc};
+use tokio::sync::watch;
+
+/**
+ * A scan run from code, its results handed over as a stream instead of
+ * printed. The options are the ones of the command line
+ *
+ *   let prober = Prober::from_args(["--title", "--tech-detect"], hosts).await?;
+ *   let mut results = prober.run();
+ *   while let Some(result) = results.next().await {
+ *       println!("{}", result.record().url);
+ *   }
+ */
+pub struct Prober {
+    scan: Scan,
+    http: Option<Arc<dyn HttpClient>>,
+    stop: Arc<watch::Sender<bool>>,
+}
+
+/**
+ * Stops the scan of a Prober the way ctrl-c stops the command line, the
+ * requests in flight are given a moment and the results are written out.
+ * The scan never listens for signals itself
+ */
+#[derive(Clone)]
+pub struct StopHandle {
+    stop: Arc<watch::Sender<bool>>,
+}
+
+impl StopHandle {
+    pub fn stop(&self) {
+        self.stop.send_replace(true);
+    }
+}
+
+impl Prober {
+    /**
+     * Set up a scan of the hosts with a built config
+     */
+    pub async fn new(
+        config: &ProberConfig,
+        targets: Vec<String>,
+    ) -> Result<Prober, Box<dyn Error + Send + Sync + 'static>> {
+        let mut scan = Prober::scan(config.args(), targets).await?;
+        config.apply(&mut scan.job, Arc::make_mut(&mut scan.settings));
+        Ok(Prober::with_scan(scan))
+    }
+
+    /**
+     * Read the options as the command line would, without the name of the
+     * binary, and the hosts to probe in any form the input accepts
+     */
+    pub async fn from_args<I, T>(
+        args: I,
+        targets: Vec<String>,
+    ) -> Result<Prober, Box<dyn Error + Send + Sync + 'static>>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString> + Clone,
+    {
+        let scan = Prober::scan(args, targets).await?;
+        Ok(Prober::with_scan(scan))
+    }
+
+    /**
+     * Read the options and the hosts into the scan they describe
+     */
+    async fn scan<I, T>(
+        args: I,
+        targets: Vec<String>,
+    ) -> Result<Scan, Box<dyn Error + Send + Sync + 'static>>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString> + Clone,
+    {
+        let args = iter::once(OsString::from("hrekt")).chain(args.into_iter().map(Into::into));
+        let matches = build_cli().try_get_matches_from(args)?;
+
+        let mut parsed = vec![];
+        for target in &targets {
+            match input::parse_input_line(target) {
+                Some(target) => parsed.push(target),
+                None if input::is_comment(target.trim()) => {}
+                None => return Err(format!("could not make out a host in {}", target).into()),
+            }
+        }
+        let targets = input::merge_ports(parsed);
+
+        let scan = match configure(&matches, Some(targets)).await? {
+            Some(scan) => scan,
+            None => return Err("the options don't describe a scan".into()),
+        };
+        if scan.analyze_dir.is_some() || scan.schedule.is_some() {
+            return Err("--analyze and --schedule can't be streamed".into());
+        }
+        Ok(scan)
+    }
+
+    fn with_scan(scan: Scan) -> Prober {
+        let (stop, _) = watch::channel(false);
+        Prober {
+            scan,
+            http: None,
+            stop: Arc::new(stop),
+        }
+    }
+
+    /**
+     * Send the requests through this client instead of the one each worker
+     * builds from the options, such as a mock in the tests
+     */
+    pub fn with_http_client(mut self, http: Arc<dyn HttpClient>) -> Prober {
+        self.http = Some(http);
+        self
+    }
+
+    /**
+     * A handle stopping the scan once it's running, from anywhere
+     */
+    pub fn stop_handle(&self) -> StopHandle {
+        StopHandle {
+            stop: self.stop.clone(),
+        }
+    }
+
+    /**
+     * Start the scan in the background and stream its results as they come,
+     * dropping the stream stops the scan. Must be called within a tokio
+     * runtime
+     */
+    pub fn run(self) -> impl Stream<Item = ProbeResult> {
+        let Scan {
+            targets,
+            job,
+            settings,
+            ..
+        } = self.scan;
+        let (results_tx, results_rx) = async_channel::bounded(settings.queue_size);
+        let embedding = Embedding {
+            results: Some(results_tx),
+            http: self.http,
+            stop: Some(self.stop.subscribe()),
+            ..Default::default()
+        };
+        tokio::spawn(run_scan(targets, job, settings, None, embedding));
+        results_rx
+    }
+
+    /**
+     * Run the scan to its end, calling the callbacks as it goes
+     */
+    pub async fn run_with<C: Callbacks + 'static>(self, callbacks: C) {
+        let Scan {
+            targets,
+            job,
+            settings,
+            ..
+        } = self.scan;
+        let embedding = Embedding {
+            callbacks: Some(Arc::new(callbacks)),
+            http: self.http,
+            stop: Some(self.stop.subscribe()),
+            ..Default::default()
+        };
+        run_scan(targets, job, settings, None, embedding).await;
+    }
+}
@@ -0,0 +1,213 @@
+use crate::output::Record;
+use std::collections::BTreeMap;
+
+/**
+ * The messages of schema/result.proto, kept in step with it by hand so the
+ * build doesn't need protoc
+ */
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Hsts {
+    #[prost(bool, tag = "1")]
+    pub present: bool,
+    #[prost(uint64, optional, tag = "2")]
+    pub max_age: Option<u64>,
+    #[prost(bool, tag = "3")]
+    pub include_subdomains: bool,
+    #[prost(bool, tag = "4")]
+    pub preload: bool,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Cache {
+    #[prost(string, optional, tag = "1")]
+    pub cache_control: Option<String>,
+    #[prost(string, optional, tag = "2")]
+    pub expires: Option<String>,
+    #[prost(string, optional, tag = "3")]
+    pub vary: Option<String>,
+    #[prost(bool, tag = "4")]
+    pub sets_cookie: bool,
+    #[prost(bool, tag = "5")]
+    pub cacheable: bool,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Timing {
+    #[prost(double, optional, tag = "1")]
+    pub dns_ms: Option<f64>,
+    #[prost(double, optional, tag = "2")]
+    pub connect_ms: Option<f64>,
+    #[prost(double, optional, tag = "3")]
+    pub tls_ms: Option<f64>,
+    #[prost(double, tag = "4")]
+    pub ttfb_ms: f64,
+    #[prost(double, tag = "5")]
+    pub total_ms: f64,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Error {
+    #[prost(string, tag = "1")]
+    pub kind: String,
+    #[prost(string, tag = "2")]
+    pub message: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ProbeResult {
+    #[prost(uint32, tag = "1")]
+    pub schema_version: u32,
+    #[prost(string, tag = "2")]
+    pub timestamp: String,
+    #[prost(string, tag = "3")]
+    pub url: String,
+    #[prost(string, optional, tag = "4")]
+    pub ip: Option<String>,
+    #[prost(string, optional, tag = "5")]
+    pub ip_family: Option<String>,
+    #[prost(string, optional, tag = "6")]
+    pub ip_group: Option<String>,
+    #[prost(uint32, tag = "7")]
+    pub status_code: u32,
+    #[prost(string, optional, tag = "8")]
+    pub title: Option<String>,
+    #[prost(string, repeated, tag = "9")]
+    pub tech: Vec<String>,
+    #[prost(string, optional, tag = "10")]
+    pub content_type: Option<String>,
+    #[prost(uint64, optional, tag = "11")]
+    pub content_length: Option<u64>,
+    #[prost(uint64, optional, tag = "12")]
+    pub words: Option<u64>,
+    #[prost(uint64, optional, tag = "13")]
+    pub lines: Option<u64>,
+    #[prost(string, optional, tag = "14")]
+    pub body_hash: Option<String>,
+    #[prost(string, optional, tag = "15")]
+    pub server: Option<String>,
+    #[prost(message, optional, tag = "16")]
+    pub hsts: Option<Hsts>,
+    #[prost(string, optional, tag = "17")]
+    pub tls_error: Option<String>,
+    #[prost(bool, tag = "18")]
+    pub cert_expired: bool,
+    #[prost(int32, optional, tag = "19")]
+    pub cert_expires_in_days: Option<i32>,
+    #[prost(string, repeated, tag = "20")]
+    pub methods: Vec<String>,
+    #[prost(string, optional, tag = "21")]
+    pub etag: Option<String>,
+    #[prost(string, optional, tag = "22")]
+    pub last_modified: Option<String>,
+    #[prost(message, optional, tag = "23")]
+    pub cache: Option<Cache>,
+    #[prost(string, repeated, tag = "24")]
+    pub matches: Vec<String>,
+    #[prost(string, repeated, tag = "25")]
+    pub links: Vec<String>,
+    #[prost(string, repeated, tag = "26")]
+    pub emails: Vec<String>,
+    #[prost(string, repeated, tag = "27")]
+    pub js_endpoints: Vec<String>,
+    #[prost(string, repeated, tag = "28")]
+    pub js_secrets: Vec<String>,
+    #[prost(string, repeated, tag = "29")]
+    pub plugin_fields: Vec<String>,
+    #[prost(string, repeated, tag = "30")]
+    pub script_tags: Vec<String>,
+    #[prost(string, repeated, tag = "31")]
+    pub tags: Vec<String>,
+    #[prost(string, optional, tag = "32")]
+    pub redirect_host: Option<String>,
+    #[prost(btree_map = "string, uint32", tag = "33")]
+    pub tech_confidence: BTreeMap<String, u32>,
+    #[prost(string, optional, tag = "34")]
+    pub banner: Option<String>,
+    #[prost(message, optional, tag = "35")]
+    pub timing: Option<Timing>,
+    #[prost(message, optional, tag = "36")]
+    pub error: Option<Error>,
+    #[prost(bool, tag = "37")]
+    pub failed: bool,
+    #[prost(string, repeated, tag = "38")]
+    pub bypasses: Vec<String>,
+    #[prost(btree_map = "string, uint32", tag = "39")]
+    pub method_diff: BTreeMap<String, u32>,
+    #[prost(uint64, optional, tag = "40")]
+    pub duplicates: Option<u64>,
+}
+
+impl From<Record> for ProbeResult {
+    fn from(record: Record) -> Self {
+        ProbeResult {
+            schema_version: record.schema_version,
+            timestamp: record.timestamp,
+            url: record.url,
+            ip: record.ip,
+            ip_family: record.ip_family,
+            ip_group: record.ip_group,
+            status_code: record.status_code as u32,
+            title: record.title,
+            tech: record.tech,
+            content_type: record.content_type,
+            content_length: record.content_length,
+            words: record.words,
+            lines: record.lines,
+            body_hash: record.body_hash,
+            server: record.server,
+            hsts: record.hsts.map(|hsts| Hsts {
+                present: hsts.present,
+                max_age: hsts.max_age,
+                include_subdomains: hsts.include_subdomains,
+                preload: hsts.preload,
+            }),
+            tls_error: record.tls_error,
+            cert_expired: record.cert_expired,
+            cert_expires_in_days: record.cert_expires_in_days,
+            methods: record.methods.unwrap_or_default(),
+            etag: record.etag,
+            last_modified: record.last_modified,
+            cache: record.cache.map(|cache| Cache {
+                cache_control: cache.cache_control,
+                expires: cache.expires,
+                vary: cache.vary,
+                sets_cookie: cache.sets_cookie,
+                cacheable: cache.cacheable,
+            }),
+            matches: record.matches,
+            links: record.links,
+            emails: record.emails,
+            js_endpoints: record.js_endpoints,
+            js_secrets: record.js_secrets,
+            plugin_fields: record.plugin_fields,
+            script_tags: record.script_tags,
+            tags: record.tags,
+            redirect_host: record.redirect_host,
+            tech_confidence: record
+                .tech_confidence
+                .into_iter()
+                .map(|(name, confidence)| (name, confidence as u32))
+                .collect(),
+            banner: record.banner,
+            timing: record.timing.map(|timing| Timing {
+                dns_ms: timing.dns_ms,
+                connect_ms: timing.connect_ms,
+                tls_ms: timing.tls_ms,
+                ttfb_ms: timing.ttfb_ms,
+                total_ms: timing.total_ms,
+            }),
+            error: record.error.map(|error| Error {
+                kind: error.kind,
+                message: error.message,
+            }),
+            failed: record.failed,
+            bypasses: record.bypasses,
+            method_diff: record
+                .method_diff
+                .into_iter()
+                .map(|(method, status)| (method, status as u32))
+                .collect(),
+            duplicates: record.duplicates,
+        }
+    }
+}
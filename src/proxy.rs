@@ -0,0 +1,166 @@
+use crate::{accept_invalid_certs, client_builder, connect::connect, Settings};
+use colored::Colorize;
+use std::{
+    fs,
+    sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering},
+    time::Duration,
+};
+
+// how many failed checks in a row before a proxy is dropped
+const MAX_STRIKES: u32 = 3;
+
+/**
+ * A proxy of the list with the clients sending through it
+ */
+struct Proxy {
+    addr: String,
+    client: reqwest::Client,
+    insecure_client: Option<reqwest::Client>,
+    strikes: AtomicU32,
+    dead: AtomicBool,
+}
+
+/**
+ * The proxies the probes are spread over, each one used for a number of
+ * probes before moving to the next, skipping the ones found dead
+ */
+pub struct ProxyPool {
+    proxies: Vec<Proxy>,
+    rotate: usize,
+    turns: AtomicUsize,
+    exhausted: AtomicBool,
+}
+
+/**
+ * Read a proxy from a line of the list, a bare host:port is taken as http
+ */
+fn parse_proxy(line: &str) -> Result<(String, String), String> {
+    let url = if line.contains("://") {
+        line.to_string()
+    } else {
+        format!("http://{}", line)
+    };
+    let parsed = reqwest::Url::parse(&url).map_err(|err| format!("{}: {}", line, err))?;
+    if !["http", "https", "socks5", "socks5h"].contains(&parsed.scheme()) {
+        return Err(format!("{} isn't an http, https or socks5 proxy", line));
+    }
+    let host = parsed
+        .host_str()
+        .ok_or_else(|| format!("{} has no host", line))?;
+    let port = parsed
+        .port_or_known_default()
+        .ok_or_else(|| format!("{} has no port", line))?;
+    Ok((url, format!("{}:{}", host, port)))
+}
+
+impl ProxyPool {
+    /**
+     * Read the proxies from a file, one per line, and build their clients
+     */
+    pub fn load(path: &str, rotate: usize, settings: &Settings) -> Result<ProxyPool, String> {
+        let contents = fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err))?;
+        let lines = contents
+            .lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .collect::<Vec<&str>>();
+        if lines.is_empty() {
+            return Err(format!("{}: no proxies found", path));
+        }
+        ProxyPool::build(&lines, rotate, settings)
+    }
+
+    /**
+     * A pool of the one proxy, such as the socks port of tor
+     */
+    pub fn single(proxy: &str, settings: &Settings) -> Result<ProxyPool, String> {
+        ProxyPool::build(&[proxy], 1, settings)
+    }
+
+    fn build(lines: &[&str], rotate: usize, settings: &Settings) -> Result<ProxyPool, String> {
+        let mut proxies = vec![];
+        for line in lines {
+            let (url, addr) = parse_proxy(line)?;
+            let proxy = reqwest::Proxy::all(&url).map_err(|err| format!("{}: {}", line, err))?;
+            let client = client_builder(settings)
+                .proxy(proxy.clone())
+                .build()
+                .map_err(|err| format!("{}: {}", line, err))?;
+            let insecure_client = if settings.verify_tls {
+                accept_invalid_certs(client_builder(settings).proxy(proxy))
+                    .build()
+                    .ok()
+            } else {
+                None
+            };
+            proxies.push(Proxy {
+                addr,
+                client,
+                insecure_client,
+                strikes: AtomicU32::new(0),
+                dead: AtomicBool::new(false),
+            });
+        }
+        Ok(ProxyPool {
+            proxies,
+            rotate: rotate.max(1),
+            turns: AtomicUsize::new(0),
+            exhausted: AtomicBool::new(false),
+        })
+    }
+
+    /**
+     * Whether the first proxy takes connections, checked before the scan
+     * starts so a proxy that isn't running fails it straight away
+     */
+    pub async fn reachable(&self, settings: &Settings) -> bool {
+        let timeout = Duration::from_secs(settings.timeout as u64);
+        matches!(
+            tokio::time::timeout(timeout, connect(&self.proxies[0].addr, settings)).await,
+            Ok(Ok(_))
+        )
+    }
+
+    /**
+     * The proxy whose turn it is with its clients, None once every proxy is
+     * dead so nothing leaves without one
+     */
+    pub fn next(&self) -> Option<(usize, &reqwest::Client, Option<&reqwest::Client>)> {
+        let start = self.turns.fetch_add(1, Ordering::Relaxed) / self.rotate;
+        for offset in 0..self.proxies.len() {
+            let index = (start + offset) % self.proxies.len();
+            let proxy = &self.proxies[index];
+            if !proxy.dead.load(Ordering::Relaxed) {
+                return Some((index, &proxy.client, proxy.insecure_client.as_ref()));
+            }
+        }
+        if !self.exhausted.swap(true, Ordering::Relaxed) {
+            eprintln!("{}", "[proxy] every proxy is dead, skipping the rest".red());
+        }
+        None
+    }
+
+    /**
+     * After a probe through the proxy failed, see whether the proxy itself
+     * still takes connections and drop it when it hasn't a few times in a row
+     */
+    pub async fn check(&self, index: usize, settings: &Settings) {
+        let proxy = &self.proxies[index];
+        let timeout = Duration::from_secs(settings.timeout as u64);
+        let up = matches!(
+            tokio::time::timeout(timeout, connect(&proxy.addr, settings)).await,
+            Ok(Ok(_))
+        );
+        if up {
+            proxy.strikes.store(0, Ordering::Relaxed);
+            return;
+        }
+        let strikes = proxy.strikes.fetch_add(1, Ordering::Relaxed) + 1;
+        if strikes >= MAX_STRIKES && !proxy.dead.swap(true, Ordering::Relaxed) && !settings.silent {
+            eprintln!(
+                "{}",
+                format!("[proxy] {} is not answering, dropping it", proxy.addr).yellow()
+            );
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use crate::stats::Stats;
+use colored::Colorize;
+use governor::Quota;
+use std::{
+    num::NonZeroU32,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+// back off once more than this share of requests time out, fail or get throttled
+const PRESSURE_THRESHOLD: f64 = 0.1;
+
+/**
+ * The quota of so many requests a second, configure turns a rate of 0 away
+ * before it gets here but it's taken as 1 rather than panicking
+ */
+pub fn per_second(rate: u32) -> Quota {
+    Quota::per_second(NonZeroU32::new(rate).unwrap_or(NonZeroU32::MIN))
+}
+
+/**
+ * The requests the targets pushed back on so far: the timeouts, the resets
+ * and refusals and the 429s and 503s. The ports answering in something
+ * other than http aren't pushing back
+ */
+fn pressure(stats: &Stats) -> u64 {
+    stats.errors().saturating_sub(stats.non_http()) + stats.throttled()
+}
+
+/**
+ * Adjusts the request rate between a floor and a ceiling based on
+ * how many requests are timing out, failing or being throttled
+ */
+#[derive(Debug)]
+pub struct AdaptiveRate {
+    current: AtomicU32,
+    min: u32,
+    max: u32,
+}
+
+impl AdaptiveRate {
+    pub fn new(rate: u32, min: u32, max: u32) -> Self {
+        let min = min.max(1);
+        let max = max.max(min);
+        AdaptiveRate {
+            current: AtomicU32::new(rate.clamp(min, max)),
+            min,
+            max,
+        }
+    }
+
+    /**
+     * The rate the jobs should currently be sent at
+     */
+    pub fn current(&self) -> u32 {
+        self.current.load(Ordering::Relaxed)
+    }
+
+    /**
+     * Every second halve the rate when the targets are pushing back,
+     * otherwise creep back up towards the ceiling
+     */
+    pub async fn run(self: Arc<Self>, stats: Arc<Stats>, silent: bool) {
+        let mut last_requests = stats.requests();
+        let mut last_pressure = pressure(&stats);
+        loop {
+            tokio::time::sleep(Duration::from_secs(1)).await;
+
+            let requests = stats.requests();
+            let pressure = pressure(&stats);
+            let window_requests = requests - last_requests;
+            let window_pressure = pressure - last_pressure;
+            last_requests = requests;
+            last_pressure = pressure;
+            if window_requests == 0 {
+                continue;
+            }
+
+            let current = self.current();
+            let ratio = window_pressure as f64 / window_requests as f64;
+            let next = if ratio > PRESSURE_THRESHOLD {
+                (current / 2).max(self.min)
+            } else {
+                current.saturating_add((current / 10).max(1)).min(self.max)
+            };
+            if next == current {
+                continue;
+            }
+            self.current.store(next, Ordering::Relaxed);
+            if !silent && next < current {
+                eprintln!(
+                    "{}",
+                    format!(
+                        "[auto-rate] backing off to {} req/s ({:.0}% timed out, failed or throttled)",
+                        next,
+                        ratio * 100.0
+                    )
+                    .yellow()
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_the_failed_requests_as_pressure() {
+        let stats = Stats::default();
+        stats.record_error(true);
+        // a reset or a refusal
+        stats.record_error(false);
+        stats.record_throttled();
+        assert_eq!(pressure(&stats), 3);
+    }
+
+    #[test]
+    fn leaves_out_the_ports_that_dont_speak_http() {
+        let stats = Stats::default();
+        stats.record_error(false);
+        stats.record_non_http();
+        assert_eq!(pressure(&stats), 0);
+    }
+
+    #[test]
+    fn starts_within_the_floor_and_ceiling() {
+        assert_eq!(AdaptiveRate::new(500, 10, 200).current(), 200);
+        assert_eq!(AdaptiveRate::new(5, 10, 200).current(), 10);
+        assert_eq!(AdaptiveRate::new(0, 0, 0).current(), 1);
+    }
+}
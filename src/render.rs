@@ -0,0 +1,31 @@
+use headless_chrome::Browser;
+use std::time::Duration;
+
+// how long the scripts get to fill in the page once it has loaded
+const SETTLE: Duration = Duration::from_millis(1000);
+
+// how long a page gets to load unless the target has its own timeout
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/**
+ * Load the page in chrome and get the html of the dom once its scripts have
+ * run, None when the page couldn't be rendered
+ */
+pub async fn render_page(browser: &Browser, url: &str, timeout: Option<usize>) -> Option<String> {
+    let timeout = timeout.map_or(TIMEOUT, |timeout| Duration::from_secs(timeout as u64));
+    let browser = browser.clone();
+    let url = url.to_string();
+    tokio::task::spawn_blocking(move || {
+        let tab = browser.new_tab().ok()?;
+        tab.set_default_timeout(timeout);
+        let html = (|| {
+            tab.navigate_to(&url).ok()?.wait_until_navigated().ok()?;
+            std::thread::sleep(SETTLE);
+            tab.get_content().ok()
+        })();
+        let _ = tab.close(true);
+        html
+    })
+    .await
+    .ok()?
+}
@@ -0,0 +1,79 @@
+use crate::{input::Target, rate, Settings};
+use colored::Colorize;
+use futures::StreamExt;
+use governor::RateLimiter;
+use std::time::Duration;
+use trust_dns_resolver::{proto::rr::RData, system_conf, TokioAsyncResolver};
+
+/**
+ * Build a resolver from the system configuration using the dns timeout and retries
+ */
+pub fn build_resolver(settings: &Settings) -> Option<TokioAsyncResolver> {
+    let (config, mut opts) = system_conf::read_system_conf().unwrap_or_default();
+    opts.timeout = Duration::from_secs(settings.dns_timeout);
+    opts.attempts = settings.dns_retries + 1;
+    TokioAsyncResolver::tokio(config, opts).ok()
+}
+
+/**
+ * Resolve every target and print its addresses and cname chain without probing it
+ */
+pub async fn resolve_only(targets: Vec<Target>, settings: &Settings) {
+    let resolver = match build_resolver(settings) {
+        Some(resolver) => resolver,
+        None => {
+            eprintln!("{}", "could not set up the dns resolver".red());
+            return;
+        }
+    };
+    let lim = RateLimiter::direct(rate::per_second(settings.rate));
+
+    futures::stream::iter(targets)
+        .map(|target| {
+            let resolver = &resolver;
+            let lim = &lim;
+            async move {
+                // the pinned hosts are printed as they were given
+                if let Some(ips) = settings.static_hosts.get(&target.host) {
+                    let ips: Vec<String> = ips.iter().map(|ip| ip.to_string()).collect();
+                    return Some(format!("{} {}", target.host, ips.join(",")));
+                }
+                lim.until_ready().await;
+                resolve_host(resolver, &target.host).await
+            }
+        })
+        .buffer_unordered(settings.concurrency.max(1) as usize)
+        .for_each(|line| async move {
+            if let Some(line) = line {
+                println!("{}", line);
+            }
+        })
+        .await;
+}
+
+/**
+ * Resolve a host into a `host ip,ip [cname,cname]` line
+ */
+async fn resolve_host(resolver: &TokioAsyncResolver, host: &str) -> Option<String> {
+    let lookup = resolver.lookup_ip(host).await.ok()?;
+    let ips: Vec<String> = lookup.iter().map(|ip| ip.to_string()).collect();
+    if ips.is_empty() {
+        return None;
+    }
+
+    let cnames: Vec<String> = lookup
+        .as_lookup()
+        .records()
+        .iter()
+        .filter_map(|record| match record.data() {
+            Some(RData::CNAME(name)) => Some(name.to_utf8().trim_end_matches('.').to_string()),
+            _ => None,
+        })
+        .collect();
+
+    let mut line = format!("{} {}", host, ips.join(","));
+    if !cnames.is_empty() {
+        line.push_str(&format!(" {}", format!("[{}]", cnames.join(",")).cyan()));
+    }
+    Some(line)
+}
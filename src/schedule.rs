@@ -0,0 +1,243 @@
+use chrono::{DateTime, Datelike, Duration, Local, Timelike};
+
+/**
+ * A five field cron expression (minute hour day-of-month month day-of-week)
+ */
+#[derive(Clone, Debug)]
+pub struct Schedule {
+    minutes: Vec<bool>,
+    hours: Vec<bool>,
+    days: Vec<bool>,
+    months: Vec<bool>,
+    weekdays: Vec<bool>,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl Schedule {
+    /**
+     * Parse a cron expression such as `0 3 * * *` or `0,30 9-17 * * 1-5`
+     */
+    pub fn parse(expression: &str) -> Result<Schedule, String> {
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        if fields.len() != 5 {
+            return Err(format!(
+                "expected 5 fields (minute hour day month weekday), got {}",
+                fields.len()
+            ));
+        }
+
+        let mut weekdays = parse_field(fields[4], 0, 7)?;
+        // both 0 and 7 mean sunday
+        if weekdays[7] {
+            weekdays[0] = true;
+        }
+
+        let days = parse_field(fields[2], 1, 31)?;
+        Ok(Schedule {
+            minutes: parse_field(fields[0], 0, 59)?,
+            hours: parse_field(fields[1], 0, 23)?,
+            any_day: covers(&days, 1, 31),
+            days,
+            months: parse_field(fields[3], 1, 12)?,
+            // 7 is only another name for sunday
+            any_weekday: covers(&weekdays, 0, 6),
+            weekdays,
+        })
+    }
+
+    /**
+     * Find the next minute after the given time that matches the schedule
+     */
+    pub fn next_after(&self, after: DateTime<Local>) -> Option<DateTime<Local>> {
+        let mut time = after.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+
+        // every schedule matches at least once within a few years
+        for _ in 0..(4 * 366 * 24 * 60) {
+            if self.matches(&time) {
+                return Some(time);
+            }
+            time = time + Duration::minutes(1);
+        }
+        None
+    }
+
+    fn matches(&self, time: &DateTime<Local>) -> bool {
+        if !self.minutes[time.minute() as usize]
+            || !self.hours[time.hour() as usize]
+            || !self.months[time.month() as usize]
+        {
+            return false;
+        }
+
+        // like cron, a restricted day and weekday match when either one does
+        let day = self.days[time.day() as usize];
+        let weekday = self.weekdays[time.weekday().num_days_from_sunday() as usize];
+        match (self.any_day, self.any_weekday) {
+            (true, true) => true,
+            (true, false) => weekday,
+            (false, true) => day,
+            (false, false) => day || weekday,
+        }
+    }
+}
+
+/**
+ * Parse a single field made of `*`, `n`, `a-b` and `/step` items separated by commas
+ */
+fn parse_field(field: &str, min: usize, max: usize) -> Result<Vec<bool>, String> {
+    let mut values = vec![false; max + 1];
+    for item in field.split(',') {
+        let (range, step) = match item.split_once('/') {
+            Some((range, step)) => match step.parse::<usize>() {
+                Ok(step) if step > 0 => (range, step),
+                _ => return Err(format!("invalid step in '{}'", item)),
+            },
+            None => (item, 1),
+        };
+
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (parse_value(start, item)?, parse_value(end, item)?)
+        } else {
+            let value = parse_value(range, item)?;
+            // `5/10` means every 10 starting at 5
+            if step > 1 {
+                (value, max)
+            } else {
+                (value, value)
+            }
+        };
+
+        if start < min || end > max || start > end {
+            return Err(format!("'{}' is out of range {}-{}", item, min, max));
+        }
+        for value in (start..=end).step_by(step) {
+            values[value] = true;
+        }
+    }
+    Ok(values)
+}
+
+/**
+ * Whether a field matches every value of its range, however it was written
+ */
+fn covers(values: &[bool], min: usize, max: usize) -> bool {
+    values[min..=max].iter().all(|&value| value)
+}
+
+fn parse_value(value: &str, item: &str) -> Result<usize, String> {
+    value
+        .parse::<usize>()
+        .map_err(|_| format!("invalid value in '{}'", item))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Local> {
+        Local
+            .with_ymd_and_hms(year, month, day, hour, minute, 0)
+            .unwrap()
+    }
+
+    fn next(expression: &str, after: DateTime<Local>) -> DateTime<Local> {
+        Schedule::parse(expression)
+            .unwrap()
+            .next_after(after)
+            .unwrap()
+    }
+
+    #[test]
+    fn parses_the_items_of_a_field() {
+        let values = parse_field("1,5-7,*/20,50/5", 0, 59).unwrap();
+        let set = (0..=59).filter(|&value| values[value]).collect::<Vec<_>>();
+        assert_eq!(set, [0, 1, 5, 6, 7, 20, 40, 50, 55]);
+    }
+
+    #[test]
+    fn rejects_a_malformed_expression() {
+        for expression in [
+            "",
+            "* * * *",
+            "* * * * * *",
+            "60 * * * *",
+            "* 24 * * *",
+            "* * 0 * *",
+            "* * * 13 *",
+            "* * * * 8",
+            "*/0 * * * *",
+            "5-1 * * * *",
+            "a * * * *",
+            "1,,2 * * * *",
+            "-5 * * * *",
+        ] {
+            assert!(Schedule::parse(expression).is_err(), "{}", expression);
+        }
+    }
+
+    #[test]
+    fn takes_7_as_sunday() {
+        let schedule = Schedule::parse("0 0 * * 7").unwrap();
+        assert!(schedule.weekdays[0]);
+        assert!(!schedule.any_weekday);
+    }
+
+    #[test]
+    fn tells_a_restricted_day_from_the_full_range() {
+        assert!(Schedule::parse("* * 1-31 * *").unwrap().any_day);
+        assert!(Schedule::parse("* * */1 * 0-7").unwrap().any_weekday);
+        assert!(!Schedule::parse("* * */2 * *").unwrap().any_day);
+        assert!(!Schedule::parse("* * * * 1-6").unwrap().any_weekday);
+    }
+
+    #[test]
+    fn fires_on_the_next_minute() {
+        assert_eq!(
+            next("* * * * *", at(2026, 1, 14, 10, 7)),
+            at(2026, 1, 14, 10, 8)
+        );
+        assert_eq!(
+            next("0 3 * * *", at(2026, 1, 14, 10, 7)),
+            at(2026, 1, 15, 3, 0)
+        );
+        assert_eq!(
+            next("0 3 * * *", at(2026, 1, 14, 2, 59)),
+            at(2026, 1, 14, 3, 0)
+        );
+        assert_eq!(
+            next("30 12 1 2 *", at(2026, 1, 14, 10, 7)),
+            at(2026, 2, 1, 12, 30)
+        );
+    }
+
+    #[test]
+    fn fires_on_either_a_restricted_day_or_weekday() {
+        // the 14th of january 2026 is a wednesday, the 15th a thursday
+        assert_eq!(
+            next("0 0 20 * 4", at(2026, 1, 14, 10, 0)),
+            at(2026, 1, 15, 0, 0)
+        );
+        assert_eq!(
+            next("0 0 16 * 2", at(2026, 1, 14, 10, 0)),
+            at(2026, 1, 16, 0, 0)
+        );
+    }
+
+    #[test]
+    fn treats_the_full_day_range_as_any_day() {
+        // like `*`, so only the weekday restricts it: the next monday
+        assert_eq!(
+            next("0 0 1-31 * 1", at(2026, 1, 14, 10, 0)),
+            at(2026, 1, 19, 0, 0)
+        );
+        // a step restricts the days, so either one matches: the 15th
+        assert_eq!(
+            next("0 0 */2 * 1", at(2026, 1, 14, 10, 0)),
+            at(2026, 1, 15, 0, 0)
+        );
+    }
+}
@@ -0,0 +1,229 @@
+use crate::{connect::connect, Settings};
+use colored::Colorize;
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    net::IpAddr,
+    sync::Mutex,
+    time::Duration,
+};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+// the ports that almost always serve plain http
+const HTTP_PORTS: [u16; 12] = [
+    80, 81, 591, 3000, 5000, 8000, 8008, 8080, 8081, 8088, 8888, 9000,
+];
+
+// the ports that almost always serve https
+const HTTPS_PORTS: [u16; 9] = [443, 832, 981, 1311, 4443, 7443, 8443, 9443, 10443];
+
+// the schemes a port serving either is probed with
+pub const BOTH: [&str; 2] = ["https", "http"];
+
+// the cipher suites offered in the client hello, enough for any tls server to answer
+const CIPHER_SUITES: [u8; 20] = [
+    0xc0, 0x2b, 0xc0, 0x2f, 0xc0, 0x2c, 0xc0, 0x30, 0xcc, 0xa9, 0xcc, 0xa8, 0x00, 0x9c, 0x00, 0x9d,
+    0x00, 0x2f, 0x00, 0x35,
+];
+
+/**
+ * Which schemes each port is probed with, the ports missing from the table
+ * are detected or probed with both
+ */
+#[derive(Clone, Debug)]
+pub struct PortSchemes {
+    schemes: HashMap<u16, &'static str>,
+}
+
+impl Default for PortSchemes {
+    fn default() -> Self {
+        let mut schemes = HashMap::new();
+        for port in HTTP_PORTS {
+            schemes.insert(port, "http");
+        }
+        for port in HTTPS_PORTS {
+            schemes.insert(port, "https");
+        }
+        PortSchemes { schemes }
+    }
+}
+
+impl PortSchemes {
+    /**
+     * Add the mappings such as 8443=https,8080=http on top of the defaults,
+     * `both` probes a port with both schemes
+     */
+    pub fn parse(mappings: &str) -> Result<PortSchemes, String> {
+        let mut port_schemes = PortSchemes::default();
+        for mapping in mappings.split(',').map(|mapping| mapping.trim()) {
+            if mapping.is_empty() {
+                continue;
+            }
+            let (port, scheme) = mapping
+                .split_once('=')
+                .ok_or_else(|| format!("{} isn't a port=scheme mapping", mapping))?;
+            let port = port
+                .trim()
+                .parse::<u16>()
+                .map_err(|_| format!("{} isn't a port", port))?;
+            match scheme.trim().to_lowercase().as_str() {
+                "http" => port_schemes.schemes.insert(port, "http"),
+                "https" => port_schemes.schemes.insert(port, "https"),
+                "both" => port_schemes.schemes.insert(port, "both"),
+                scheme => return Err(format!("{} isn't http, https or both", scheme)),
+            };
+        }
+        Ok(port_schemes)
+    }
+
+    /**
+     * The schemes to probe a port with, https first when it's both, None
+     * when the port isn't in the table
+     */
+    pub fn schemes(&self, port: &str) -> Option<Vec<&'static str>> {
+        let port = port.trim().parse::<u16>().ok()?;
+        match *self.schemes.get(&port)? {
+            "both" => Some(BOTH.to_vec()),
+            scheme => Some(vec![scheme]),
+        }
+    }
+}
+
+/**
+ * A tls 1.2 client hello, with the host as the sni name unless it's an address
+ */
+fn client_hello(host: &str) -> Vec<u8> {
+    let mut extensions = vec![];
+    if host
+        .trim_matches(|c| c == '[' || c == ']')
+        .parse::<IpAddr>()
+        .is_err()
+    {
+        let name = host.as_bytes();
+        extensions.extend_from_slice(&[0x00, 0x00]);
+        extensions.extend_from_slice(&(name.len() as u16 + 5).to_be_bytes());
+        extensions.extend_from_slice(&(name.len() as u16 + 3).to_be_bytes());
+        extensions.push(0x00);
+        extensions.extend_from_slice(&(name.len() as u16).to_be_bytes());
+        extensions.extend_from_slice(name);
+    }
+    // the groups, point formats and signature algorithms some servers insist on
+    extensions.extend_from_slice(&[
+        0x00, 0x0a, 0x00, 0x08, 0x00, 0x06, 0x00, 0x1d, 0x00, 0x17, 0x00, 0x18,
+    ]);
+    extensions.extend_from_slice(&[0x00, 0x0b, 0x00, 0x02, 0x01, 0x00]);
+    extensions.extend_from_slice(&[
+        0x00, 0x0d, 0x00, 0x0a, 0x00, 0x08, 0x04, 0x03, 0x08, 0x04, 0x04, 0x01, 0x05, 0x01,
+    ]);
+
+    let mut hello = vec![0x03, 0x03];
+    hello.extend_from_slice(&rand::random::<[u8; 32]>());
+    hello.push(0x00);
+    hello.extend_from_slice(&(CIPHER_SUITES.len() as u16).to_be_bytes());
+    hello.extend_from_slice(&CIPHER_SUITES);
+    hello.extend_from_slice(&[0x01, 0x00]);
+    hello.extend_from_slice(&(extensions.len() as u16).to_be_bytes());
+    hello.extend_from_slice(&extensions);
+
+    let mut handshake = vec![0x01];
+    handshake.extend_from_slice(&(hello.len() as u32).to_be_bytes()[1..]);
+    handshake.extend_from_slice(&hello);
+
+    let mut record = vec![0x16, 0x03, 0x01];
+    record.extend_from_slice(&(handshake.len() as u16).to_be_bytes());
+    record.extend_from_slice(&handshake);
+    record
+}
+
+/**
+ * Send a client hello to the port and see whether it answers in tls, a
+ * handshake or an alert record means https, anything else http, None when
+ * the port can't be connected to
+ */
+pub async fn detect_scheme(host: &str, port: &str, settings: &Settings) -> Option<&'static str> {
+    // plain http servers may sit waiting for the rest of the request
+    let timeout = Duration::from_secs((settings.timeout as u64).clamp(1, 5));
+    let addr = format!("{}:{}", host, port.trim());
+    let mut stream = tokio::time::timeout(timeout, connect(&addr, settings))
+        .await
+        .ok()?
+        .ok()?;
+    if stream.write_all(&client_hello(host)).await.is_err() {
+        return Some("http");
+    }
+    let mut first = [0u8; 1];
+    match tokio::time::timeout(timeout, stream.read(&mut first)).await {
+        Ok(Ok(1)) if first[0] == 0x16 || first[0] == 0x15 => Some("https"),
+        _ => Some("http"),
+    }
+}
+
+/**
+ * Whether a request failed on an answer that isn't http at all, the binary
+ * or the greeting of another protocol, rather than on the network
+ */
+pub fn is_non_http(err: &reqwest::Error) -> bool {
+    let mut source = err.source();
+    while let Some(inner) = source {
+        // how hyper words the responses it couldn't parse
+        let message = inner.to_string().to_lowercase();
+        if message.starts_with("invalid http ") || message == "message head is too large" {
+            return true;
+        }
+        source = inner.source();
+    }
+    false
+}
+
+/**
+ * The origins found not to speak http, so the rest of their requests are
+ * skipped instead of failing one by one
+ */
+pub struct NonHttp {
+    origins: Mutex<HashSet<String>>,
+    silent: bool,
+}
+
+/**
+ * The scheme, host and port of a url
+ */
+fn origin(url: &str) -> Option<String> {
+    Some(
+        reqwest::Url::parse(url)
+            .ok()?
+            .origin()
+            .ascii_serialization(),
+    )
+}
+
+impl NonHttp {
+    pub fn new(silent: bool) -> NonHttp {
+        NonHttp {
+            origins: Mutex::new(HashSet::new()),
+            silent,
+        }
+    }
+
+    /**
+     * Remember the origin of the url, reporting it the first time
+     */
+    pub fn record(&self, url: &str) {
+        let origin = match origin(url) {
+            Some(origin) => origin,
+            None => return,
+        };
+        if self.origins.lock().unwrap().insert(origin.clone()) && !self.silent {
+            eprintln!("{}", format!("[non-http] {}", origin).yellow());
+        }
+    }
+
+    /**
+     * Whether the origin of the url was already found not to speak http
+     */
+    pub fn is_known(&self, url: &str) -> bool {
+        match origin(url) {
+            Some(origin) => self.origins.lock().unwrap().contains(&origin),
+            None => false,
+        }
+    }
+}
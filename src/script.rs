@@ -0,0 +1,70 @@
+use crate::ProbeResult;
+use reqwest::header::HeaderMap;
+use rhai::{Array, Dynamic, Engine, Map, Scope, AST};
+
+/**
+ * A rhai script run over every result, it sees the `url`, `status`,
+ * `headers`, `body` and `title` of the response and can push extra fields
+ * onto `tags`, rewrite `title`, or evaluate to false to drop the result
+ */
+pub struct Script {
+    engine: Engine,
+    ast: AST,
+}
+
+impl Script {
+    /**
+     * Compile the script up front so mistakes show before the scan starts
+     */
+    pub fn load(path: &str) -> Result<Script, String> {
+        let mut engine = Engine::new();
+        // keep a runaway loop from stalling a worker forever
+        engine.set_max_operations(1_000_000);
+        let ast = engine
+            .compile_file(path.into())
+            .map_err(|err| format!("{}: {}", path, err))?;
+        Ok(Script { engine, ast })
+    }
+
+    /**
+     * Run the script over a result, returns false when it should be dropped
+     */
+    pub fn run(&self, result: &mut ProbeResult, headers: &HeaderMap, body: &str) -> bool {
+        let mut header_map = Map::new();
+        for (name, value) in headers {
+            header_map.insert(
+                name.as_str().into(),
+                value.to_str().unwrap_or_default().into(),
+            );
+        }
+
+        let mut scope = Scope::new();
+        scope.push("url", result.url.clone());
+        scope.push("status", result.status_code as i64);
+        scope.push("headers", header_map);
+        scope.push("body", body.to_string());
+        scope.push("title", result.title.clone().unwrap_or_default());
+        scope.push("tags", Array::new());
+
+        let verdict = match self
+            .engine
+            .eval_ast_with_scope::<Dynamic>(&mut scope, &self.ast)
+        {
+            Ok(verdict) => verdict,
+            Err(err) => {
+                eprintln!("script failed on {}: {}", result.url, err);
+                return true;
+            }
+        };
+
+        if let Some(tags) = scope.get_value::<Array>("tags") {
+            result.script_tags = tags.into_iter().map(|tag| tag.to_string()).collect();
+        }
+        if let Some(title) = scope.get_value::<String>("title") {
+            if !title.is_empty() {
+                result.title = Some(title);
+            }
+        }
+        verdict.as_bool().unwrap_or(true)
+    }
+}
@@ -0,0 +1,120 @@
+use crate::{fetch_method, patterns::Pattern, Job, Shared};
+use regex::Regex;
+use reqwest::{header::HeaderMap, Method, Url};
+
+/**
+ * What was found in the scripts a page loads
+ */
+#[derive(Clone, Debug, Default)]
+pub struct ScriptFindings {
+    pub endpoints: Vec<String>,
+    pub secrets: Vec<String>,
+}
+
+/**
+ * The scripts loaded by the page, resolved against its url
+ */
+pub fn script_urls(body: &str, base: &Url) -> Vec<Url> {
+    let mut urls: Vec<Url> = Vec::new();
+    let re = match Regex::new(r#"(?i)<script[^>]*\ssrc\s*=\s*["']([^"']+)["']"#) {
+        Ok(re) => re,
+        Err(_) => return urls,
+    };
+    for cap in re.captures_iter(body) {
+        let url = match base.join(&cap[1]) {
+            Ok(url) => url,
+            Err(_) => continue,
+        };
+        if (url.scheme() == "http" || url.scheme() == "https")
+            && url.path().to_lowercase().ends_with(".js")
+            && !urls.contains(&url)
+        {
+            urls.push(url);
+        }
+    }
+    urls
+}
+
+/**
+ * Pull the endpoint looking strings out of a script
+ */
+pub fn extract_endpoints(script: &str) -> Vec<String> {
+    let mut endpoints = Vec::new();
+    let re = match Regex::new(
+        r#"["'`]((?:https?://|/)[A-Za-z0-9_\-.~/%]*[A-Za-z0-9_\-~%]/?(?:\?[^"'`\s]*)?)["'`]"#,
+    ) {
+        Ok(re) => re,
+        Err(_) => return endpoints,
+    };
+    for cap in re.captures_iter(script) {
+        let endpoint = cap[1].to_string();
+        if !endpoints.contains(&endpoint) {
+            endpoints.push(endpoint);
+        }
+    }
+    endpoints
+}
+
+/**
+ * Read at most limit bytes of the response body
+ */
+async fn read_limited(mut resp: reqwest::Response, limit: usize) -> Option<String> {
+    let mut body = Vec::new();
+    while let Some(chunk) = resp.chunk().await.ok()? {
+        body.extend_from_slice(&chunk);
+        if body.len() >= limit {
+            body.truncate(limit);
+            break;
+        }
+    }
+    Some(String::from_utf8_lossy(&body).into_owned())
+}
+
+/**
+ * Fetch the scripts the page loads, bounded by the job's file count and
+ * size limits, and search them for endpoints and secrets
+ */
+pub async fn scrape_scripts(
+    client: &reqwest::Client,
+    job: &Job,
+    body: &str,
+    base: &Url,
+    secret_patterns: &[Pattern],
+    shared: &Shared,
+) -> ScriptFindings {
+    let mut findings = ScriptFindings::default();
+    let max_files = job.js_max_files;
+    let max_size = job.js_max_size * 1024;
+    for url in script_urls(body, base).into_iter().take(max_files) {
+        let resp = match fetch_method(
+            client,
+            Method::GET,
+            url.as_str(),
+            HeaderMap::new(),
+            job.timeout,
+            shared,
+        )
+        .await
+        {
+            Some(resp) if resp.status().is_success() => resp,
+            _ => continue,
+        };
+        let script = match read_limited(resp, max_size).await {
+            Some(script) => script,
+            None => continue,
+        };
+
+        for endpoint in extract_endpoints(&script) {
+            if !findings.endpoints.contains(&endpoint) {
+                findings.endpoints.push(endpoint);
+            }
+        }
+        for pattern in secret_patterns {
+            let name = pattern.name();
+            if pattern.regex.is_match(&script) && !findings.secrets.contains(&name) {
+                findings.secrets.push(name);
+            }
+        }
+    }
+    findings
+}
@@ -0,0 +1,50 @@
+use std::{
+    collections::HashSet,
+    fs::{File, OpenOptions},
+    io::{self, BufRead, BufReader, Write},
+    sync::Mutex,
+};
+
+/**
+ * The urls reported by every earlier run, kept in a file of one url per line
+ */
+pub struct SeenDb {
+    seen: Mutex<HashSet<String>>,
+    file: Mutex<File>,
+}
+
+impl SeenDb {
+    /**
+     * Load the urls already in the file, creating it when missing
+     */
+    pub fn open(path: &str) -> io::Result<SeenDb> {
+        let mut seen = HashSet::new();
+        if let Ok(existing) = File::open(path) {
+            for line in BufReader::new(existing).lines() {
+                let line = line?;
+                let url = line.trim();
+                if !url.is_empty() {
+                    seen.insert(url.to_string());
+                }
+            }
+        }
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(SeenDb {
+            seen: Mutex::new(seen),
+            file: Mutex::new(file),
+        })
+    }
+
+    /**
+     * Record a reported url, returns true when no earlier run reported it
+     */
+    pub fn record(&self, url: &str) -> io::Result<bool> {
+        let mut seen = self.seen.lock().unwrap();
+        if !seen.insert(url.to_string()) {
+            return Ok(false);
+        }
+        let mut file = self.file.lock().unwrap();
+        writeln!(file, "{}", url)?;
+        Ok(true)
+    }
+}
@@ -0,0 +1,157 @@
+use crate::convert::{read_records, write_csv, write_html};
+use colored::Colorize;
+use std::{io, time::Duration};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    task, time,
+};
+
+// the most of a request read, the pages take no body
+const MAX_REQUEST: usize = 16 * 1024;
+
+// how long a client gets to send its request before it's dropped
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+// the pause after a failed accept, so running out of file descriptors
+// doesn't spin
+const ACCEPT_BACKOFF: Duration = Duration::from_millis(100);
+
+/**
+ * A page to answer with, the status line, the content type and the body
+ */
+struct Page {
+    status: &'static str,
+    content_type: &'static str,
+    body: Vec<u8>,
+}
+
+impl Page {
+    fn text(status: &'static str, body: String) -> Page {
+        Page {
+            status,
+            content_type: "text/plain; charset=utf-8",
+            body: body.into_bytes(),
+        }
+    }
+}
+
+/**
+ * Read the results again for every request, so a file a scan is still
+ * writing shows what it has so far
+ */
+fn render(path: &str, target: &str) -> Page {
+    let records = match read_records(path) {
+        Ok(records) => records,
+        Err(err) => {
+            return Page::text(
+                "500 Internal Server Error",
+                format!("could not read {}: {}\n", path, err),
+            )
+        }
+    };
+    let mut body = vec![];
+    let content_type = match target.split('?').next().unwrap_or_default() {
+        "/" | "/index.html" => {
+            if let Err(err) = write_html(&records, &mut body) {
+                return Page::text("500 Internal Server Error", format!("{}\n", err));
+            }
+            "text/html; charset=utf-8"
+        }
+        "/results.csv" => {
+            if let Err(err) = write_csv(&records, &mut body) {
+                return Page::text("500 Internal Server Error", format!("{}\n", err));
+            }
+            "text/csv; charset=utf-8"
+        }
+        "/results.json" => {
+            body = serde_json::to_vec(&records).unwrap_or_default();
+            "application/json"
+        }
+        _ => return Page::text("404 Not Found", "not found\n".to_string()),
+    };
+    Page {
+        status: "200 OK",
+        content_type,
+        body,
+    }
+}
+
+/**
+ * Read the request up to the end of its headers, None when the client
+ * closed the connection or sent too much
+ */
+async fn read_head(stream: &mut TcpStream) -> io::Result<Option<Vec<u8>>> {
+    let mut request = vec![];
+    let mut buf = [0u8; 4096];
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+        let n = stream.read(&mut buf).await?;
+        if n == 0 || request.len() + n > MAX_REQUEST {
+            return Ok(None);
+        }
+        request.extend_from_slice(&buf[..n]);
+    }
+    Ok(Some(request))
+}
+
+/**
+ * Answer one request and close the connection, dropping a client that
+ * doesn't send its request in time
+ */
+async fn answer(mut stream: TcpStream, path: String) -> io::Result<()> {
+    let request = match time::timeout(REQUEST_TIMEOUT, read_head(&mut stream)).await {
+        Ok(Ok(Some(request))) => request,
+        Ok(Ok(None)) | Err(_) => return Ok(()),
+        Ok(Err(err)) => return Err(err),
+    };
+    let request = String::from_utf8_lossy(&request).to_string();
+    let mut request_line = request.lines().next().unwrap_or_default().split(' ');
+    let method = request_line.next().unwrap_or_default().to_string();
+    let target = request_line.next().unwrap_or_default().to_string();
+
+    let page = if method == "GET" || method == "HEAD" {
+        task::spawn_blocking(move || render(&path, &target))
+            .await
+            .unwrap_or_else(|err| Page::text("500 Internal Server Error", format!("{}\n", err)))
+    } else {
+        Page::text("405 Method Not Allowed", "only GET and HEAD\n".to_string())
+    };
+
+    let head = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        page.status,
+        page.content_type,
+        page.body.len()
+    );
+    stream.write_all(head.as_bytes()).await?;
+    if method != "HEAD" {
+        stream.write_all(&page.body).await?;
+    }
+    stream.shutdown().await
+}
+
+/**
+ * Serve the results file as the html report at /, and as csv and json at
+ * /results.csv and /results.json
+ */
+pub async fn serve(path: String, listen: String) -> io::Result<()> {
+    let listener = TcpListener::bind(&listen).await?;
+    eprintln!(
+        "{}",
+        format!(
+            "[serve] {} on http://{}/, /results.csv and /results.json",
+            path,
+            listener.local_addr()?
+        )
+        .white()
+    );
+    loop {
+        // a failed accept, such as when out of file descriptors, only loses that connection
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                task::spawn(answer(stream, path.clone()));
+            }
+            Err(_) => time::sleep(ACCEPT_BACKOFF).await,
+        }
+    }
+}
@@ -0,0 +1,258 @@
+use colored::Colorize;
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, Write},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+/**
+ * Counters shared between the workers
+ */
+#[derive(Debug, Default)]
+pub struct Stats {
+    requests: AtomicU64,
+    errors: AtomicU64,
+    timeouts: AtomicU64,
+    non_http: AtomicU64,
+    throttled: AtomicU64,
+    completed: AtomicU64,
+    // how many of the reported results had each status code
+    status_codes: Mutex<BTreeMap<u16, u64>>,
+}
+
+/**
+ * The counters at the end of the scan, as --stats-json writes them
+ */
+#[derive(Serialize)]
+pub struct Summary {
+    requests: u64,
+    errors: u64,
+    timeouts: u64,
+    non_http: u64,
+    throttled: u64,
+    completed: u64,
+    status_codes: BTreeMap<String, u64>,
+    status_classes: BTreeMap<String, u64>,
+}
+
+impl Stats {
+    /**
+     * Record a request being sent
+     */
+    pub fn record_request(&self) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /**
+     * Record a request that failed before a response came back
+     */
+    pub fn record_error(&self, timed_out: bool) {
+        self.errors.fetch_add(1, Ordering::Relaxed);
+        if timed_out {
+            self.timeouts.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /**
+     * Record a request answered in something other than http
+     */
+    pub fn record_non_http(&self) {
+        self.non_http.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /**
+     * Record a response telling us to slow down (429 or 503)
+     */
+    pub fn record_throttled(&self) {
+        self.throttled.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /**
+     * Record the status code of a reported result, the banners of the ports
+     * that don't speak http have none
+     */
+    pub fn record_status(&self, status_code: u16) {
+        if status_code == 0 {
+            return;
+        }
+        *self
+            .status_codes
+            .lock()
+            .unwrap()
+            .entry(status_code)
+            .or_default() += 1;
+    }
+
+    /**
+     * Record a target being fully probed
+     */
+    pub fn record_completed(&self) {
+        self.completed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn completed(&self) -> u64 {
+        self.completed.load(Ordering::Relaxed)
+    }
+
+    pub fn requests(&self) -> u64 {
+        self.requests.load(Ordering::Relaxed)
+    }
+
+    pub fn errors(&self) -> u64 {
+        self.errors.load(Ordering::Relaxed)
+    }
+
+    pub fn timeouts(&self) -> u64 {
+        self.timeouts.load(Ordering::Relaxed)
+    }
+
+    pub fn non_http(&self) -> u64 {
+        self.non_http.load(Ordering::Relaxed)
+    }
+
+    pub fn throttled(&self) -> u64 {
+        self.throttled.load(Ordering::Relaxed)
+    }
+
+    /**
+     * The count of each status code and of each class, 2xx, 3xx and so on
+     */
+    fn status_counts(&self) -> (BTreeMap<u16, u64>, BTreeMap<String, u64>) {
+        let status_codes = self.status_codes.lock().unwrap().clone();
+        let mut status_classes = BTreeMap::new();
+        for (status_code, count) in &status_codes {
+            *status_classes
+                .entry(format!("{}xx", status_code / 100))
+                .or_default() += count;
+        }
+        (status_codes, status_classes)
+    }
+
+    /**
+     * Print how many results had each status code, each class after its
+     * codes, to stderr
+     */
+    pub fn print_status_table(&self) {
+        let (status_codes, status_classes) = self.status_counts();
+        if status_codes.is_empty() {
+            return;
+        }
+        eprintln!(
+            "{}",
+            format!("{:<8}{:>10}", "status", "results").white().bold()
+        );
+        for (class, count) in &status_classes {
+            for (status_code, count) in &status_codes {
+                if format!("{}xx", status_code / 100) == *class {
+                    eprintln!("{}", format!("{:<8}{:>10}", status_code, count).white());
+                }
+            }
+            eprintln!("{}", format!("{:<8}{:>10}", class, count).white().bold());
+        }
+    }
+
+    /**
+     * The counters and the status codes of the results so far
+     */
+    pub fn summary(&self) -> Summary {
+        let (status_codes, status_classes) = self.status_counts();
+        Summary {
+            requests: self.requests(),
+            errors: self.errors(),
+            timeouts: self.timeouts(),
+            non_http: self.non_http(),
+            throttled: self.throttled(),
+            completed: self.completed.load(Ordering::Relaxed),
+            status_codes: status_codes
+                .into_iter()
+                .map(|(status_code, count)| (status_code.to_string(), count))
+                .collect(),
+            status_classes,
+        }
+    }
+
+    /**
+     * Write the counters and the status codes of the results as json
+     */
+    pub fn write_json(&self, path: &str) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(&self.summary())?;
+        fs::write(path, format!("{}\n", json))
+    }
+}
+
+/**
+ * Parse an interval such as 10s, 5m or 1h, a bare number is taken as seconds
+ */
+pub fn parse_interval(interval: &str) -> Option<Duration> {
+    let interval = interval.trim();
+    let (number, multiplier) = match interval.chars().last()? {
+        's' => (&interval[..interval.len() - 1], 1),
+        'm' => (&interval[..interval.len() - 1], 60),
+        'h' => (&interval[..interval.len() - 1], 3600),
+        _ => (interval, 1),
+    };
+    let number = number.trim().parse::<u64>().ok()?;
+    Some(Duration::from_secs(number * multiplier))
+}
+
+/**
+ * Format a number of seconds as hh:mm:ss
+ */
+fn format_eta(seconds: u64) -> String {
+    format!(
+        "{:02}:{:02}:{:02}",
+        seconds / 3600,
+        seconds % 3600 / 60,
+        seconds % 60
+    )
+}
+
+impl Stats {
+    /**
+     * Print the request rate, progress, errors and eta every interval to
+     * stderr, either rewriting one line or as a new line each time
+     */
+    pub async fn report_progress(self: Arc<Self>, total: usize, interval: Duration, live: bool) {
+        let started = Instant::now();
+        let mut last_requests = self.requests();
+        let mut last_tick = Instant::now();
+        loop {
+            tokio::time::sleep(interval).await;
+            let requests = self.requests();
+            let elapsed = last_tick.elapsed().as_secs_f64().max(0.001);
+            let rate = (requests - last_requests) as f64 / elapsed;
+            last_requests = requests;
+            last_tick = Instant::now();
+
+            let completed = self.completed.load(Ordering::Relaxed);
+            let per_second = completed as f64 / started.elapsed().as_secs_f64().max(0.001);
+            let eta = if completed > 0 {
+                format_eta(((total as u64).saturating_sub(completed) as f64 / per_second) as u64)
+            } else {
+                "--:--:--".to_string()
+            };
+            let line = format!(
+                "[stats] {:.0} req/s, {}/{} targets, {} errors ({} non-http), eta {}",
+                rate,
+                completed,
+                total,
+                self.errors(),
+                self.non_http(),
+                eta
+            );
+            if live {
+                // clear what's left of the previous line before rewriting it
+                eprint!("\r\x1b[2K{}", line.white());
+                let _ = io::stderr().flush();
+            } else {
+                eprintln!("{}", line.white());
+            }
+        }
+    }
+}
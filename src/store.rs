@@ -0,0 +1,184 @@
+use crate::{analyze_response, body_hash, dedup::UniqueFilter, output::Output, stats::Stats, Job};
+use crate::{hook::Hook, schemes::NonHttp, Settings, Shared};
+use colored::Colorize;
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue},
+    Url,
+};
+use std::{
+    fs, io,
+    path::Path,
+    sync::{atomic::AtomicBool, Arc},
+};
+
+/**
+ * Save a response as the url, the status, the headers and the body, in a
+ * file named after the url
+ */
+pub fn store_response(
+    dir: &str,
+    url: &str,
+    status_code: u16,
+    headers: &HeaderMap,
+    body: &str,
+) -> io::Result<()> {
+    let mut contents = format!("{}\nHTTP {}\n", url, status_code);
+    for (name, value) in headers {
+        contents.push_str(&format!(
+            "{}: {}\n",
+            name.as_str(),
+            value.to_str().unwrap_or_default()
+        ));
+    }
+    contents.push('\n');
+    contents.push_str(body);
+
+    let file_name = format!("{}.txt", &body_hash(url)[..16]);
+    fs::write(Path::new(dir).join(file_name), contents)
+}
+
+/**
+ * A response read back from the store
+ */
+struct StoredResponse {
+    url: String,
+    status_code: u16,
+    headers: HeaderMap,
+    body: String,
+}
+
+/**
+ * Parse a stored response, None when the file wasn't written by hrekt
+ */
+fn read_stored_response(path: &Path) -> Option<StoredResponse> {
+    let contents = fs::read_to_string(path).ok()?;
+    let (head, body) = contents.split_once("\n\n")?;
+    let mut lines = head.lines();
+    let url = lines.next()?.to_string();
+    let status_code = lines.next()?.strip_prefix("HTTP ")?.parse::<u16>().ok()?;
+    let mut headers = HeaderMap::new();
+    for line in lines {
+        let (name, value) = match line.split_once(": ") {
+            Some(header) => header,
+            None => continue,
+        };
+        if let (Ok(name), Ok(value)) = (
+            HeaderName::from_bytes(name.as_bytes()),
+            HeaderValue::from_str(value),
+        ) {
+            headers.append(name, value);
+        }
+    }
+    Some(StoredResponse {
+        url,
+        status_code,
+        headers,
+        body: body.to_string(),
+    })
+}
+
+/**
+ * Run the title extraction, regexes and filters again over the stored
+ * responses without sending any requests
+ */
+pub async fn analyze(dir: &str, job: &Job, settings: &Settings) -> io::Result<()> {
+    let shared = Shared {
+        stats: Arc::new(Stats::default()),
+        unique: if settings.unique {
+            Some(UniqueFilter::default())
+        } else {
+            None
+        },
+        validators: None,
+        crawl: None,
+        xml: None,
+        output: Output::create(
+            settings.encoding,
+            settings.output.as_deref(),
+            settings.output_compress,
+            settings.sort,
+            settings.group_by,
+        )?,
+        seen_db: None,
+        unresolved: None,
+        archive: None,
+        checkpoint: None,
+        results: None,
+        callbacks: None,
+        http: None,
+        total: 0,
+        discover: None,
+        dedupe: None,
+        one_per_ip: None,
+        delay: None,
+        host_throttle: None,
+        host_delay: None,
+        proxies: None,
+        tor_control: None,
+        memory: None,
+        hook: settings.on_match.as_deref().map(Hook::new),
+        plugins: None,
+        script: None,
+        fingerprints: None,
+        banners: None,
+        non_http: NonHttp::new(settings.silent),
+        stopping: AtomicBool::new(false),
+    };
+    if job.render && !settings.silent {
+        eprintln!(
+            "{}",
+            "[analyze] pages can only be rendered against the live hosts, using the stored html"
+                .yellow()
+        );
+    }
+    if job.display_tech && !settings.silent {
+        eprintln!(
+            "{}",
+            "[analyze] technologies can only be detected against the live hosts, skipping them"
+                .yellow()
+        );
+    }
+
+    let mut paths = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().map(|ext| ext == "txt").unwrap_or(false))
+        .collect::<Vec<_>>();
+    paths.sort();
+
+    for path in paths {
+        let stored = match read_stored_response(&path) {
+            Some(stored) => stored,
+            None => continue,
+        };
+        let final_url = match Url::parse(&stored.url) {
+            Ok(url) => url,
+            Err(_) => continue,
+        };
+        if let Some(result) = analyze_response(
+            job,
+            &shared,
+            &stored.url,
+            &final_url,
+            stored.status_code,
+            &stored.headers,
+            &stored.body,
+        )
+        .await
+        {
+            let held = match &shared.unique {
+                Some(unique) => unique.hold(&result, job.status_codes),
+                None => false,
+            };
+            if !held {
+                shared.output.write_result(&result, job.status_codes)?;
+            }
+        }
+    }
+    if let Some(unique) = &shared.unique {
+        for (result, status_codes) in unique.release() {
+            shared.output.write_result(&result, status_codes)?;
+        }
+    }
+    shared.output.finish()
+}
@@ -0,0 +1,113 @@
+use crate::output::Record;
+use colored::Colorize;
+use std::collections::{BTreeMap, HashMap};
+
+/**
+ * The tallies of a set of results, as hrekt report prints them
+ */
+#[derive(Default)]
+pub struct Summary {
+    results: usize,
+    status_codes: BTreeMap<u16, usize>,
+    errors: BTreeMap<String, usize>,
+    tech: HashMap<String, usize>,
+    servers: HashMap<String, usize>,
+    titles: HashMap<String, usize>,
+}
+
+/**
+ * The most common values first, the ties in alphabetical order
+ */
+fn top(counts: &HashMap<String, usize>, limit: usize) -> Vec<(&str, usize)> {
+    let mut top = counts
+        .iter()
+        .map(|(value, count)| (value.as_str(), *count))
+        .collect::<Vec<(&str, usize)>>();
+    top.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    top.truncate(limit);
+    top
+}
+
+/**
+ * Print a table of values and how many results had them
+ */
+fn print_table(heading: &str, rows: &[(String, usize)]) {
+    if rows.is_empty() {
+        return;
+    }
+    println!();
+    println!(
+        "{}",
+        format!("{:<48}{:>10}", heading, "results").white().bold()
+    );
+    for (value, count) in rows {
+        println!("{:<48}{:>10}", value, count);
+    }
+}
+
+impl Summary {
+    pub fn from_records(records: &[Record]) -> Summary {
+        let mut summary = Summary::default();
+        for record in records {
+            summary.results += 1;
+            if let Some(error) = &record.error {
+                *summary.errors.entry(error.kind.clone()).or_default() += 1;
+                continue;
+            }
+            *summary.status_codes.entry(record.status_code).or_default() += 1;
+            for tech in &record.tech {
+                *summary.tech.entry(tech.clone()).or_default() += 1;
+            }
+            if let Some(server) = &record.server {
+                *summary.servers.entry(server.clone()).or_default() += 1;
+            }
+            if let Some(title) = &record.title {
+                *summary.titles.entry(title.trim().to_string()).or_default() += 1;
+            }
+        }
+        summary
+    }
+
+    /**
+     * Print the status codes with their classes, the errors, and the most
+     * common technologies, servers and titles
+     */
+    pub fn print(&self, limit: usize) {
+        println!("{}", format!("{} results", self.results).white().bold());
+
+        // each class after its codes, as at the end of a scan
+        let mut classes: BTreeMap<u16, usize> = BTreeMap::new();
+        for (status_code, count) in &self.status_codes {
+            *classes.entry(status_code / 100).or_default() += count;
+        }
+        let mut status_rows = vec![];
+        for (class, class_count) in classes {
+            for (status_code, count) in &self.status_codes {
+                if status_code / 100 == class {
+                    status_rows.push((status_code.to_string(), *count));
+                }
+            }
+            status_rows.push((format!("{}xx", class), class_count));
+        }
+        print_table("status", &status_rows);
+
+        let error_rows = self
+            .errors
+            .iter()
+            .map(|(kind, count)| (kind.clone(), *count))
+            .collect::<Vec<(String, usize)>>();
+        print_table("error", &error_rows);
+
+        for (heading, counts) in [
+            ("tech", &self.tech),
+            ("server", &self.servers),
+            ("title", &self.titles),
+        ] {
+            let rows = top(counts, limit)
+                .into_iter()
+                .map(|(value, count)| (value.to_string(), count))
+                .collect::<Vec<(String, usize)>>();
+            print_table(heading, &rows);
+        }
+    }
+}
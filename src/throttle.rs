@@ -0,0 +1,87 @@
+use crate::delay::Delay;
+use governor::{clock::DefaultClock, state::keyed::DefaultKeyedStateStore, Quota, RateLimiter};
+use std::{collections::HashMap, num::NonZeroU32, sync::Mutex};
+use tokio::time::Instant;
+
+/**
+ * Caps how many requests a second each host gets, whatever the global rate,
+ * so a host with many ports or crawled paths isn't hammered
+ */
+pub struct HostThrottle {
+    limiter: RateLimiter<String, DefaultKeyedStateStore<String>, DefaultClock>,
+}
+
+impl HostThrottle {
+    /**
+     * None when the rate is 0, which leaves the hosts unthrottled
+     */
+    pub fn new(per_second: u32) -> Option<HostThrottle> {
+        let per_second = NonZeroU32::new(per_second)?;
+        Some(HostThrottle {
+            limiter: RateLimiter::keyed(Quota::per_second(per_second)),
+        })
+    }
+
+    /**
+     * Wait until the host can be sent another request
+     */
+    pub async fn wait(&self, host: &str) {
+        self.limiter.until_key_ready(&host.to_lowercase()).await;
+    }
+}
+
+/**
+ * The delay and jitter between the requests to each host when the hosts are
+ * throttled, the requests to other hosts don't wait on them
+ */
+pub struct HostDelay {
+    delay: Delay,
+    // the earliest each host may be sent its next request
+    ready: Mutex<HashMap<String, Instant>>,
+}
+
+impl HostDelay {
+    pub fn new(delay: Delay) -> HostDelay {
+        HostDelay {
+            delay,
+            ready: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /**
+     * Wait out the delay since the last request to the host, taking the slot
+     * before sleeping so the requests in flight to it line up one after another
+     */
+    pub async fn wait(&self, host: &str) {
+        let pause = self.delay.pause();
+        let slot = {
+            let mut ready = self.ready.lock().unwrap();
+            let now = Instant::now();
+            let slot = ready
+                .get(&host.to_lowercase())
+                .map_or(now, |&ready| ready.max(now));
+            ready.insert(host.to_lowercase(), slot + pause);
+            slot
+        };
+        tokio::time::sleep_until(slot).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn spaces_the_requests_to_each_host() {
+        let delay = Delay::parse(Some("200ms"), None).unwrap().unwrap();
+        let host_delay = HostDelay::new(delay);
+        let start = Instant::now();
+        host_delay.wait("a.example.com").await;
+        host_delay.wait("b.example.com").await;
+        // the first request to each host goes at once
+        assert!(start.elapsed() < Duration::from_millis(150));
+        host_delay.wait("A.example.com").await;
+        assert!(start.elapsed() >= Duration::from_millis(200));
+    }
+}
@@ -0,0 +1,124 @@
+use crate::{connect::connect, lookup_host, Settings};
+use openssl::ssl::{SslConnector, SslMethod, SslVerifyMode};
+use serde::{Deserialize, Serialize};
+use std::{
+    net::IpAddr,
+    time::{Duration, Instant},
+};
+
+/**
+ * How long each phase of a request took, the way curl -w reports them
+ */
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Timing {
+    // None when the host is an address or pinned with --resolve
+    pub dns: Option<Duration>,
+    // None when the handshake couldn't be timed
+    pub connect: Option<Duration>,
+    // None over plain http
+    pub tls: Option<Duration>,
+    pub ttfb: Duration,
+    pub total: Duration,
+}
+
+/**
+ * The lookup, connect and tls handshake of a url, timed on a connection of
+ * their own since the client doesn't tell them apart, nothing is sent over
+ * it but the handshake
+ */
+pub struct Handshake {
+    pub dns: Option<Duration>,
+    pub connect: Duration,
+    pub tls: Option<Duration>,
+}
+
+/**
+ * A duration in milliseconds, to the microsecond
+ */
+pub fn millis(duration: Duration) -> f64 {
+    (duration.as_secs_f64() * 1000.0 * 1000.0).round() / 1000.0
+}
+
+impl Timing {
+    /**
+     * The phases as a field of the text output
+     */
+    pub fn to_field(&self) -> String {
+        let mut phases = vec![];
+        if let Some(dns) = self.dns {
+            phases.push(format!("dns={}ms", millis(dns)));
+        }
+        if let Some(connect) = self.connect {
+            phases.push(format!("connect={}ms", millis(connect)));
+        }
+        if let Some(tls) = self.tls {
+            phases.push(format!("tls={}ms", millis(tls)));
+        }
+        phases.push(format!("ttfb={}ms", millis(self.ttfb)));
+        phases.push(format!("total={}ms", millis(self.total)));
+        format!("timing:{}", phases.join(","))
+    }
+}
+
+/**
+ * Time the lookup, the connect and the tls handshake of the url, None when
+ * any of them fails
+ */
+pub async fn handshake(url: &reqwest::Url, settings: &Settings) -> Option<Handshake> {
+    let host = url.host_str()?.trim_matches(|c| c == '[' || c == ']');
+    let port = url.port_or_known_default()?;
+    let timeout = Duration::from_secs(settings.timeout as u64);
+
+    let started = Instant::now();
+    let (addr, dns) = match host.parse::<IpAddr>() {
+        Ok(ip) => ((ip, port).into(), None),
+        Err(_) => {
+            let pinned = settings.static_hosts.get(host).is_some();
+            let addrs = lookup_host(&format!("{}:{}", host, port), settings).await?;
+            let dns = if pinned {
+                None
+            } else {
+                Some(started.elapsed())
+            };
+            (*addrs.first()?, dns)
+        }
+    };
+
+    let started = Instant::now();
+    let stream = tokio::time::timeout(timeout, connect(&addr.to_string(), settings))
+        .await
+        .ok()?
+        .ok()?;
+    let connect = started.elapsed();
+    if url.scheme() != "https" {
+        return Some(Handshake {
+            dns,
+            connect,
+            tls: None,
+        });
+    }
+
+    // only the handshake is timed, whatever certificate the server has
+    let stream = stream.into_std().ok()?;
+    stream.set_nonblocking(false).ok()?;
+    stream.set_read_timeout(Some(timeout)).ok()?;
+    stream.set_write_timeout(Some(timeout)).ok()?;
+    let host = host.to_string();
+    let tls = tokio::task::spawn_blocking(move || {
+        let mut connector = SslConnector::builder(SslMethod::tls()).ok()?;
+        connector.set_verify(SslVerifyMode::NONE);
+        let mut config = connector.build().configure().ok()?;
+        config.set_verify_hostname(false);
+        config.set_use_server_name_indication(host.parse::<IpAddr>().is_err());
+        let started = Instant::now();
+        config.connect(&host, stream).ok()?;
+        Some(started.elapsed())
+    })
+    .await
+    .ok()??;
+    Some(Handshake {
+        dns,
+        connect,
+        tls: Some(tls),
+    })
+}
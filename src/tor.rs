@@ -0,0 +1,108 @@
+use colored::Colorize;
+use std::{
+    io,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::TcpStream,
+};
+
+// how long the control port gets to answer
+const CONTROL_TIMEOUT: Duration = Duration::from_secs(10);
+
+/**
+ * Asks tor for a new circuit over its control port every so many requests,
+ * so the targets see the scan come from a different exit
+ */
+pub struct TorControl {
+    addr: String,
+    password: Option<String>,
+    every: usize,
+    requests: AtomicUsize,
+    silent: bool,
+}
+
+/**
+ * Quote a value as a control port string
+ */
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+impl TorControl {
+    /**
+     * None when the circuits aren't renewed
+     */
+    pub fn new(
+        addr: &str,
+        password: Option<String>,
+        every: usize,
+        silent: bool,
+    ) -> Option<Arc<TorControl>> {
+        if every == 0 {
+            return None;
+        }
+        Some(Arc::new(TorControl {
+            addr: addr.to_string(),
+            password,
+            every,
+            requests: AtomicUsize::new(0),
+            silent,
+        }))
+    }
+
+    /**
+     * Count a request and ask for a new circuit in the background when it's
+     * the one that makes up the batch
+     */
+    pub fn record_request(self: &Arc<Self>) {
+        let requests = self.requests.fetch_add(1, Ordering::Relaxed) + 1;
+        if requests % self.every != 0 {
+            return;
+        }
+        let control = self.clone();
+        tokio::spawn(async move {
+            let renewed = tokio::time::timeout(CONTROL_TIMEOUT, control.new_circuit()).await;
+            let err = match renewed {
+                Ok(Ok(())) => return,
+                Ok(Err(err)) => err,
+                Err(_) => io::Error::new(io::ErrorKind::TimedOut, "the control port timed out"),
+            };
+            if !control.silent {
+                eprintln!(
+                    "{}",
+                    format!("[tor] could not ask for a new circuit: {}", err).yellow()
+                );
+            }
+        });
+    }
+
+    /**
+     * Authenticate to the control port and send the NEWNYM signal
+     */
+    async fn new_circuit(&self) -> io::Result<()> {
+        let stream = TcpStream::connect(&self.addr).await?;
+        let (read, mut write) = stream.into_split();
+        let mut lines = BufReader::new(read).lines();
+
+        let password = quote(self.password.as_deref().unwrap_or_default());
+        for command in [
+            format!("AUTHENTICATE {}", password),
+            "SIGNAL NEWNYM".to_string(),
+        ] {
+            write
+                .write_all(format!("{}\r\n", command).as_bytes())
+                .await?;
+            let reply = lines.next_line().await?.unwrap_or_default();
+            if !reply.starts_with("250") {
+                return Err(io::Error::new(io::ErrorKind::Other, reply));
+            }
+        }
+        write.write_all(b"QUIT\r\n").await
+    }
+}
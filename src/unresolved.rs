@@ -0,0 +1,36 @@
+use std::{
+    collections::HashSet,
+    fs::File,
+    io::{self, Write},
+    sync::Mutex,
+};
+
+/**
+ * The input hosts that have no address at all, one per line, to prune the
+ * dead subdomains before the next run
+ */
+pub struct Unresolved {
+    seen: Mutex<HashSet<String>>,
+    file: Mutex<File>,
+}
+
+impl Unresolved {
+    pub fn create(path: &str) -> io::Result<Unresolved> {
+        Ok(Unresolved {
+            seen: Mutex::new(HashSet::new()),
+            file: Mutex::new(File::create(path)?),
+        })
+    }
+
+    /**
+     * Write a host that didn't resolve, once however many of its ports
+     * were tried
+     */
+    pub fn record(&self, host: &str) -> io::Result<()> {
+        if !self.seen.lock().unwrap().insert(host.to_string()) {
+            return Ok(());
+        }
+        let mut file = self.file.lock().unwrap();
+        writeln!(file, "{}", host)
+    }
+}
@@ -0,0 +1,187 @@
+use crate::output::Record;
+use chrono::{DateTime, Local};
+use reqwest::Url;
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+};
+
+/**
+ * Streams the results to a file laid out like nmap's xml output so the
+ * tools importing nmap scans can read it
+ */
+pub struct XmlReport {
+    out: Mutex<BufWriter<Box<dyn Write + Send>>>,
+    started: i64,
+    hosts: AtomicUsize,
+}
+
+/**
+ * Escape a value for use inside an attribute
+ */
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\n' => escaped.push_str("&#xa;"),
+            c if c.is_control() => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+impl XmlReport {
+    /**
+     * Create the file and write the opening of the report
+     */
+    pub fn create(path: &str) -> io::Result<XmlReport> {
+        XmlReport::to_writer(Box::new(File::create(path)?))
+    }
+
+    /**
+     * Write the opening of the report to any writer, such as stdout
+     */
+    pub fn to_writer(out: Box<dyn Write + Send>) -> io::Result<XmlReport> {
+        let mut out = BufWriter::new(out);
+        let now = Local::now();
+        let args = std::env::args().collect::<Vec<String>>().join(" ");
+        writeln!(out, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+        writeln!(out, "<!DOCTYPE nmaprun>")?;
+        writeln!(
+            out,
+            "<nmaprun scanner=\"hrekt\" args=\"{}\" start=\"{}\" startstr=\"{}\" version=\"{}\" xmloutputversion=\"1.05\">",
+            escape(&args),
+            now.timestamp(),
+            now.format("%a %b %e %H:%M:%S %Y"),
+            env!("CARGO_PKG_VERSION")
+        )?;
+        writeln!(
+            out,
+            "<scaninfo type=\"connect\" protocol=\"tcp\" numservices=\"0\" services=\"\"/>"
+        )?;
+        out.flush()?;
+        Ok(XmlReport {
+            out: Mutex::new(out),
+            started: now.timestamp(),
+            hosts: AtomicUsize::new(0),
+        })
+    }
+
+    /**
+     * Write a result as a host with its one open port, at the time it was
+     * probed
+     */
+    pub fn record(&self, result: &Record) -> io::Result<()> {
+        let url = match Url::parse(&result.url) {
+            Ok(url) => url,
+            Err(_) => return Ok(()),
+        };
+        let hostname = url.host_str().unwrap_or_default();
+        let port = url.port_or_known_default().unwrap_or_default();
+        let now = DateTime::parse_from_rfc3339(&result.timestamp)
+            .map(|timestamp| timestamp.timestamp())
+            .unwrap_or_else(|_| Local::now().timestamp());
+
+        let mut host = String::new();
+        host.push_str(&format!(
+            "<host starttime=\"{}\" endtime=\"{}\">\n",
+            now, now
+        ));
+        host.push_str("<status state=\"up\" reason=\"syn-ack\" reason_ttl=\"0\"/>\n");
+        let addr = result.ip.as_deref().unwrap_or(hostname);
+        let addrtype = if addr.contains(':') { "ipv6" } else { "ipv4" };
+        host.push_str(&format!(
+            "<address addr=\"{}\" addrtype=\"{}\"/>\n",
+            escape(addr),
+            addrtype
+        ));
+        host.push_str(&format!(
+            "<hostnames>\n<hostname name=\"{}\" type=\"user\"/>\n</hostnames>\n",
+            escape(hostname)
+        ));
+        host.push_str("<ports>");
+        host.push_str(&format!("<port protocol=\"tcp\" portid=\"{}\">", port));
+        host.push_str("<state state=\"open\" reason=\"syn-ack\" reason_ttl=\"0\"/>");
+        let tunnel = if url.scheme() == "https" {
+            " tunnel=\"ssl\""
+        } else {
+            ""
+        };
+        let product = match &result.server {
+            Some(server) => format!(" product=\"{}\"", escape(server)),
+            None => String::new(),
+        };
+        host.push_str(&format!(
+            "<service name=\"http\"{}{} method=\"probed\" conf=\"10\"/>",
+            product, tunnel
+        ));
+        if let Some(title) = &result.title {
+            host.push_str(&format!(
+                "<script id=\"http-title\" output=\"{}\"><elem key=\"title\">{}</elem></script>",
+                escape(title),
+                escape(title)
+            ));
+        }
+        if let Some(server) = &result.server {
+            host.push_str(&format!(
+                "<script id=\"http-server-header\" output=\"{}\"><elem>{}</elem></script>",
+                escape(server),
+                escape(server)
+            ));
+        }
+        if !result.tech.is_empty() {
+            host.push_str(&format!(
+                "<script id=\"http-tech\" output=\"{}\"/>",
+                escape(&result.tech.join(", "))
+            ));
+        }
+        host.push_str(&format!(
+            "<script id=\"http-status\" output=\"{}\"/>",
+            result.status_code
+        ));
+        if !result.tags.is_empty() {
+            host.push_str(&format!(
+                "<script id=\"hrekt-tags\" output=\"{}\"/>",
+                escape(&result.tags.join(","))
+            ));
+        }
+        host.push_str("</port>\n</ports>\n</host>");
+
+        let mut out = self.out.lock().unwrap();
+        writeln!(out, "{}", host)?;
+        self.hosts.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    }
+
+    /**
+     * Write the run statistics and close the report
+     */
+    pub fn finish(&self) -> io::Result<()> {
+        let now = Local::now();
+        let hosts = self.hosts.load(Ordering::SeqCst);
+        let mut out = self.out.lock().unwrap();
+        writeln!(
+            out,
+            "<runstats><finished time=\"{}\" timestr=\"{}\" elapsed=\"{}\" summary=\"hrekt done; {} urls up\" exit=\"success\"/><hosts up=\"{}\" down=\"0\" total=\"{}\"/>",
+            now.timestamp(),
+            now.format("%a %b %e %H:%M:%S %Y"),
+            now.timestamp() - self.started,
+            hosts,
+            hosts,
+            hosts
+        )?;
+        writeln!(out, "</runstats>")?;
+        writeln!(out, "</nmaprun>")?;
+        out.flush()
+    }
+}
@@ -0,0 +1,89 @@
+// each test file uses some of the helpers
+#![allow(dead_code)]
+
+use futures::StreamExt;
+use hrekt::{HttpClient, ProbeResult, Prober, ProberConfig};
+use hyper::{
+    service::{make_service_fn, service_fn},
+    Body, Request, Response, Server,
+};
+use std::{convert::Infallible, future::Future, net::SocketAddr, sync::Arc, time::Duration};
+
+// the longest a scan of the local servers may take before the test fails
+const SCAN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/**
+ * Serve the handler on a free port of the loopback, for as long as the test
+ * runtime lives
+ */
+pub fn serve<F, R>(handler: F) -> SocketAddr
+where
+    F: Fn(Request<Body>) -> R + Clone + Send + Sync + 'static,
+    R: Future<Output = Response<Body>> + Send + 'static,
+{
+    let make_service = make_service_fn(move |_| {
+        let handler = handler.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| {
+                let response = handler(request);
+                async move { Ok::<_, Infallible>(response.await) }
+            }))
+        }
+    });
+    let server = Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(make_service);
+    let addr = server.local_addr();
+    tokio::spawn(server);
+    addr
+}
+
+/**
+ * Probe the targets with the options and collect every result, quietly and
+ * with a few workers
+ */
+pub async fn probe(args: &[&str], targets: &[String]) -> Vec<ProbeResult> {
+    let prober = prober(args, targets).await;
+    collect(prober).await
+}
+
+/**
+ * Probe the targets like probe, sending the requests through the client
+ */
+pub async fn probe_with(
+    args: &[&str],
+    targets: &[String],
+    http: Arc<dyn HttpClient>,
+) -> Vec<ProbeResult> {
+    let prober = prober(args, targets).await.with_http_client(http);
+    collect(prober).await
+}
+
+/**
+ * Probe the targets with a built config
+ */
+pub async fn probe_config(config: &ProberConfig, targets: &[String]) -> Vec<ProbeResult> {
+    let prober = Prober::new(config, targets.to_vec())
+        .await
+        .expect("the config makes a scan");
+    collect(prober).await
+}
+
+/**
+ * The scan of the targets with the options, quietly and with a few workers
+ */
+pub async fn prober(args: &[&str], targets: &[String]) -> Prober {
+    let args = args
+        .iter()
+        .copied()
+        .chain(["--silent", "--concurrency", "4"]);
+    Prober::from_args(args, targets.to_vec())
+        .await
+        .expect("the options make a scan")
+}
+
+async fn collect(prober: Prober) -> Vec<ProbeResult> {
+    let mut results = tokio::time::timeout(SCAN_TIMEOUT, prober.run().collect::<Vec<_>>())
+        .await
+        .expect("the scan ended in time");
+    results.sort_by(|a, b| a.url.cmp(&b.url));
+    results
+}
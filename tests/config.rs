@@ -0,0 +1,96 @@
+use hrekt::{ConfigError, ProberConfig};
+
+#[test]
+fn builds_the_typed_options() {
+    let config = ProberConfig::builder()
+        .rate(100)
+        .ports([80, 8080])
+        .title(true)
+        .tag("staging")
+        .arg("--cache-info", None)
+        .build();
+
+    assert!(config.is_ok());
+}
+
+#[test]
+fn rejects_a_zero() {
+    let err = ProberConfig::builder().concurrency(0).build().unwrap_err();
+    assert!(matches!(err, ConfigError::Zero("concurrency")));
+
+    let err = ProberConfig::builder().rate(0).build().unwrap_err();
+    assert!(matches!(err, ConfigError::Zero("rate")));
+
+    let err = ProberConfig::builder().timeout(0).build().unwrap_err();
+    assert!(matches!(err, ConfigError::Zero("timeout")));
+}
+
+#[test]
+fn checks_the_typed_options_given_as_args() {
+    let err = ProberConfig::builder()
+        .arg("--rate", Some("abc"))
+        .build()
+        .unwrap_err();
+    assert!(
+        matches!(err, ConfigError::Invalid(option, value) if option == "--rate" && value == "abc")
+    );
+
+    let err = ProberConfig::builder()
+        .arg("-r", Some("0"))
+        .build()
+        .unwrap_err();
+    assert!(matches!(err, ConfigError::Zero("rate")));
+
+    let err = ProberConfig::builder()
+        .arg("--concurrency=-5", None)
+        .build()
+        .unwrap_err();
+    assert!(matches!(err, ConfigError::Invalid(..)));
+
+    let err = ProberConfig::builder()
+        .arg("--ports", Some("80,http"))
+        .build()
+        .unwrap_err();
+    assert!(matches!(err, ConfigError::Invalid(..)));
+}
+
+#[test]
+fn rejects_no_ports() {
+    let err = ProberConfig::builder()
+        .ports(Vec::new())
+        .build()
+        .unwrap_err();
+    assert!(matches!(err, ConfigError::NoPorts));
+
+    let err = ProberConfig::builder().ports([80, 0]).build().unwrap_err();
+    assert!(matches!(err, ConfigError::NoPorts));
+}
+
+#[test]
+fn rejects_an_invalid_body_regex() {
+    let err = ProberConfig::builder()
+        .body_regex("(unclosed")
+        .build()
+        .unwrap_err();
+    assert!(matches!(err, ConfigError::BodyRegex(value, _) if value == "(unclosed"));
+}
+
+#[test]
+fn rejects_what_the_command_line_rejects() {
+    let err = ProberConfig::builder()
+        .arg("--no-such-option", None)
+        .build()
+        .unwrap_err();
+    assert!(matches!(err, ConfigError::Options(_)));
+
+    // --same-host-only needs --follow-redirects, given with its method
+    assert!(ProberConfig::builder()
+        .arg("--same-host-only", None)
+        .build()
+        .is_err());
+    assert!(ProberConfig::builder()
+        .follow_redirects(true)
+        .arg("--same-host-only", None)
+        .build()
+        .is_ok());
+}
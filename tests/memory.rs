@@ -0,0 +1,100 @@
+mod common;
+
+use common::{probe, serve};
+use hrekt::Prober;
+use hyper::{header, Body, Response};
+use std::{
+    fs,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+/**
+ * The resident memory of the test, only known on linux
+ */
+fn resident_bytes() -> Option<u64> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    let kb = line.split_whitespace().nth(1)?.parse::<u64>().ok()?;
+    Some(kb * 1024)
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn refuses_a_limit_of_nothing() {
+    for size in ["0", "0G", "-1G"] {
+        let prober = Prober::from_args(["--max-memory", size], vec!["127.0.0.1".to_string()]);
+        assert!(prober.await.is_err(), "--max-memory {}", size);
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn reads_the_response_in_flight_over_the_limit() {
+    let addr = serve(|_| async { Response::new(Body::empty()) });
+    // a first scan so whatever the scans set up once is resident already
+    probe(&[], &[format!("http://{}/", addr)]).await;
+    let resident = match resident_bytes() {
+        Some(resident) => resident,
+        None => return,
+    };
+    let limit = resident / 4 * 5;
+
+    // the server takes the memory over the limit once the request is sent,
+    // and keeps it for as long as the test runs
+    let hog = Arc::new(Mutex::new(vec![]));
+    let addr = serve(move |_| {
+        let hog = hog.clone();
+        async move {
+            hog.lock().unwrap().push(vec![1u8; limit as usize / 2]);
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            Response::builder()
+                .header(header::CONTENT_TYPE, "text/html")
+                .body(Body::from("<title>Held</title>"))
+                .unwrap()
+        }
+    });
+    let results = probe(
+        &["--title", "--max-memory", &limit.to_string()],
+        &[format!("http://{}/", addr)],
+    )
+    .await;
+
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].title.as_deref(), Some("Held"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn cuts_the_body_short_over_the_limit() {
+    let addr = serve(|_| async { Response::new(Body::empty()) });
+    probe(&[], &[format!("http://{}/", addr)]).await;
+    let resident = match resident_bytes() {
+        Some(resident) => resident,
+        None => return,
+    };
+    let limit = resident / 4 * 5;
+
+    // 4MB of words, sent once the memory is over the limit
+    let hog = Arc::new(Mutex::new(vec![]));
+    let addr = serve(move |_| {
+        let hog = hog.clone();
+        async move {
+            hog.lock().unwrap().push(vec![1u8; limit as usize / 2]);
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            let body = format!("<title>Big</title> {}", "w ".repeat(2 << 20));
+            Response::builder()
+                .header(header::CONTENT_TYPE, "text/html")
+                .body(Body::from(body))
+                .unwrap()
+        }
+    });
+    let results = probe(
+        &["--title", "--wc", "--max-memory", &limit.to_string()],
+        &[format!("http://{}/", addr)],
+    )
+    .await;
+
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].title.as_deref(), Some("Big"));
+    // the words of the first megabyte only
+    let words = results[0].words.unwrap();
+    assert!(words < 1 << 19, "{} words read", words);
+}
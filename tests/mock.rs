@@ -0,0 +1,99 @@
+mod common;
+
+use common::probe_with;
+use futures::future::BoxFuture;
+use hrekt::{HttpClient, ProbeResult};
+use reqwest::{Request, Response, ResponseBuilderExt};
+use std::sync::{Arc, Mutex};
+
+/**
+ * Answers the requests of a scan without the network, keeping the path of
+ * each request it was sent
+ */
+#[derive(Default)]
+struct MockClient {
+    paths: Mutex<Vec<String>>,
+}
+
+impl HttpClient for MockClient {
+    fn execute(&self, request: Request) -> BoxFuture<'_, reqwest::Result<Response>> {
+        let url = request.url().clone();
+        self.paths.lock().unwrap().push(url.path().to_string());
+        let (status, body) = match url.path() {
+            "/" => (200, "<title>Mock home</title>"),
+            "/admin" => (403, "<title>Forbidden</title>"),
+            _ => (404, "not found"),
+        };
+        let response = http::Response::builder()
+            .status(status)
+            .url(url)
+            .header("Server", "mock/1.0")
+            .header("Content-Type", "text/html")
+            .body(body)
+            .unwrap();
+        Box::pin(async move { Ok(Response::from(response)) })
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn sends_the_requests_through_the_client() {
+    let mock = Arc::new(MockClient::default());
+    let targets = ["/", "/admin", "/missing"]
+        .iter()
+        .map(|path| format!("http://app.mock.test:80{}", path))
+        .collect::<Vec<String>>();
+    let results = probe_with(
+        // the host only has to resolve, the mock answers for it
+        &[
+            "--resolve",
+            "app.mock.test:127.0.0.1",
+            "--title",
+            "--server",
+        ],
+        &targets,
+        mock.clone(),
+    )
+    .await;
+
+    let mut paths = mock.paths.lock().unwrap().clone();
+    paths.sort();
+    paths.dedup();
+    assert_eq!(paths, ["/", "/admin", "/missing"]);
+
+    // the 404 of a path isn't reported
+    let found = results
+        .iter()
+        .map(|result| (result.url.as_str(), result.status_code))
+        .collect::<Vec<(&str, u16)>>();
+    assert_eq!(
+        found,
+        [
+            ("http://app.mock.test:80", 200),
+            ("http://app.mock.test:80/admin", 403),
+        ]
+    );
+    assert_eq!(results[0].title.as_deref(), Some("Mock home"));
+    assert_eq!(results[1].title.as_deref(), Some("Forbidden"));
+    assert!(results
+        .iter()
+        .all(|result| result.server.as_deref() == Some("mock/1.0")));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn round_trips_a_result_through_json() {
+    let mock = Arc::new(MockClient::default());
+    let results = probe_with(
+        &["--resolve", "app.mock.test:127.0.0.1", "--title"],
+        &["http://app.mock.test:80".to_string()],
+        mock,
+    )
+    .await;
+    assert_eq!(results.len(), 1);
+
+    let json = serde_json::to_string(&results[0]).unwrap();
+    let read: ProbeResult = serde_json::from_str(&json).unwrap();
+    assert_eq!(read.url, results[0].url);
+    assert_eq!(read.status_code, results[0].status_code);
+    assert_eq!(read.title, results[0].title);
+    assert_eq!(serde_json::to_string(&read).unwrap(), json);
+}
@@ -0,0 +1,442 @@
+mod common;
+
+use common::{probe, probe_config, prober, serve};
+use futures::StreamExt;
+use hrekt::{ErrorKind, ProberConfig};
+use hyper::{body::Bytes, header, Body, Response, StatusCode};
+use openssl::{
+    asn1::Asn1Time,
+    bn::BigNum,
+    hash::MessageDigest,
+    pkey::PKey,
+    rsa::Rsa,
+    ssl::{SslAcceptor, SslMethod},
+    x509::{X509NameBuilder, X509},
+};
+use std::{
+    io::{self, Read, Write},
+    net::{SocketAddr, TcpListener},
+    thread,
+    time::{Duration, Instant},
+};
+
+fn html(body: &'static str) -> Response<Body> {
+    Response::builder()
+        .header(header::CONTENT_TYPE, "text/html")
+        .body(Body::from(body))
+        .unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn reports_the_status_and_title() {
+    let addr = serve(|_| async { html("<html><title>Welcome</title></html>") });
+    let results = probe(&["--title"], &[format!("http://{}/", addr)]).await;
+
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].url, format!("http://{}", addr));
+    assert_eq!(results[0].status_code, 200);
+    assert_eq!(results[0].title.as_deref(), Some("Welcome"));
+    assert!(results[0].error.is_none());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn probes_with_the_typed_config() {
+    let addr = serve(|request| async move {
+        let mut response = html(match request.uri().path() {
+            "/admin" => "<title>Admin</title>",
+            _ => "<title>Home</title>",
+        });
+        response
+            .headers_mut()
+            .insert(header::SERVER, "test/1.0".parse().unwrap());
+        response
+    });
+    let config = ProberConfig::builder()
+        .concurrency(4)
+        .ports([addr.port()])
+        .path("/admin")
+        .title(true)
+        .server(true)
+        .arg("--silent", None)
+        .build()
+        .unwrap();
+    let results = probe_config(&config, &["127.0.0.1".to_string()]).await;
+
+    assert_eq!(results.len(), 1);
+    assert!(results[0].url.ends_with("/admin"));
+    assert_eq!(results[0].title.as_deref(), Some("Admin"));
+    assert_eq!(results[0].server.as_deref(), Some("test/1.0"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn takes_the_title_as_written() {
+    let addr = serve(|request| async move {
+        html(match request.uri().path() {
+            "/entities" => "<title>Caf&eacute; &amp; Bar</title>",
+            "/unicode" => "<title>日本語のページ ✓</title>",
+            "/empty-first" => "<title></title>\n<title>Second</title>",
+            "/multiline" => "<title>\n  Split\n  over   lines\n</title>",
+            "/uppercase" => "<TITLE lang=\"en\">Shouting</TITLE>",
+            _ => "",
+        })
+    });
+    let paths = [
+        "/empty-first",
+        "/entities",
+        "/multiline",
+        "/unicode",
+        "/uppercase",
+    ];
+    let targets = paths
+        .iter()
+        .map(|path| format!("http://{}{}", addr, path))
+        .collect::<Vec<String>>();
+    let results = probe(&["--title"], &targets).await;
+
+    let titles = results
+        .iter()
+        .map(|result| result.title.as_deref())
+        .collect::<Vec<Option<&str>>>();
+    assert_eq!(
+        titles,
+        [
+            // the first title that isn't empty
+            Some("Second"),
+            // the entities aren't decoded
+            Some("Caf&eacute; &amp; Bar"),
+            // the whitespace is collapsed
+            Some("Split over lines"),
+            Some("日本語のページ ✓"),
+            // the tags are matched in any case, with attributes
+            Some("Shouting"),
+        ]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn counts_the_duplicates_of_a_unique_body() {
+    let addr = serve(|request| async move {
+        html(match request.uri().path() {
+            "/other" => "<title>Other</title>",
+            _ => "<title>Same</title>",
+        })
+    });
+    let targets = ["/a", "/b", "/c", "/other"]
+        .iter()
+        .map(|path| format!("http://{}{}", addr, path))
+        .collect::<Vec<String>>();
+    let results = probe(&["--unique", "--title"], &targets).await;
+
+    let mut counts = results
+        .iter()
+        .map(|result| (result.title.as_deref(), result.duplicates))
+        .collect::<Vec<(Option<&str>, Option<usize>)>>();
+    counts.sort();
+    assert_eq!(counts, [(Some("Other"), Some(0)), (Some("Same"), Some(2))]);
+    let same = results
+        .iter()
+        .find(|result| result.title.as_deref() == Some("Same"))
+        .unwrap();
+    assert_eq!(same.record().duplicates, Some(2));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn ends_the_scan_when_the_stream_is_dropped() {
+    let addr = serve(|_| async { html("<title>Page</title>") });
+    let targets = (0..200)
+        .map(|page| format!("http://{}/{}", addr, page))
+        .collect::<Vec<String>>();
+    // the stats are written last, once the scan is over
+    let stats = std::env::temp_dir().join(format!("hrekt-dropped-{}.json", std::process::id()));
+    let _ = std::fs::remove_file(&stats);
+    let prober = prober(
+        &["--queue-size", "2", "--stats-json", stats.to_str().unwrap()],
+        &targets,
+    )
+    .await;
+
+    let mut results = Box::pin(prober.run());
+    assert!(results.next().await.is_some());
+    drop(results);
+
+    let start = Instant::now();
+    while !stats.exists() {
+        assert!(
+            start.elapsed() < Duration::from_secs(10),
+            "the scan kept running"
+        );
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    std::fs::remove_file(&stats).unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn reports_the_redirect_without_following_it() {
+    let addr = serve(|_| async {
+        Response::builder()
+            .status(StatusCode::FOUND)
+            .header(header::LOCATION, "/loop")
+            .body(Body::empty())
+            .unwrap()
+    });
+    let results = probe(&[], &[format!("http://{}/loop", addr)]).await;
+
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].status_code, 302);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn gives_up_on_a_redirect_loop() {
+    let addr = serve(|_| async {
+        Response::builder()
+            .status(StatusCode::FOUND)
+            .header(header::LOCATION, "/loop")
+            .body(Body::empty())
+            .unwrap()
+    });
+    let results = probe(
+        &["--follow-redirects", "--include-errors"],
+        &[format!("http://{}/loop", addr)],
+    )
+    .await;
+
+    assert_eq!(results.len(), 1);
+    let error = results[0].error.as_ref().expect("the loop is an error");
+    assert_eq!(error.kind, ErrorKind::TooManyRedirects);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn times_out_on_slow_headers() {
+    let addr = serve(|_| async {
+        tokio::time::sleep(Duration::from_secs(5)).await;
+        html("<title>Too late</title>")
+    });
+    let results = probe(
+        &["--timeout", "1", "--include-errors"],
+        &[format!("http://{}/", addr)],
+    )
+    .await;
+
+    assert_eq!(results.len(), 1);
+    let error = results[0].error.as_ref().expect("the timeout is an error");
+    assert_eq!(error.kind, ErrorKind::HttpTimeout);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn drops_a_body_that_stalls() {
+    let addr = serve(|_| async {
+        let chunks = futures::stream::unfold(0, |sent| async move {
+            if sent > 0 {
+                // the rest of the body never comes in time
+                tokio::time::sleep(Duration::from_secs(10)).await;
+            }
+            Some((
+                Ok::<_, io::Error>(Bytes::from_static(b"<title>Half</title>")),
+                sent + 1,
+            ))
+        });
+        Response::new(Body::wrap_stream(chunks))
+    });
+    let started = Instant::now();
+    let results = probe(
+        &["--timeout", "1", "--title"],
+        &[format!("http://{}/", addr)],
+    )
+    .await;
+
+    assert!(results.is_empty());
+    assert!(started.elapsed() < Duration::from_secs(10));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn reads_a_huge_body_whole() {
+    const SIZE: usize = 16 * 1024 * 1024;
+    let addr = serve(|_| async {
+        let mut body = b"<title>Huge</title>".to_vec();
+        body.resize(SIZE, b'a');
+        Response::new(Body::from(body))
+    });
+    let results = probe(
+        &["--title", "--content-length"],
+        &[format!("http://{}/", addr)],
+    )
+    .await;
+
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].title.as_deref(), Some("Huge"));
+    assert_eq!(results[0].content_length, Some(SIZE as u64));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn reports_a_body_that_stalls_as_an_error() {
+    let addr = serve(|_| async {
+        let chunks = futures::stream::unfold(0, |sent| async move {
+            if sent > 0 {
+                tokio::time::sleep(Duration::from_secs(10)).await;
+            }
+            Some((
+                Ok::<_, io::Error>(Bytes::from_static(b"<title>Half</title>")),
+                sent + 1,
+            ))
+        });
+        Response::new(Body::wrap_stream(chunks))
+    });
+    let results = probe(
+        &["--timeout", "1", "--include-errors"],
+        &[format!("http://{}/", addr)],
+    )
+    .await;
+
+    assert_eq!(results.len(), 1);
+    let error = results[0].error.as_ref().expect("the stall is an error");
+    assert_eq!(error.kind, ErrorKind::HttpTimeout);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn reports_a_digest_retry_that_fails() {
+    let addr = serve(|request| async move {
+        if request.headers().contains_key(header::AUTHORIZATION) {
+            // the answer to the credentials never comes in time
+            tokio::time::sleep(Duration::from_secs(10)).await;
+            return html("<title>Inside</title>");
+        }
+        Response::builder()
+            .status(StatusCode::UNAUTHORIZED)
+            .header(
+                header::WWW_AUTHENTICATE,
+                r#"Digest realm="test", nonce="abc", qop="auth""#,
+            )
+            .body(Body::empty())
+            .unwrap()
+    });
+    let results = probe(
+        &[
+            "--timeout",
+            "1",
+            "--include-errors",
+            "--digest-auth",
+            "admin:admin",
+        ],
+        &[format!("http://{}/", addr)],
+    )
+    .await;
+
+    assert_eq!(results.len(), 1);
+    let error = results[0].error.as_ref().expect("the retry is an error");
+    assert_eq!(error.kind, ErrorKind::HttpTimeout);
+}
+
+/**
+ * A server answering every connection with an ssh banner
+ */
+fn serve_ssh() -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            let _ = stream.write_all(b"SSH-2.0-OpenSSH_9.3\r\n");
+        }
+    });
+    addr
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn tells_a_port_that_doesnt_speak_http() {
+    let addr = serve_ssh();
+    let results = probe(&["--include-errors"], &[format!("http://{}/", addr)]).await;
+
+    assert_eq!(results.len(), 1);
+    let error = results[0].error.as_ref().expect("ssh isn't http");
+    assert_eq!(error.kind, ErrorKind::NonHttp);
+}
+
+/**
+ * A https server with a certificate signed by itself, answering every
+ * request with the same page
+ */
+fn serve_self_signed() -> SocketAddr {
+    let key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+    let mut name = X509NameBuilder::new().unwrap();
+    name.append_entry_by_text("CN", "localhost").unwrap();
+    let name = name.build();
+    let mut cert = X509::builder().unwrap();
+    cert.set_version(2).unwrap();
+    let serial = BigNum::from_u32(1).unwrap().to_asn1_integer().unwrap();
+    cert.set_serial_number(&serial).unwrap();
+    cert.set_subject_name(&name).unwrap();
+    cert.set_issuer_name(&name).unwrap();
+    cert.set_pubkey(&key).unwrap();
+    cert.set_not_before(&Asn1Time::days_from_now(0).unwrap())
+        .unwrap();
+    cert.set_not_after(&Asn1Time::days_from_now(30).unwrap())
+        .unwrap();
+    cert.sign(&key, MessageDigest::sha256()).unwrap();
+    let cert = cert.build();
+
+    let mut acceptor = SslAcceptor::mozilla_intermediate(SslMethod::tls()).unwrap();
+    acceptor.set_private_key(&key).unwrap();
+    acceptor.set_certificate(&cert).unwrap();
+    let acceptor = acceptor.build();
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let acceptor = acceptor.clone();
+            thread::spawn(move || {
+                // the clients verifying the certificate hang up in the handshake
+                let mut stream = match acceptor.accept(stream) {
+                    Ok(stream) => stream,
+                    Err(_) => return,
+                };
+                let mut request = vec![];
+                let mut buf = [0; 1024];
+                while !request.windows(4).any(|end| end == b"\r\n\r\n") {
+                    match stream.read(&mut buf) {
+                        Ok(0) | Err(_) => return,
+                        Ok(n) => request.extend_from_slice(&buf[..n]),
+                    }
+                }
+                let body = "<title>Self signed</title>";
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                let _ = stream.write_all(response.as_bytes());
+                let _ = stream.shutdown();
+            });
+        }
+    });
+    addr
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn accepts_a_bad_certificate_by_default() {
+    let addr = serve_self_signed();
+    let results = probe(&["--title"], &[format!("https://{}/", addr)]).await;
+
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].status_code, 200);
+    assert_eq!(results[0].title.as_deref(), Some("Self signed"));
+    assert!(results[0].tls.is_none());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn reports_a_bad_certificate_when_verifying() {
+    let addr = serve_self_signed();
+    let results = probe(
+        &["--title", "--verify-tls"],
+        &[format!("https://{}/", addr)],
+    )
+    .await;
+
+    // the page is fetched anyway, the certificate error is the finding
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].status_code, 200);
+    let tls = results[0]
+        .tls
+        .as_ref()
+        .expect("the certificate is reported");
+    assert!(tls.error.is_some());
+}